@@ -0,0 +1,10 @@
+#version 430
+layout(std140) uniform;
+
+in vec4 couleur_sommet;
+
+out vec4 couleur;
+
+void main() {
+    couleur = couleur_sommet;
+}
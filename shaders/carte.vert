@@ -0,0 +1,14 @@
+#version 430
+layout(std140) uniform;
+
+uniform vec2 dimensions_ecran; // En pixels
+
+in vec2 position; // En pixels, à partir du coin inférieur gauche
+in vec4 couleur;
+
+out vec4 couleur_sommet;
+
+void main() {
+    gl_Position = vec4(position / dimensions_ecran * 2.0 - 1.0, 0.0, 1.0);
+    couleur_sommet = couleur;
+}
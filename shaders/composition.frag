@@ -0,0 +1,55 @@
+#version 430
+
+uniform sampler2D scene;
+uniform sampler2D profondeur;
+uniform sampler2D bloom;
+
+uniform float plan_proche;
+uniform float plan_loin;
+
+uniform bool tonalite_active;
+uniform float exposition;
+uniform bool gamma_actif;
+uniform bool bloom_actif;
+uniform float intensite_bloom;
+uniform bool brouillard_actif;
+uniform float densite_brouillard;
+uniform vec3 couleur_brouillard;
+uniform bool vignette_active;
+uniform float intensite_vignette;
+
+in vec2 coord_ecran;
+out vec4 couleur;
+
+void main() {
+    vec3 valeur = texture(scene, coord_ecran).rgb;
+
+    if (brouillard_actif) {
+        // On retrouve la distance à partir de la profondeur non linéaire
+        float z = texture(profondeur, coord_ecran).r * 2.0 - 1.0;
+        float distance = 2.0 * plan_proche * plan_loin / (plan_loin + plan_proche - z * (plan_loin - plan_proche));
+
+        float brouillard = 1.0 - exp(-densite_brouillard * distance);
+        valeur = mix(valeur, couleur_brouillard, brouillard);
+    }
+
+    if (bloom_actif) {
+        valeur += intensite_bloom * texture(bloom, coord_ecran).rgb;
+    }
+
+    if (tonalite_active) {
+        valeur = vec3(1.0) - exp(-valeur * exposition);
+    }
+    valeur = clamp(valeur, 0.0, 1.0);
+
+    if (vignette_active) {
+        float distance_centre = length(coord_ecran - 0.5) * 1.41421;
+        valeur *= 1.0 - intensite_vignette * smoothstep(0.4, 1.0, distance_centre);
+    }
+
+    if (gamma_actif) {
+        valeur = pow(valeur, vec3(1.0 / 2.2));
+    }
+
+    couleur = vec4(valeur, 1.0);
+}
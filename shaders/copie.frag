@@ -0,0 +1,10 @@
+#version 430
+
+uniform sampler2D image;
+
+in vec2 coord_ecran;
+out vec4 couleur;
+
+void main() {
+    couleur = vec4(texture(image, coord_ecran).rgb, 1.0);
+}
@@ -0,0 +1,9 @@
+#version 430
+
+out vec2 coord_ecran;
+
+void main() {
+    vec2 coin = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    coord_ecran = coin;
+    gl_Position = vec4(coin * 2.0 - 1.0, 0.0, 1.0);
+}
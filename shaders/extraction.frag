@@ -0,0 +1,16 @@
+#version 430
+
+uniform sampler2D scene;
+uniform float seuil;
+
+in vec2 coord_ecran;
+out vec4 couleur;
+
+void main() {
+    vec3 valeur = texture(scene, coord_ecran).rgb;
+    float luminance = dot(valeur, vec3(0.2126, 0.7152, 0.0722));
+
+    // Transition douce pour éviter le scintillement autour du seuil
+    float facteur = smoothstep(seuil, seuil * 1.5, luminance);
+    couleur = vec4(valeur * facteur, 1.0);
+}
@@ -0,0 +1,20 @@
+#version 430
+
+uniform sampler2D image;
+uniform vec2 pas; // Distance entre deux échantillons, horizontale ou verticale
+
+in vec2 coord_ecran;
+out vec4 couleur;
+
+const float POIDS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec3 somme = texture(image, coord_ecran).rgb * POIDS[0];
+
+    for(int i=1; i<5; ++i) {
+        somme += texture(image, coord_ecran + pas * float(i)).rgb * POIDS[i];
+        somme += texture(image, coord_ecran - pas * float(i)).rgb * POIDS[i];
+    }
+
+    couleur = vec4(somme, 1.0);
+}
@@ -0,0 +1,59 @@
+#version 430
+
+// Anticrénelage approximatif (FXAA): les contours détectés par la luminance sont adoucis
+// le long de leur direction, sur l'image finale
+
+uniform sampler2D image;
+uniform vec2 taille_texel;
+
+in vec2 coord_ecran;
+out vec4 couleur;
+
+const float SEUIL_RELATIF = 0.125;
+const float SEUIL_MINIMAL = 1.0 / 32.0;
+const float REDUCTION = 1.0 / 8.0;
+const float REDUCTION_MINIMALE = 1.0 / 128.0;
+const float PORTEE = 8.0; // En texels
+
+float luminance(vec3 valeur) {
+    return dot(valeur, vec3(0.299, 0.587, 0.114));
+}
+
+vec3 lire(vec2 decalage) {
+    return texture(image, coord_ecran + decalage).rgb;
+}
+
+void main() {
+    vec3 centre = lire(vec2(0.0));
+
+    float l_centre = luminance(centre);
+    float l_no = luminance(lire(vec2(-1.0, 1.0) * taille_texel));
+    float l_ne = luminance(lire(vec2(1.0, 1.0) * taille_texel));
+    float l_so = luminance(lire(vec2(-1.0, -1.0) * taille_texel));
+    float l_se = luminance(lire(vec2(1.0, -1.0) * taille_texel));
+
+    float l_min = min(l_centre, min(min(l_no, l_ne), min(l_so, l_se)));
+    float l_max = max(l_centre, max(max(l_no, l_ne), max(l_so, l_se)));
+
+    // Pas de contour, le pixel est gardé tel quel
+    if (l_max - l_min < max(SEUIL_MINIMAL, l_max * SEUIL_RELATIF)) {
+        couleur = vec4(centre, 1.0);
+        return;
+    }
+
+    // Direction perpendiculaire au gradient, donc le long du contour
+    vec2 direction = vec2(
+        -((l_no + l_ne) - (l_so + l_se)),
+        (l_no + l_so) - (l_ne + l_se));
+
+    float reduction = max((l_no + l_ne + l_so + l_se) * 0.25 * REDUCTION, REDUCTION_MINIMALE);
+    float echelle = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduction);
+    direction = clamp(direction * echelle, vec2(-PORTEE), vec2(PORTEE)) * taille_texel;
+
+    vec3 proche = 0.5 * (lire(direction * (1.0 / 3.0 - 0.5)) + lire(direction * (2.0 / 3.0 - 0.5)));
+    vec3 large = 0.5 * proche + 0.25 * (lire(direction * -0.5) + lire(direction * 0.5));
+
+    // L'échantillonnage large peut dépasser le contour; on revient alors au plus proche
+    float l_large = luminance(large);
+    couleur = vec4((l_large < l_min || l_large > l_max) ? proche : large, 1.0);
+}
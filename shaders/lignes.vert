@@ -0,0 +1,14 @@
+#version 430
+layout(std140) uniform;
+
+uniform mat4 camera_perspective;
+
+in vec3 position;
+in vec4 couleur;
+
+out vec4 couleur_sommet; // Même sortie que carte.vert, pour utiliser carte.frag
+
+void main() {
+    gl_Position = camera_perspective * vec4(position, 1.0);
+    couleur_sommet = couleur;
+}
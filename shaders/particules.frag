@@ -0,0 +1,13 @@
+#version 430
+layout(std140) uniform;
+
+in vec2 coin_particule;
+in vec4 couleur_particule;
+
+out vec4 couleur;
+
+void main() {
+    // Disque aux bords adoucis
+    float attenuation = 1.0 - smoothstep(0.0, 1.0, length(coin_particule));
+    couleur = vec4(couleur_particule.rgb, couleur_particule.a * attenuation);
+}
@@ -0,0 +1,23 @@
+#version 430
+layout(std140) uniform;
+
+uniform mat4 camera_perspective;
+uniform vec3 droite_camera;
+uniform vec3 haut_camera;
+
+in vec3 centre;
+in vec2 coin;
+in float taille;
+in vec4 couleur;
+
+out vec2 coin_particule;
+out vec4 couleur_particule;
+
+void main() {
+    // Le carré fait toujours face à la caméra
+    vec3 position = centre + taille * (coin.x * droite_camera + coin.y * haut_camera);
+    gl_Position = camera_perspective * vec4(position, 1.0);
+
+    coin_particule = coin;
+    couleur_particule = couleur;
+}
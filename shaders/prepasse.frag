@@ -0,0 +1,6 @@
+#version 430
+layout(std140) uniform;
+
+void main() {
+
+}
@@ -0,0 +1,10 @@
+#version 430
+layout(std140) uniform;
+
+uniform mat4 camera_perspective;
+
+in vec3 position;
+
+void main() {
+    gl_Position = camera_perspective * vec4(position, 1.0);
+}
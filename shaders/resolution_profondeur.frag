@@ -0,0 +1,15 @@
+#version 430
+
+// Copie la profondeur du premier échantillon de chaque pixel, pour le brouillard
+// OpenGL ES 3.0 n'a pas de textures multiéchantillonnées, mais le post-traitement n'y est pas utilisé
+
+#ifdef GL_ES
+void main() {
+}
+#else
+uniform sampler2DMS profondeur;
+
+void main() {
+    gl_FragDepth = texelFetch(profondeur, ivec2(gl_FragCoord.xy), 0).r;
+}
+#endif
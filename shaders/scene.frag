@@ -0,0 +1,147 @@
+#version 430
+layout(std140) uniform;
+
+uniform sampler2DArray textures;
+uniform bool espace_lineaire; // Les images sont en sRGB; on les convertit si la sortie applique le gamma
+
+// Débogage, voir debogage::ModeRendu
+uniform int mode_debogage;
+uniform int lumiere_debogage; // Seule lumière qui éclaire la scène avec MODE_LUMIERE
+uniform float plan_proche;
+uniform float plan_loin;
+
+const int MODE_NORMAL = 0;
+const int MODE_FIL_DE_FER = 1;
+const int MODE_NORMALES = 2;
+const int MODE_LUMIERE = 3;
+const int MODE_PROFONDEUR = 4;
+
+in vec3 position_monde;
+in vec3 normal;
+in vec3 tangent;
+in vec2 coord_tex;
+flat in uint id_materiau;
+
+in vec3 directionRegard;
+
+const int NBR_LUMIERES = 8;
+const int NBR_MATERIAUX = 16;
+
+// Les données sont dans des shader storage buffers avec OpenGL 4.3, dans des blocs uniformes avec OpenGL 3.3
+// (BLOCS_UNIFORMES) et dans de simples tableaux uniformes avec OpenGL ES 3.0 (TABLEAUX_UNIFORMES)
+#if defined(TABLEAUX_UNIFORMES)
+#define DEBUT_BLOC(nom)
+#define FIN_BLOC
+#define MEMBRE uniform
+#elif defined(BLOCS_UNIFORMES)
+#define DEBUT_BLOC(nom) layout(std140) uniform nom {
+#define FIN_BLOC };
+#define MEMBRE
+#else
+#define DEBUT_BLOC(nom) layout(std140) buffer nom {
+#define FIN_BLOC };
+#define MEMBRE
+#endif
+
+DEBUT_BLOC(lumieres)
+    MEMBRE vec4 positions[NBR_LUMIERES];
+    MEMBRE vec4 couleurs[NBR_LUMIERES];
+FIN_BLOC
+
+// Une couche négative indique une carte absente
+DEBUT_BLOC(materiaux)
+    MEMBRE vec4 couches[NBR_MATERIAUX]; // couleur, normales, spéculaire
+    MEMBRE vec4 echelles[NBR_MATERIAUX];
+    MEMBRE vec4 emissions[NBR_MATERIAUX];
+    MEMBRE vec4 reflets[NBR_MATERIAUX]; // intensité spéculaire, rugosité
+FIN_BLOC
+
+out vec4 couleur;
+
+void main() {
+
+    if (mode_debogage == MODE_FIL_DE_FER) {
+        couleur = vec4(0.3, 1.0, 0.4, 1.0);
+        return;
+    }
+
+    if (mode_debogage == MODE_PROFONDEUR) {
+        // Distance retrouvée à partir de la profondeur non linéaire: blanc près de la caméra, noir au loin
+        const float ECHELLE_PROFONDEUR = 4.0; // Quelques cellules
+        float z = gl_FragCoord.z * 2.0 - 1.0;
+        float distance = 2.0 * plan_proche * plan_loin / (plan_loin + plan_proche - z * (plan_loin - plan_proche));
+        couleur = vec4(vec3(exp(-distance / ECHELLE_PROFONDEUR)), 1.0);
+        return;
+    }
+
+    vec4 luminosite = vec4(0.0, 0.0, 0.0, 1.0);
+
+    vec4 couches_materiau = couches[id_materiau];
+    vec2 echelle = echelles[id_materiau].xy;
+    vec2 coordonnees = coord_tex * echelle;
+
+    // Le niveau de mipmap est calculé à partir des coordonnées interpolées, car les pixels voisins
+    // qui servent aux dérivées ne lisent pas toujours les tampons (bords des triangles)
+    vec2 gradient_x = dFdx(coord_tex) * echelle;
+    vec2 gradient_y = dFdy(coord_tex) * echelle;
+
+    // La carte de normales est exprimée dans l'espace de la tangente
+    vec4 albedo = textureGrad(textures, vec3(coordonnees, couches_materiau.x), gradient_x, gradient_y);
+
+    vec3 normale = normalize(normal);
+    if (couches_materiau.y >= 0.0) {
+        vec3 tangente = normalize(tangent - dot(tangent, normale) * normale);
+        vec3 bitangente = cross(normale, tangente);
+        vec3 normale_carte = textureGrad(textures, vec3(coordonnees, couches_materiau.y), gradient_x, gradient_y).xyz * 2.0 - 1.0;
+        normale = normalize(mat3(tangente, bitangente, normale) * normale_carte);
+    }
+
+    // La carte de spécularité module les valeurs du matériau
+    vec2 reflet = reflets[id_materiau].xy;
+    if (couches_materiau.z >= 0.0) {
+        reflet *= textureGrad(textures, vec3(coordonnees, couches_materiau.z), gradient_x, gradient_y).xy;
+    }
+    float intensite_speculaire = reflet.x;
+    float exposant_speculaire = mix(48.0, 4.0, reflet.y);
+
+    if (mode_debogage == MODE_NORMALES) {
+        couleur = vec4(normale * 0.5 + 0.5, 1.0);
+        return;
+    }
+
+    const float INTENSITE_DIFFUSE = 0.45;
+    const float INTENSITE_AMBIANTE = 0.1;
+
+    for(int i=0; i<NBR_LUMIERES; ++i) {
+
+        if (mode_debogage == MODE_LUMIERE && i != lumiere_debogage) {
+            continue;
+        }
+
+        float distance_lumiere = distance(positions[i].xyz, position_monde);
+        vec3 direction_lumiere = normalize(position_monde - positions[i].xyz);
+
+        vec3 direction_reflexion = reflect(-direction_lumiere, normale);
+
+        float lumiere_speculaire = intensite_speculaire * pow(max(dot(directionRegard, direction_reflexion), 0.0), exposant_speculaire);
+
+        float lumiere_diffuse = INTENSITE_DIFFUSE * max(dot(normale, direction_lumiere), 0.0);
+
+        const float FACTEUR_DIMINUTION = 10.0;
+        float diminution = FACTEUR_DIMINUTION * (distance_lumiere + distance_lumiere * distance_lumiere) + 1.0;
+        vec4 luminosite2 = (lumiere_speculaire + lumiere_diffuse + INTENSITE_AMBIANTE) * couleurs[i] / diminution;
+
+        // Le rendu est en HDR: les contributions s'additionnent sans saturer
+        luminosite.xyz += luminosite2.xyz;
+    }
+
+    if (mode_debogage != MODE_LUMIERE) {
+        luminosite.xyz += emissions[id_materiau].xyz;
+    }
+
+    if (espace_lineaire) {
+        albedo.rgb = pow(albedo.rgb, vec3(2.2));
+    }
+
+    couleur = albedo * luminosite;
+}
@@ -0,0 +1,32 @@
+#version 430
+layout(std140) uniform;
+
+uniform mat4 camera_perspective;
+uniform vec3 direction_regard;
+
+in vec3 position;
+in vec3 normale;
+in vec3 tangente;
+in vec2 coordonnees_texture;
+in uint materiau;
+
+out vec3 position_monde;
+out vec3 normal;
+out vec3 tangent;
+out vec2 coord_tex;
+flat out uint id_materiau;
+
+out vec3 directionRegard;
+
+void main() {
+    gl_Position = camera_perspective * vec4(position, 1.0);
+
+    position_monde = position;
+    normal = normale;
+    tangent = tangente;
+
+    coord_tex = coordonnees_texture;
+    id_materiau = materiau;
+
+    directionRegard = direction_regard;
+}
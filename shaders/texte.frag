@@ -0,0 +1,13 @@
+#version 430
+layout(std140) uniform;
+
+uniform sampler2D police; // Seule la couche alpha est utilisée
+
+in vec2 coordonnees_police;
+in vec4 couleur_texte;
+
+out vec4 couleur;
+
+void main() {
+    couleur = vec4(couleur_texte.rgb, couleur_texte.a * texture(police, coordonnees_police).a);
+}
@@ -0,0 +1,17 @@
+#version 430
+layout(std140) uniform;
+
+uniform vec2 dimensions_ecran; // En pixels
+
+in vec2 position; // En pixels, à partir du coin inférieur gauche
+in vec2 coordonnees; // Dans l'image de la police
+in vec4 couleur;
+
+out vec2 coordonnees_police;
+out vec4 couleur_texte;
+
+void main() {
+    gl_Position = vec4(position / dimensions_ecran * 2.0 - 1.0, 0.0, 1.0);
+    coordonnees_police = coordonnees;
+    couleur_texte = couleur;
+}
@@ -0,0 +1,41 @@
+extern crate rand;
+
+use self::rand::{Rng, SeedableRng};
+
+/*
+    Interface publique du module aleatoire
+
+    Générateur de nombres aléatoires partagé par tout le programme
+    Avec une graine fixée, le labyrinthe et les animations sont identiques d'une exécution à l'autre
+*/
+
+// Doit être appelée avant de créer le labyrinthe pour qu'il soit reproductible
+pub fn initialiser(graine: u64) {
+
+    GENERATEUR.with(|generateur| *generateur.borrow_mut() = rand::rngs::StdRng::seed_from_u64(graine));
+}
+
+// Entier entre 0 et limite exclusivement
+pub fn entier(limite: u32) -> u32 {
+
+    GENERATEUR.with(|generateur| generateur.borrow_mut().gen_range(0, limite))
+}
+
+// Nombre entre 0 et limite
+pub fn nombre(limite: f32) -> f32 {
+
+    GENERATEUR.with(|generateur| limite * generateur.borrow_mut().gen_range(0, 10000) as f32 / 10000.0)
+}
+
+
+
+
+
+/*
+    Partie privée du module aleatoire
+*/
+
+thread_local! {
+    static GENERATEUR: std::cell::RefCell<rand::rngs::StdRng> =
+        std::cell::RefCell::new(rand::rngs::StdRng::from_entropy());
+}
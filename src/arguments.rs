@@ -0,0 +1,159 @@
+/*
+    Interface publique du module arguments
+
+    Sert à lire les arguments de la ligne de commande
+
+    --render-to image.png          Dessine une seule image hors de l'écran, puis quitte
+    --flythrough dossier           Enregistre le survol du chemin vers la sortie image par image hors de l'écran, puis quitte
+    --fps nombre                   Images par seconde du survol enregistré
+    --camera x,y,z,lacet,tangage   Position et orientation de la caméra (angles en degrés)
+    --size largeurxhauteur         Dimensions de l'image
+    --config fichier.txt           Fichier de configuration à utiliser
+    --seed nombre                  Graine du labyrinthe et des animations, pour obtenir des images reproductibles
+    --gamepad-script fichier.txt   Remplace la manette par des valeurs écrites dans un fichier (voir manette::SourceScriptee)
+    --record fichier.txt           Enregistre les événements et la trajectoire de l'observateur (voir enregistrement)
+    --replay fichier.txt           Rejoue un enregistrement sans fenêtre, puis quitte; avec --record, l'enregistre à nouveau
+    --verify                       Avec --replay, échoue si la trajectoire diffère de celle enregistrée
+*/
+
+pub struct Arguments {
+
+    pub rendu_image: Option<String>, // Chemin de l'image à produire
+    pub survol: Option<String>, // Dossier des images du survol
+    pub images_par_seconde: f32,
+    pub camera: Option<Camera>,
+    pub dimensions: (u32, u32),
+    pub configuration: String,
+    pub graine: Option<u64>,
+    pub script_manette: Option<String>,
+    pub enregistrement: Option<String>, // Fichier à écrire
+    pub rejeu: Option<String>, // Fichier à rejouer
+    pub verifier: bool,
+}
+
+impl Arguments {
+
+    // Affiche l'erreur et quitte si les arguments sont invalides
+    pub fn lire() -> Arguments {
+
+        match Arguments::analyser(std::env::args().skip(1)) {
+
+            Ok(arguments) => arguments,
+            Err(erreur) => {
+
+                println!("Erreur dans les arguments: {}", erreur);
+                println!("Utilisation: labyrinthe [--render-to image.png] [--flythrough dossier] [--fps nombre] [--camera x,y,z,lacet,tangage] [--size largeurxhauteur] [--config fichier.txt] [--seed nombre] [--gamepad-script fichier.txt] [--record fichier.txt] [--replay fichier.txt [--verify]]");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    pub fn analyser<I: Iterator<Item = String>>(mut arguments: I) -> Result<Arguments, String> {
+
+        let mut resultat = Arguments {
+            rendu_image: None,
+            survol: None,
+            images_par_seconde: 30.0,
+            camera: None,
+            dimensions: (1280, 720),
+            configuration: "configuration.txt".to_string(),
+            graine: None,
+            script_manette: None,
+            enregistrement: None,
+            rejeu: None,
+            verifier: false,
+        };
+
+        while let Some(argument) = arguments.next() {
+
+            let mut valeur = || arguments.next().ok_or(format!("{} requiert une valeur", argument));
+
+            match argument.as_str() {
+
+                "--render-to" => resultat.rendu_image = Some(valeur()?),
+                "--flythrough" => resultat.survol = Some(valeur()?),
+                "--fps" => resultat.images_par_seconde = lire_images_par_seconde(&valeur()?)?,
+                "--camera" => resultat.camera = Some(lire_camera(&valeur()?)?),
+                "--size" => resultat.dimensions = lire_dimensions(&valeur()?)?,
+                "--config" => resultat.configuration = valeur()?,
+                "--seed" => resultat.graine = Some(lire_graine(&valeur()?)?),
+                "--gamepad-script" => resultat.script_manette = Some(valeur()?),
+                "--record" => resultat.enregistrement = Some(valeur()?),
+                "--replay" => resultat.rejeu = Some(valeur()?),
+                "--verify" => resultat.verifier = true,
+
+                _ => return Err(format!("l'argument «{}» est inconnu", argument)),
+            }
+        }
+
+        if resultat.verifier && resultat.rejeu.is_none() {
+            return Err("--verify requiert --replay".to_string());
+        }
+
+        Ok(resultat)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Camera {
+
+    pub position: [f32; 3],
+    pub lacet: f32, // Rotation gauche-droite, en radians
+    pub tangage: f32, // Rotation bas-haut, en radians
+}
+
+
+
+
+
+/*
+    Partie privée du module arguments
+*/
+
+// Format: x,y,z,lacet,tangage
+fn lire_camera(valeur: &str) -> Result<Camera, String> {
+
+    let nombres = valeur.split(',')
+        .map(|nombre| nombre.trim().parse::<f32>())
+        .collect::<Result<std::vec::Vec<f32>, _>>()
+        .map_err(|_| format!("«{}» contient une valeur qui n'est pas un nombre", valeur))?;
+
+    if nombres.len() != 5 {
+        return Err(format!("«{}» n'a pas la forme x,y,z,lacet,tangage", valeur));
+    }
+
+    Ok(Camera {
+        position: [nombres[0], nombres[1], nombres[2]],
+        lacet: nombres[3].to_radians(),
+        tangage: nombres[4].to_radians(),
+    })
+}
+
+fn lire_graine(valeur: &str) -> Result<u64, String> {
+
+    valeur.parse::<u64>().map_err(|_| format!("«{}» n'est pas une graine valide (entier positif)", valeur))
+}
+
+fn lire_images_par_seconde(valeur: &str) -> Result<f32, String> {
+
+    match valeur.parse::<f32>() {
+        Ok(images) if images > 0.0 => Ok(images),
+        _ => Err(format!("«{}» n'est pas un nombre d'images par seconde valide", valeur)),
+    }
+}
+
+// Format: largeurxhauteur
+fn lire_dimensions(valeur: &str) -> Result<(u32, u32), String> {
+
+    let erreur = || format!("«{}» n'a pas la forme largeurxhauteur", valeur);
+
+    let mut morceaux = valeur.splitn(2, 'x');
+    let largeur = morceaux.next().and_then(|largeur| largeur.parse::<u32>().ok()).ok_or_else(erreur)?;
+    let hauteur = morceaux.next().and_then(|hauteur| hauteur.parse::<u32>().ok()).ok_or_else(erreur)?;
+
+    if largeur == 0 || hauteur == 0 {
+        return Err(erreur());
+    }
+
+    Ok((largeur, hauteur))
+}
@@ -0,0 +1,108 @@
+/*
+    Interface publique du module blocs_uniformes
+
+    Remplace implement_uniform_block! de glium, qui déréférence un pointeur nul pour calculer
+    la position des champs; les compilateurs récents arrêtent alors le programme en mode debug
+
+    Utilisation: implementer_bloc_uniforme!(Structure, champ1, champ2, ...);
+
+    Les champs doivent être des tableaux de vec4 ([[f32; 4]; N]); la macro ajoute aussi visiter_tableaux,
+    qui envoie chaque élément comme un uniform séparé («champ[i]») quand les blocs ne sont pas disponibles
+*/
+
+macro_rules! implementer_bloc_uniforme {
+
+    ($structure:ident, $($champ:ident),+) => (
+
+        impl ::glium::uniforms::UniformBlock for $structure {
+
+            fn matches(disposition: &::glium::program::BlockLayout, decalage_base: usize)
+                -> Result<(), ::glium::uniforms::LayoutMismatchError>
+            {
+                use glium::program::BlockLayout;
+                use glium::uniforms::LayoutMismatchError;
+
+                let membres = match disposition {
+
+                    BlockLayout::Struct { members } => members,
+                    _ => return Err(LayoutMismatchError::LayoutMismatch {
+                        expected: disposition.clone(),
+                        obtained: <Self as ::glium::uniforms::UniformBlock>::build_layout(decalage_base),
+                    }),
+                };
+
+                // Chaque membre du shader doit exister dans la structure
+                for (nom, _) in membres.iter() {
+
+                    if $(nom != stringify!($champ) &&)+ true {
+                        return Err(LayoutMismatchError::MissingField { name: nom.clone() });
+                    }
+                }
+
+                // Et chaque champ de la structure doit correspondre au membre du shader
+                $(
+                    let membre = membres.iter().find(|(nom, _)| nom == stringify!($champ));
+
+                    let disposition_champ = match membre {
+                        Some((_, disposition_champ)) => disposition_champ,
+                        None => return Err(LayoutMismatchError::MissingField { name: stringify!($champ).to_owned() }),
+                    };
+
+                    ::blocs_uniformes::verifier_champ(
+                        |structure: &$structure| &structure.$champ,
+                        disposition_champ,
+                        decalage_base + ::std::mem::offset_of!($structure, $champ))
+                        .map_err(|erreur| LayoutMismatchError::MemberMismatch {
+                            member: stringify!($champ).to_owned(),
+                            err: Box::new(erreur),
+                        })?;
+                )+
+
+                Ok(())
+            }
+
+            fn build_layout(decalage_base: usize) -> ::glium::program::BlockLayout {
+
+                ::glium::program::BlockLayout::Struct {
+                    members: vec![
+                        $(
+                            (
+                                stringify!($champ).to_owned(),
+                                ::blocs_uniformes::disposition_champ(
+                                    |structure: &$structure| &structure.$champ,
+                                    decalage_base + ::std::mem::offset_of!($structure, $champ)),
+                            ),
+                        )+
+                    ],
+                }
+            }
+        }
+
+        impl $structure {
+
+            pub fn visiter_tableaux<'a, F: FnMut(&str, ::glium::uniforms::UniformValue<'a>)>(&self, visiteur: &mut F) {
+                $(
+                    for (i, valeur) in self.$champ.iter().enumerate() {
+                        visiteur(&format!("{}[{}]", stringify!($champ), i), ::glium::uniforms::UniformValue::Vec4(*valeur));
+                    }
+                )+
+            }
+        }
+    );
+}
+
+// La fonction d'accès ne sert qu'à déduire le type du champ
+pub fn verifier_champ<S, C: glium::uniforms::UniformBlock>(
+    _acces: fn(&S) -> &C,
+    disposition: &glium::program::BlockLayout,
+    decalage: usize) -> Result<(), glium::uniforms::LayoutMismatchError>
+{
+    C::matches(disposition, decalage)
+}
+
+pub fn disposition_champ<S, C: glium::uniforms::UniformBlock>(
+    _acces: fn(&S) -> &C,
+    decalage: usize) -> glium::program::BlockLayout
+{
+    C::build_layout(decalage)
+}
@@ -0,0 +1,229 @@
+extern crate nalgebra_glm as glm;
+
+use collision;
+use configuration;
+use labyrinthe;
+use observateur;
+
+/*
+    Interface publique du module camera
+
+    Sert à choisir le point de vue transmis à ecran::Vue::changer_camera
+    À la première personne, la caméra est aux yeux de l'observateur; les autres modes le regardent de l'extérieur:
+    tout le labyrinthe vu de dessus, l'observateur suivi de derrière, ou une orbite autour d'une cellule
+
+    Derrière l'observateur et en orbite, la caméra tourne avec son regard et s'arrête devant les murs;
+    l'observateur y est alors dessiné (voir donnees::DonneesOpenGL::ajouter_silhouette)
+    En changeant de mode, la caméra glisse de l'ancien point de vue vers le nouveau
+    Vu de dessus, le brouillard est retiré peu à peu, sinon il cacherait tout le labyrinthe
+*/
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ModeCamera {
+
+    PremierePersonne,
+    DeDessus, // Tout le labyrinthe
+    TroisiemePersonne,
+    Orbite, // Autour de la cellule où était l'observateur en choisissant ce mode
+}
+
+impl ModeCamera {
+
+    pub fn nom(self) -> &'static str {
+
+        match self {
+            ModeCamera::PremierePersonne => "première personne",
+            ModeCamera::DeDessus => "vue de dessus",
+            ModeCamera::TroisiemePersonne => "troisième personne",
+            ModeCamera::Orbite => "orbite",
+        }
+    }
+
+    pub fn suivant(self) -> ModeCamera {
+
+        match self {
+            ModeCamera::PremierePersonne => ModeCamera::DeDessus,
+            ModeCamera::DeDessus => ModeCamera::TroisiemePersonne,
+            ModeCamera::TroisiemePersonne => ModeCamera::Orbite,
+            ModeCamera::Orbite => ModeCamera::PremierePersonne,
+        }
+    }
+}
+
+pub struct Camera {
+
+    pub mode: ModeCamera,
+
+    position: glm::Vec3,
+    direction: glm::Vec3,
+    brouillard: f32, // Voir ecran::Vue::attenuer_brouillard
+
+    depart: (glm::Vec3, glm::Vec3, f32), // Position, direction et brouillard au changement de mode
+    transition: f32, // Entre 0 et 1; à 1, la caméra est au point de vue du mode
+    centre_orbite: glm::Vec3,
+}
+
+impl Camera {
+
+    pub fn new() -> Camera {
+
+        Camera {
+
+            mode: ModeCamera::PremierePersonne,
+
+            position: glm::Vec3::new(0.0, 0.0, 0.0),
+            direction: glm::Vec3::new(0.0, 0.0, 1.0),
+            brouillard: 1.0,
+
+            depart: (glm::Vec3::new(0.0, 0.0, 0.0), glm::Vec3::new(0.0, 0.0, 1.0), 1.0),
+            transition: 1.0, // Aucune transition à la première image
+            centre_orbite: glm::Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    // La transition part du point de vue actuel, même au milieu d'une autre transition
+    pub fn changer_mode(&mut self, mode: ModeCamera, observateur: &observateur::Observateur, labyrinthe: &labyrinthe::Labyrinthe) {
+
+        self.mode = mode;
+        self.depart = (self.position, self.direction, self.brouillard);
+        self.transition = 0.0;
+
+        // L'orbite reste autour de la même cellule, même si l'observateur s'en éloigne
+        let (x, z) = labyrinthe.coordonnees_grille(&observateur.position);
+        let sol = labyrinthe.position_monde(x.floor() + 0.5, z.floor() + 0.5);
+        let hauteur = labyrinthe.hauteur_plafond(&sol) - labyrinthe.hauteur_sol(&sol);
+
+        self.centre_orbite = sol + glm::Vec3::new(0.0, hauteur / 2.0, 0.0);
+    }
+
+    // yeux: position de la caméra à la première personne, voir observateur::Observateur::position_camera
+    // duree: depuis la dernière image
+    pub fn mettre_a_jour(&mut self,
+                    yeux: &glm::Vec3,
+                    observateur: &observateur::Observateur,
+                    labyrinthe: &labyrinthe::Labyrinthe,
+                    parametres: &configuration::ParametresCamera,
+                    duree: f32)
+    {
+        const DUREE_TRANSITION: f32 = 0.6;
+
+        let (position, direction) = match self.mode {
+            ModeCamera::PremierePersonne => (*yeux, *observateur.direction()),
+            ModeCamera::DeDessus => vue_de_dessus(labyrinthe, parametres),
+            ModeCamera::TroisiemePersonne => troisieme_personne(yeux, observateur, labyrinthe),
+            ModeCamera::Orbite => orbite(&self.centre_orbite, observateur, labyrinthe),
+        };
+
+        self.transition = (self.transition + duree / DUREE_TRANSITION).min(1.0);
+
+        // Départ et arrivée en douceur
+        let t = self.transition;
+        let proportion = t * t * (3.0 - 2.0 * t);
+
+        let brouillard = if self.mode == ModeCamera::DeDessus { 0.0 } else { 1.0 };
+
+        self.position = glm::lerp(&self.depart.0, &position, proportion);
+        self.brouillard = self.depart.2 + (brouillard - self.depart.2) * proportion;
+
+        // Entre deux directions opposées, la moyenne est nulle; on prend alors directement la nouvelle
+        let melange = glm::lerp(&self.depart.1, &direction, proportion);
+        self.direction = if melange.norm() > 1e-3 { melange.normalize() } else { direction };
+    }
+
+    pub fn position(&self) -> &glm::Vec3 {
+        &self.position
+    }
+
+    pub fn direction(&self) -> &glm::Vec3 {
+        &self.direction
+    }
+
+    pub fn brouillard(&self) -> f32 {
+        self.brouillard
+    }
+
+    // Derrière l'observateur et en orbite, il doit être dessiné pour qu'on le voie
+    pub fn montre_observateur(&self) -> bool {
+        self.mode == ModeCamera::TroisiemePersonne || self.mode == ModeCamera::Orbite
+    }
+}
+
+
+
+
+
+/*
+    Partie privée du module camera
+*/
+
+// Au-dessus du centre, assez haut pour voir tout le labyrinthe; le plafond n'est pas dessiné vu de dessus
+fn vue_de_dessus(labyrinthe: &labyrinthe::Labyrinthe, parametres: &configuration::ParametresCamera) -> (glm::Vec3, glm::Vec3) {
+
+    const MARGE: f32 = 1.1;
+
+    let (longueur, largeur) = labyrinthe.dimensions();
+    let coin = labyrinthe.position_monde(0.0, 0.0);
+    let autre_coin = labyrinthe.position_monde(longueur as f32, largeur as f32);
+
+    let centre = (coin + autre_coin) / 2.0;
+    let etendue = (autre_coin.x - coin.x).abs().max((autre_coin.z - coin.z).abs());
+
+    // Le champ de vision est vertical, et l'écran est généralement plus large que haut
+    let distance = etendue / 2.0 / (parametres.champ_vision.to_radians() / 2.0).tan() * MARGE;
+    let hauteur = labyrinthe.hauteur_plafond(&centre) + distance;
+
+    // Pas tout à fait à la verticale, qui ne permet pas de savoir où est la droite; l'axe des z pointe vers le haut de l'écran
+    let direction = glm::Vec3::new(0.0, -1.0, 0.001).normalize();
+
+    (glm::Vec3::new(centre.x, hauteur, centre.z), direction)
+}
+
+// Derrière et un peu au-dessus des yeux, en visant devant l'observateur
+fn troisieme_personne(yeux: &glm::Vec3, observateur: &observateur::Observateur, labyrinthe: &labyrinthe::Labyrinthe) -> (glm::Vec3, glm::Vec3) {
+
+    const DISTANCE: f32 = 2.0;
+    const HAUTEUR: f32 = 0.3;
+    const VISEE: f32 = 1.0; // Point visé, devant les yeux
+
+    let voulue = yeux - observateur.direction() * DISTANCE + glm::Vec3::new(0.0, HAUTEUR, 0.0);
+    let position = retenir(yeux, &voulue, labyrinthe);
+
+    let visee = yeux + observateur.direction() * VISEE;
+
+    (position, (visee - position).normalize())
+}
+
+fn orbite(centre: &glm::Vec3, observateur: &observateur::Observateur, labyrinthe: &labyrinthe::Labyrinthe) -> (glm::Vec3, glm::Vec3) {
+
+    const RAYON: f32 = 1.2;
+
+    let voulue = centre - observateur.direction() * RAYON;
+    let position = retenir(centre, &voulue, labyrinthe);
+
+    // Près du plafond ou du sol, la position est déplacée; on vise quand même le centre
+    let direction = if glm::distance(centre, &position) > 1e-3 { (centre - position).normalize() } else { *observateur.direction() };
+
+    (position, direction)
+}
+
+// Rapproche la position voulue de l'origine pour que la caméra reste devant les murs, le sol et le plafond
+fn retenir(origine: &glm::Vec3, voulue: &glm::Vec3, labyrinthe: &labyrinthe::Labyrinthe) -> glm::Vec3 {
+
+    const MARGE: f32 = 0.1; // Assez pour que le plan proche ne coupe pas les murs
+
+    let capsule = collision::Capsule {
+        rayon: MARGE,
+        bas: 0.0,
+        haut: 0.0,
+    };
+
+    // La caméra s'arrête au premier mur sur le chemin, sans glisser le long de celui-ci
+    let deplacement = voulue - origine;
+    let mut position = origine + deplacement * labyrinthe.proportion_libre(origine, &deplacement, &capsule);
+
+    let sol = labyrinthe.hauteur_sol(&position) + MARGE;
+    let plafond = labyrinthe.hauteur_plafond(&position) - MARGE;
+    position.y = position.y.max(sol).min(plafond);
+
+    position
+}
@@ -0,0 +1,32 @@
+extern crate image;
+
+/*
+    Interface publique du module capture
+
+    Sert à enregistrer des images dessinées par la vue
+*/
+
+pub const DOSSIER_CAPTURES: &str = "captures/";
+
+// Le format est déduit de l'extension du fichier
+pub fn enregistrer(image: &image::RgbaImage, chemin: &str) -> Result<(), String> {
+
+    if let Some(dossier) = std::path::Path::new(chemin).parent() {
+
+        if !dossier.as_os_str().is_empty() {
+            std::fs::create_dir_all(dossier).map_err(|erreur| format!("{}: {}", chemin, erreur))?;
+        }
+    }
+
+    image.save(chemin).map_err(|erreur| format!("{}: {}", chemin, erreur))
+}
+
+// Chemin unique pour une capture d'écran, selon la date
+pub fn chemin_capture() -> String {
+
+    let date = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    format!("{}capture_{}_{:03}.png", DOSSIER_CAPTURES, date.as_secs(), date.subsec_millis())
+}
@@ -0,0 +1,311 @@
+extern crate nalgebra_glm as glm;
+use glium::{Surface}; // Surface est un trait et doit être importé
+
+use configuration;
+use labyrinthe;
+use observateur;
+
+/*
+    Interface publique du module carte
+
+    Sert à dessiner le labyrinthe vu de dessus, par-dessus la scène: une minicarte dans le coin de l'écran,
+    ou la carte complète qui couvre l'écran
+
+    Seules les cellules déjà vues par l'observateur sont affichées (brouillard de guerre)
+    Sur la carte, l'axe des x va vers la droite et l'axe des z vers le haut, comme pour la caméra
+*/
+
+pub struct Carte {
+
+    longueur: u32, // Nombre de cellules en x
+    largeur: u32, // Nombre de cellules en z
+    sentiers: std::vec::Vec<bool>, // Indexées par z * longueur + x
+    explorees: std::vec::Vec<bool>,
+    torches: std::vec::Vec<(f32, f32)>, // Coordonnées dans la grille
+
+    // Observateur dans la grille
+    position: (f32, f32),
+    direction: (f32, f32),
+
+    pub plein_ecran: bool,
+    pub parametres: configuration::ParametresCarte,
+}
+
+impl Carte {
+
+    pub fn new(labyrinthe: &labyrinthe::Labyrinthe, parametres: &configuration::ParametresCarte) -> Carte {
+
+        let (longueur, largeur) = labyrinthe.dimensions();
+
+        let mut sentiers = std::vec::Vec::with_capacity((longueur * largeur) as usize);
+
+        for z in 0..largeur as i32 {
+            for x in 0..longueur as i32 {
+                sentiers.push(labyrinthe.est_un_sentier(x, z));
+            }
+        }
+
+        let torches = labyrinthe.positions_flammes().iter().map(|flamme| {
+            labyrinthe.coordonnees_grille(&glm::Vec3::new(flamme[0], flamme[1], flamme[2]))
+        }).collect();
+
+        Carte {
+
+            longueur,
+            largeur,
+            sentiers,
+            explorees: vec![false; (longueur * largeur) as usize],
+            torches,
+
+            position: (0.0, 0.0),
+            direction: (0.0, 1.0),
+
+            plein_ecran: false,
+            parametres: parametres.clone(),
+        }
+    }
+
+    pub fn basculer_plein_ecran(&mut self) {
+
+        self.plein_ecran = !self.plein_ecran;
+    }
+
+    // Suit l'observateur et révèle les cellules qu'il peut voir
+    pub fn mettre_a_jour(&mut self, labyrinthe: &labyrinthe::Labyrinthe, observateur: &observateur::Observateur) {
+
+        self.position = labyrinthe.coordonnees_grille(&observateur.position);
+
+        let direction = observateur.direction();
+        let norme = (direction.x * direction.x + direction.z * direction.z).sqrt();
+        if norme > 0.0001 {
+            self.direction = (direction.x / norme, direction.z / norme);
+        }
+
+        let x = self.position.0.floor() as i32;
+        let z = self.position.1.floor() as i32;
+
+        // Les cellules voisines sont toujours visibles
+        for dx in -1..2 {
+            for dz in -1..2 {
+                self.explorer(x + dx, z + dz);
+            }
+        }
+
+        // On voit le long des couloirs, jusqu'au premier mur
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+
+            let (mut cx, mut cz) = (x, z);
+
+            while self.est_un_sentier(cx, cz) {
+
+                cx += dx;
+                cz += dz;
+                self.explorer(cx, cz);
+            }
+        }
+    }
+
+    // La surface doit avoir les dimensions de l'écran
+    pub fn dessiner<S: Surface>(&self, surface: &mut S, programme: &glium::Program, affichage: &dyn glium::backend::Facade) {
+
+        if !self.plein_ecran && !self.parametres.minicarte {
+            return;
+        }
+
+        let (largeur_ecran, hauteur_ecran) = surface.get_dimensions();
+        let (largeur_ecran, hauteur_ecran) = (largeur_ecran as f32, hauteur_ecran as f32);
+
+        let mut sommets = std::vec::Vec::new();
+        let opacite = self.parametres.opacite;
+
+        // Le point centre de la grille est dessiné à la position origine de l'écran, avec echelle pixels par cellule
+        let (centre, origine, echelle, cadre) = if self.plein_ecran {
+
+            // Tout le labyrinthe, centré sur un fond qui couvre l'écran
+            ajouter_rectangle(&mut sommets, (0.0, 0.0), (largeur_ecran, hauteur_ecran), [0.0, 0.0, 0.0, 0.85 * opacite]);
+
+            const MARGE: f32 = 0.9;
+            let echelle = MARGE * largeur_ecran.min(hauteur_ecran) / self.longueur.max(self.largeur) as f32;
+            let centre_carte = (self.longueur as f32 / 2.0, self.largeur as f32 / 2.0);
+
+            (centre_carte, (largeur_ecran / 2.0, hauteur_ecran / 2.0), echelle, None)
+        }
+        else {
+
+            // Quelques cellules autour de l'observateur, dans le coin supérieur droit
+            const CELLULES_VISIBLES: f32 = 9.0;
+            let cote = self.parametres.taille * hauteur_ecran;
+            let marge = 0.03 * hauteur_ecran;
+            let coin = (largeur_ecran - marge - cote, hauteur_ecran - marge - cote);
+
+            ajouter_rectangle(&mut sommets, coin, (cote, cote), [0.05, 0.05, 0.05, opacite]);
+
+            let cadre = glium::Rect {
+                left: coin.0.max(0.0) as u32,
+                bottom: coin.1.max(0.0) as u32,
+                width: cote as u32,
+                height: cote as u32,
+            };
+
+            (self.position, (coin.0 + cote / 2.0, coin.1 + cote / 2.0), cote / CELLULES_VISIBLES, Some(cadre))
+        };
+
+        let vers_ecran = |x: f32, z: f32| -> (f32, f32) {
+            (origine.0 + (x - centre.0) * echelle, origine.1 + (z - centre.1) * echelle)
+        };
+
+        const COULEUR_SENTIER: [f32; 3] = [0.75, 0.72, 0.65];
+        const COULEUR_MUR: [f32; 3] = [0.22, 0.17, 0.13];
+        const COULEUR_TORCHE: [f32; 3] = [1.0, 0.65, 0.15];
+        const COULEUR_OBSERVATEUR: [f32; 4] = [0.9, 0.15, 0.1, 1.0]; // Toujours opaque
+
+        let avec_opacite = |couleur: [f32; 3]| [couleur[0], couleur[1], couleur[2], opacite];
+
+        for z in 0..self.largeur as i32 {
+            for x in 0..self.longueur as i32 {
+
+                if !self.est_exploree(x, z) {
+                    continue;
+                }
+
+                let couleur = if self.est_un_sentier(x, z) { COULEUR_SENTIER } else { COULEUR_MUR };
+                ajouter_rectangle(&mut sommets, vers_ecran(x as f32, z as f32), (echelle, echelle), avec_opacite(couleur));
+            }
+        }
+
+        for (x, z) in self.torches.iter() {
+
+            if !self.est_exploree(x.floor() as i32, z.floor() as i32) {
+                continue;
+            }
+
+            let taille = echelle * 0.3;
+            let (ecran_x, ecran_z) = vers_ecran(*x, *z);
+            ajouter_rectangle(&mut sommets, (ecran_x - taille / 2.0, ecran_z - taille / 2.0), (taille, taille), avec_opacite(COULEUR_TORCHE));
+        }
+
+        // Flèche qui pointe dans la direction du regard
+        let (dx, dz) = self.direction;
+        let (px, pz) = self.position;
+        let pointe = vers_ecran(px + dx * 0.45, pz + dz * 0.45);
+        let gauche = vers_ecran(px - dx * 0.3 - dz * 0.3, pz - dz * 0.3 + dx * 0.3);
+        let droite = vers_ecran(px - dx * 0.3 + dz * 0.3, pz - dz * 0.3 - dx * 0.3);
+
+        for coin in [pointe, gauche, droite].iter() {
+            sommets.push(SommetCarte { position: [coin.0, coin.1], couleur: COULEUR_OBSERVATEUR });
+        }
+
+        let donnees_globales = uniform! {
+            dimensions_ecran: [largeur_ecran, hauteur_ecran],
+        };
+
+        let parametres = glium::DrawParameters {
+            blend: glium::Blend::alpha_blending(),
+            scissor: cadre, // La minicarte ne déborde pas de son carré
+            .. Default::default()
+        };
+
+        surface.draw(
+            &glium::VertexBuffer::new(affichage, &sommets).unwrap(),
+            glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+            programme,
+            &donnees_globales,
+            &parametres,
+        ).unwrap();
+    }
+
+    fn explorer(&mut self, x: i32, z: i32) {
+
+        if let Some(index) = self.index(x, z) {
+            self.explorees[index] = true;
+        }
+    }
+
+    fn est_exploree(&self, x: i32, z: i32) -> bool {
+
+        self.index(x, z).map(|index| self.explorees[index]).unwrap_or(false)
+    }
+
+    // L'extérieur du labyrinthe est considéré comme un mur
+    fn est_un_sentier(&self, x: i32, z: i32) -> bool {
+
+        self.index(x, z).map(|index| self.sentiers[index]).unwrap_or(false)
+    }
+
+    fn index(&self, x: i32, z: i32) -> Option<usize> {
+
+        if x < 0 || z < 0 || x >= self.longueur as i32 || z >= self.largeur as i32 {
+            return None;
+        }
+
+        Some((z as u32 * self.longueur + x as u32) as usize)
+    }
+}
+
+
+// Rectangle de couleur unie dessiné par-dessus la scène, par exemple pour le menu
+pub struct Rectangle {
+
+    pub coin: (f32, f32), // Inférieur gauche, en pixels
+    pub taille: (f32, f32),
+    pub couleur: [f32; 4],
+}
+
+// Utilise le même programme que la carte
+pub fn dessiner_rectangles<S: Surface>(surface: &mut S,
+                rectangles: &[Rectangle],
+                programme: &glium::Program,
+                affichage: &dyn glium::backend::Facade)
+{
+    let mut sommets = std::vec::Vec::with_capacity(rectangles.len() * 6);
+
+    for rectangle in rectangles.iter() {
+        ajouter_rectangle(&mut sommets, rectangle.coin, rectangle.taille, rectangle.couleur);
+    }
+
+    let (largeur_ecran, hauteur_ecran) = surface.get_dimensions();
+
+    let donnees_globales = uniform! {
+        dimensions_ecran: [largeur_ecran as f32, hauteur_ecran as f32],
+    };
+
+    let parametres = glium::DrawParameters {
+        blend: glium::Blend::alpha_blending(),
+        .. Default::default()
+    };
+
+    surface.draw(
+        &glium::VertexBuffer::new(affichage, &sommets).unwrap(),
+        glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+        programme,
+        &donnees_globales,
+        &parametres,
+    ).unwrap();
+}
+
+
+
+
+/*
+    Partie privée du module carte
+*/
+
+// Primitive pour OpenGL
+#[derive(Copy, Clone)]
+struct SommetCarte {
+    position: [f32; 2], // En pixels
+    couleur: [f32; 4],
+}
+// Permet à Glium de l'utiliser avec OpenGL
+implement_vertex!(SommetCarte, position, couleur);
+
+// Deux triangles, à partir du coin inférieur gauche
+fn ajouter_rectangle(sommets: &mut std::vec::Vec<SommetCarte>, coin: (f32, f32), taille: (f32, f32), couleur: [f32; 4]) {
+
+    let (x, y) = coin;
+    let (l, h) = taille;
+
+    for position in [[x, y], [x + l, y], [x + l, y + h], [x, y], [x + l, y + h], [x, y + h]].iter() {
+        sommets.push(SommetCarte { position: *position, couleur });
+    }
+}
@@ -0,0 +1,353 @@
+extern crate nalgebra_glm as glm;
+
+use labyrinthe;
+
+/*
+    Interface publique du module collision
+
+    Sert à déplacer un volume parmi des boîtes sans les traverser, peu importe la longueur du déplacement:
+    le mouvement est arrêté au premier contact, puis glisse le long de la surface touchée
+
+    Les boîtes couvrent toute la hauteur de la capsule qu'elles bloquent; le contact est donc calculé
+    dans le plan xz, entre un cercle et des rectangles. Les coins des rectangles sont arrondis par le rayon,
+    ce qui permet de contourner les coins sans accrocher
+*/
+
+// Cylindre vertical autour d'une position, par exemple les yeux de l'observateur
+#[derive(Clone, Copy)]
+pub struct Capsule {
+
+    pub rayon: f32,
+    pub bas: f32, // Hauteurs par rapport à la position
+    pub haut: f32,
+}
+
+// Retourne la position atteinte; le déplacement vertical n'est pas bloqué
+pub fn balayer(position: &glm::Vec3,
+                deplacement: &glm::Vec3,
+                capsule: &Capsule,
+                boites: &[labyrinthe::BoiteCollision]) -> glm::Vec3
+{
+    const ITERATIONS: usize = 4; // Contacts successifs, par exemple dans un coin
+
+    let boites = boites_a_hauteur(position, deplacement, capsule, boites);
+
+    let rayon = capsule.rayon;
+    let mut centre = sortir_des_boites(glm::Vec2::new(position.x, position.z), rayon, &boites);
+    let mut restant = glm::Vec2::new(deplacement.x, deplacement.z);
+
+    for _ in 0..ITERATIONS {
+
+        let longueur = restant.norm();
+
+        if longueur < 1e-6 {
+            break;
+        }
+
+        match premier_contact(&centre, &restant, rayon, &boites) {
+
+            None => {
+                centre += restant;
+                break;
+            },
+
+            Some((t, normale)) => {
+
+                let t = (t - MARGE / longueur).max(0.0);
+                centre += restant * t;
+
+                // Glissement: seule la partie du reste qui longe la surface est gardée
+                let reste = restant * (1.0 - t);
+                let vers_surface = reste.dot(&normale);
+                restant = if vers_surface < 0.0 { reste - normale * vers_surface } else { reste };
+            },
+        }
+    }
+
+    let centre = sortir_des_boites(centre, rayon, &boites);
+
+    glm::Vec3::new(centre.x, position.y + deplacement.y, centre.y)
+}
+
+
+// Proportion du déplacement faite avant le premier contact, sans glisser; 1 si rien n'est touché
+// Par exemple pour une caméra qui doit s'arrêter devant un mur
+pub fn lancer(position: &glm::Vec3,
+                deplacement: &glm::Vec3,
+                capsule: &Capsule,
+                boites: &[labyrinthe::BoiteCollision]) -> f32
+{
+    let boites = boites_a_hauteur(position, deplacement, capsule, boites);
+
+    let centre = glm::Vec2::new(position.x, position.z);
+    let deplacement = glm::Vec2::new(deplacement.x, deplacement.z);
+
+    if deplacement.norm() < 1e-6 {
+        return 1.0;
+    }
+
+    match premier_contact(&centre, &deplacement, capsule.rayon, &boites) {
+        Some((t, _)) => (t - MARGE / deplacement.norm()).max(0.0),
+        None => 1.0,
+    }
+}
+
+
+
+
+/*
+    Partie privée du module collision
+*/
+
+const MARGE: f32 = 0.001; // Distance gardée avec la surface touchée
+
+// Seules les boîtes à la hauteur de la capsule, au début ou à la fin du déplacement, la bloquent
+fn boites_a_hauteur<'a>(position: &glm::Vec3,
+                deplacement: &glm::Vec3,
+                capsule: &Capsule,
+                boites: &'a [labyrinthe::BoiteCollision]) -> std::vec::Vec<&'a labyrinthe::BoiteCollision>
+{
+    let y_debut = position.y.min(position.y + deplacement.y);
+    let y_fin = position.y.max(position.y + deplacement.y);
+
+    boites.iter()
+        .filter(|boite| y_debut + capsule.bas < boite.max[1] && y_fin + capsule.haut > boite.min[1])
+        .collect()
+}
+
+// Moment du premier contact, entre 0 et 1, et normale de la surface touchée
+fn premier_contact(centre: &glm::Vec2,
+                deplacement: &glm::Vec2,
+                rayon: f32,
+                boites: &[&labyrinthe::BoiteCollision]) -> Option<(f32, glm::Vec2)>
+{
+    let mut premier: Option<(f32, glm::Vec2)> = None;
+
+    for boite in boites.iter() {
+
+        if let Some((t, normale)) = contact_boite(centre, deplacement, rayon, boite) {
+
+            if plus_tot(t, &premier) {
+                premier = Some((t, normale));
+            }
+        }
+    }
+
+    premier
+}
+
+// La boîte agrandie par le rayon est un rectangle aux coins arrondis;
+// le centre du cercle y entre par un côté ou par un coin
+fn contact_boite(centre: &glm::Vec2,
+                deplacement: &glm::Vec2,
+                rayon: f32,
+                boite: &labyrinthe::BoiteCollision) -> Option<(f32, glm::Vec2)>
+{
+    const TOLERANCE: f32 = 1e-4; // Un contact à peine derrière le départ compte, pour ne pas s'enfoncer
+
+    let (x_min, x_max) = (boite.min[0], boite.max[0]);
+    let (z_min, z_max) = (boite.min[2], boite.max[2]);
+    let (d_x, d_z) = (deplacement.x, deplacement.y);
+
+    let mut premier: Option<(f32, glm::Vec2)> = None;
+    let mut garder = |t: f32, normale: glm::Vec2| {
+
+        if (-TOLERANCE..=1.0).contains(&t) && plus_tot(t, &premier) {
+            premier = Some((t.max(0.0), normale));
+        }
+    };
+
+    // Côtés, seulement si le déplacement va vers eux
+    if d_x > 0.0 {
+        let t = (x_min - rayon - centre.x) / d_x;
+        let z = centre.y + t * d_z;
+        if (z_min..=z_max).contains(&z) { garder(t, glm::Vec2::new(-1.0, 0.0)); }
+    }
+    if d_x < 0.0 {
+        let t = (x_max + rayon - centre.x) / d_x;
+        let z = centre.y + t * d_z;
+        if (z_min..=z_max).contains(&z) { garder(t, glm::Vec2::new(1.0, 0.0)); }
+    }
+    if d_z > 0.0 {
+        let t = (z_min - rayon - centre.y) / d_z;
+        let x = centre.x + t * d_x;
+        if (x_min..=x_max).contains(&x) { garder(t, glm::Vec2::new(0.0, -1.0)); }
+    }
+    if d_z < 0.0 {
+        let t = (z_max + rayon - centre.y) / d_z;
+        let x = centre.x + t * d_x;
+        if (x_min..=x_max).contains(&x) { garder(t, glm::Vec2::new(0.0, 1.0)); }
+    }
+
+    // Coins: intersection avec un cercle du rayon de la capsule
+    let a = deplacement.dot(deplacement);
+
+    for coin in [(x_min, z_min), (x_max, z_min), (x_min, z_max), (x_max, z_max)].iter() {
+
+        let coin = glm::Vec2::new(coin.0, coin.1);
+        let ecart = centre - coin;
+
+        let b = ecart.dot(deplacement);
+        let c = ecart.dot(&ecart) - rayon * rayon;
+        let discriminant = b * b - a * c;
+
+        // Déjà dans le cercle, en s'éloignant, ou sans le croiser
+        if c < 0.0 || b >= 0.0 || discriminant < 0.0 {
+            continue;
+        }
+
+        let t = (-b - discriminant.sqrt()) / a;
+        let contact = centre + deplacement * t;
+        garder(t, (contact - coin) / rayon);
+    }
+
+    premier
+}
+
+// Repousse le cercle hors des boîtes qu'il chevauche, par exemple à l'apparition
+fn sortir_des_boites(centre: glm::Vec2, rayon: f32, boites: &[&labyrinthe::BoiteCollision]) -> glm::Vec2 {
+
+    const ITERATIONS: usize = 4; // Sortir d'une boîte peut faire entrer dans sa voisine
+    const MARGE: f32 = 1e-4;
+
+    let mut centre = centre;
+
+    for _ in 0..ITERATIONS {
+
+        let mut deplace = false;
+
+        for boite in boites.iter() {
+
+            let (x_min, x_max) = (boite.min[0], boite.max[0]);
+            let (z_min, z_max) = (boite.min[2], boite.max[2]);
+
+            let proche = glm::Vec2::new(centre.x.max(x_min).min(x_max), centre.y.max(z_min).min(z_max));
+            let ecart = centre - proche;
+            let distance = ecart.norm();
+
+            if distance >= rayon {
+                continue;
+            }
+
+            if distance > 1e-6 {
+                centre = proche + ecart * ((rayon + MARGE) / distance);
+            }
+            else {
+
+                // Le centre est dans la boîte: on sort par le côté le plus proche
+                let sorties = [
+                    (centre.x - x_min, glm::Vec2::new(x_min - rayon - MARGE, centre.y)),
+                    (x_max - centre.x, glm::Vec2::new(x_max + rayon + MARGE, centre.y)),
+                    (centre.y - z_min, glm::Vec2::new(centre.x, z_min - rayon - MARGE)),
+                    (z_max - centre.y, glm::Vec2::new(centre.x, z_max + rayon + MARGE)),
+                ];
+
+                let mut sortie = sorties[0];
+                for autre in sorties.iter().skip(1) {
+                    if autre.0 < sortie.0 {
+                        sortie = *autre;
+                    }
+                }
+
+                centre = sortie.1;
+            }
+
+            deplace = true;
+        }
+
+        if !deplace {
+            break;
+        }
+    }
+
+    centre
+}
+
+fn plus_tot(t: f32, premier: &Option<(f32, glm::Vec2)>) -> bool {
+
+    match premier {
+        Some((t_premier, _)) => t < *t_premier,
+        None => true,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const CAPSULE: Capsule = Capsule { rayon: 0.2, bas: -1.5, haut: 0.2 };
+
+    fn boite(x: (f32, f32), z: (f32, f32)) -> labyrinthe::BoiteCollision {
+
+        labyrinthe::BoiteCollision {
+            min: [x.0, 0.0, z.0],
+            max: [x.1, 3.0, z.1],
+        }
+    }
+
+    fn proche(a: f32, b: f32) -> bool {
+
+        (a - b).abs() < 1e-2
+    }
+
+    #[test]
+    fn deplacement_rapide_arrete_devant_un_mur_mince() {
+
+        let boites = [boite((1.0, 1.1), (-5.0, 5.0))];
+        let position = glm::Vec3::new(0.0, 1.5, 0.0);
+        let deplacement = glm::Vec3::new(10.0, 0.0, 0.0);
+
+        let arrivee = balayer(&position, &deplacement, &CAPSULE, &boites);
+        assert!(arrivee.x <= 1.0 - CAPSULE.rayon && proche(arrivee.x, 0.8), "traversé jusqu'à {}", arrivee.x);
+
+        let proportion = lancer(&position, &deplacement, &CAPSULE, &boites);
+        assert!(proportion <= 0.08 && proche(proportion, 0.08), "proportion {}", proportion);
+    }
+
+    #[test]
+    fn glissement_garde_la_partie_le_long_du_mur() {
+
+        let boites = [boite((1.0, 1.1), (-5.0, 5.0))];
+        let position = glm::Vec3::new(0.5, 1.5, 0.0);
+
+        let arrivee = balayer(&position, &glm::Vec3::new(2.0, 0.0, 1.0), &CAPSULE, &boites);
+
+        assert!(proche(arrivee.x, 0.8), "x: {}", arrivee.x);
+        assert!(proche(arrivee.z, 1.0), "z: {}", arrivee.z);
+    }
+
+    #[test]
+    fn coin_exterieur_contourne_sans_accrocher() {
+
+        // Le cercle frôle le coin (1, 0) de la boîte
+        let boites = [boite((1.0, 2.0), (0.0, 1.0))];
+        let position = glm::Vec3::new(0.5, 1.5, -0.19);
+
+        let arrivee = balayer(&position, &glm::Vec3::new(3.0, 0.0, 0.0), &CAPSULE, &boites);
+
+        assert!(arrivee.x > 2.0 + CAPSULE.rayon, "accroché au coin en x = {}", arrivee.x);
+        assert!(arrivee.z <= -CAPSULE.rayon, "entré dans la boîte en z = {}", arrivee.z);
+    }
+
+    #[test]
+    fn depart_dans_une_boite_repousse_dehors() {
+
+        let boites = [boite((1.0, 2.0), (0.0, 1.0))];
+        let position = glm::Vec3::new(1.1, 1.5, 0.5);
+
+        // Sans déplacement, on sort par le côté le plus proche
+        let arrivee = balayer(&position, &glm::Vec3::new(0.0, 0.0, 0.0), &CAPSULE, &boites);
+
+        assert!(arrivee.x <= 1.0 - CAPSULE.rayon && proche(arrivee.x, 0.8), "x: {}", arrivee.x);
+        assert!(proche(arrivee.z, 0.5), "z: {}", arrivee.z);
+
+        // Le centre à moins d'un rayon d'un coin est repoussé en diagonale
+        let centre = sortir_des_boites(glm::Vec2::new(0.95, -0.05), CAPSULE.rayon, &[&boites[0]]);
+        let distance = (centre - glm::Vec2::new(1.0, 0.0)).norm();
+
+        assert!(distance >= CAPSULE.rayon, "distance au coin: {}", distance);
+        assert!(proche(centre.x - 1.0, centre.y), "pas en diagonale: {:?}", centre);
+    }
+}
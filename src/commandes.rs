@@ -0,0 +1,436 @@
+use glium::{glutin};
+
+/*
+    Interface publique du module commandes
+
+    Sert à associer les touches du clavier et les boutons de la souris aux commandes du programme
+    Une commande peut avoir plusieurs entrées; la logique du programme ne consulte que les commandes,
+    voir evenements::GestionnaireEvenements::valeur_commande
+
+    Les associations sont lues dans un fichier texte, et y sont enregistrées lorsqu'elles changent dans le menu
+    Chaque ligne a la forme «commande = entrée, entrée», par exemple «avancer = W, Up»
+    Les touches portent le nom des variantes de glutin::event::VirtualKeyCode,
+    les boutons de la souris s'écrivent souris_gauche, souris_droite et souris_milieu,
+    et ceux de la manette manette_0, manette_1, etc.
+
+    Une entrée peut aussi être une position sur le clavier, peu importe la lettre qui y est imprimée:
+    position_W est la touche qui porte W en QWERTY, donc Z en AZERTY
+    Le déplacement utilise ces positions par défaut; la lettre réellement associée à une position
+    est apprise lorsque la touche est appuyée, pour l'afficher (voir Etiquettes)
+*/
+
+pub const CHEMIN: &str = "touches.txt";
+
+// Touche produite par chaque position du clavier (code de balayage) déjà appuyée
+pub type Etiquettes = std::collections::HashMap<u32, glutin::event::VirtualKeyCode>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Commande {
+
+    Avancer,
+    Reculer,
+    Gauche,
+    Droite,
+    Sauter, // En marchant
+    Accroupir,
+    Monter, // En mode aérien seulement
+    Descendre,
+    ModeAerien,
+    Camera, // Passe au point de vue suivant, voir camera::ModeCamera
+    Survol, // Commence ou interrompt le survol du chemin vers la sortie
+    Menu,
+    Carte,
+    Capture,
+}
+
+impl Commande {
+
+    pub const TOUTES: [Commande; 14] = [
+        Commande::Avancer,
+        Commande::Reculer,
+        Commande::Gauche,
+        Commande::Droite,
+        Commande::Sauter,
+        Commande::Accroupir,
+        Commande::Monter,
+        Commande::Descendre,
+        Commande::ModeAerien,
+        Commande::Camera,
+        Commande::Survol,
+        Commande::Menu,
+        Commande::Carte,
+        Commande::Capture,
+    ];
+
+    // Affiché dans le menu
+    pub fn nom(self) -> &'static str {
+
+        match self {
+            Commande::Avancer => "Avancer",
+            Commande::Reculer => "Reculer",
+            Commande::Gauche => "Gauche",
+            Commande::Droite => "Droite",
+            Commande::Sauter => "Sauter",
+            Commande::Accroupir => "S'accroupir",
+            Commande::Monter => "Monter",
+            Commande::Descendre => "Descendre",
+            Commande::ModeAerien => "Mode aérien",
+            Commande::Camera => "Changer de caméra",
+            Commande::Survol => "Survol vers la sortie",
+            Commande::Menu => "Menu",
+            Commande::Carte => "Carte",
+            Commande::Capture => "Capture d'écran",
+        }
+    }
+
+    // Écrit dans le fichier
+    fn clef(self) -> &'static str {
+
+        match self {
+            Commande::Avancer => "avancer",
+            Commande::Reculer => "reculer",
+            Commande::Gauche => "gauche",
+            Commande::Droite => "droite",
+            Commande::Sauter => "sauter",
+            Commande::Accroupir => "accroupir",
+            Commande::Monter => "monter",
+            Commande::Descendre => "descendre",
+            Commande::ModeAerien => "mode_aerien",
+            Commande::Camera => "camera",
+            Commande::Survol => "survol",
+            Commande::Menu => "menu",
+            Commande::Carte => "carte",
+            Commande::Capture => "capture",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Entree {
+
+    Touche(glutin::event::VirtualKeyCode),
+    Position(u32), // Code de balayage, indépendant de la disposition du clavier
+    Souris(glutin::event::MouseButton),
+    Manette(u8), // Numéro du bouton, voir manette::Manette
+}
+
+impl Entree {
+
+    // Tel qu'écrit dans le fichier
+    pub fn nom(self) -> String {
+
+        match self {
+
+            Entree::Touche(touche) => match TOUCHES.iter().find(|(autre, _)| *autre == touche) {
+                Some((_, nom)) => nom.to_string(),
+                None => format!("{:?}", touche),
+            },
+
+            Entree::Position(code) => match POSITIONS.iter().find(|(autre, _)| *autre == code) {
+                Some((_, nom)) => format!("position_{}", nom),
+                None => format!("position_{}", code),
+            },
+
+            Entree::Souris(glutin::event::MouseButton::Left) => "souris_gauche".to_string(),
+            Entree::Souris(glutin::event::MouseButton::Right) => "souris_droite".to_string(),
+            Entree::Souris(glutin::event::MouseButton::Middle) => "souris_milieu".to_string(),
+            Entree::Souris(glutin::event::MouseButton::Other(numero)) => format!("souris_{}", numero),
+
+            Entree::Manette(bouton) => format!("manette_{}", bouton),
+        }
+    }
+
+    // Tel qu'affiché: une position montre la touche qu'elle produit avec la disposition de l'utilisateur
+    pub fn libelle(self, etiquettes: &Etiquettes) -> String {
+
+        match self {
+
+            Entree::Position(code) => match (etiquettes.get(&code), POSITIONS.iter().find(|(autre, _)| *autre == code)) {
+                (Some(touche), _) => Entree::Touche(*touche).nom(),
+                (None, Some((_, nom))) => format!("{} (QWERTY)", nom),
+                (None, None) => format!("position {}", code),
+            },
+
+            _ => self.nom(),
+        }
+    }
+
+    // Inverse de nom
+    pub fn lire(nom: &str) -> Result<Entree, String> {
+
+        match nom {
+
+            "souris_gauche" => return Ok(Entree::Souris(glutin::event::MouseButton::Left)),
+            "souris_droite" => return Ok(Entree::Souris(glutin::event::MouseButton::Right)),
+            "souris_milieu" => return Ok(Entree::Souris(glutin::event::MouseButton::Middle)),
+            _ => (),
+        }
+
+        if let Some(numero) = nom.strip_prefix("souris_").and_then(|numero| numero.parse::<u8>().ok()) {
+            return Ok(Entree::Souris(glutin::event::MouseButton::Other(numero)));
+        }
+
+        if let Some(bouton) = nom.strip_prefix("manette_") {
+
+            return match bouton.parse::<u8>() {
+                Ok(bouton) if bouton < 32 => Ok(Entree::Manette(bouton)),
+                _ => Err(format!("le bouton de manette «{}» est invalide (0 à 31)", bouton)),
+            };
+        }
+
+        if let Some(position) = nom.strip_prefix("position_") {
+
+            return match POSITIONS.iter().find(|(_, autre)| *autre == position) {
+                Some((code, _)) => Ok(Entree::Position(*code)),
+                None => position.parse::<u32>().map(Entree::Position)
+                    .map_err(|_| format!("la position «{}» est inconnue", position)),
+            };
+        }
+
+        match TOUCHES.iter().find(|(_, autre)| *autre == nom) {
+            Some((touche, _)) => Ok(Entree::Touche(*touche)),
+            None => Err(format!("l'entrée «{}» est inconnue", nom)),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Associations {
+
+    entrees: std::collections::HashMap<Commande, std::vec::Vec<Entree>>,
+}
+
+impl Associations {
+
+    pub fn new() -> Associations {
+
+        use glium::glutin::event::VirtualKeyCode;
+
+        let mut associations = Associations {
+            entrees: std::collections::HashMap::new(),
+        };
+
+        // Le déplacement suit la position des touches: WASD en QWERTY, ZQSD en AZERTY
+        let positions = [
+            (Commande::Avancer, "W"),
+            (Commande::Reculer, "S"),
+            (Commande::Gauche, "A"),
+            (Commande::Droite, "D"),
+        ];
+
+        for (commande, nom) in positions.iter() {
+
+            let (code, _) = POSITIONS.iter().find(|(_, autre)| autre == nom).unwrap();
+            associations.entrees.insert(*commande, vec![Entree::Position(*code)]);
+        }
+
+        let touches = [
+            (Commande::Sauter, VirtualKeyCode::Space),
+            (Commande::Accroupir, VirtualKeyCode::LControl),
+            (Commande::Monter, VirtualKeyCode::Space),
+            (Commande::Descendre, VirtualKeyCode::LShift),
+            (Commande::ModeAerien, VirtualKeyCode::F),
+            (Commande::Camera, VirtualKeyCode::V),
+            (Commande::Survol, VirtualKeyCode::C),
+            (Commande::Menu, VirtualKeyCode::Escape),
+            (Commande::Carte, VirtualKeyCode::M),
+            (Commande::Capture, VirtualKeyCode::F12),
+        ];
+
+        for (commande, touche) in touches.iter() {
+            associations.entrees.insert(*commande, vec![Entree::Touche(*touche)]);
+        }
+
+        // A, B, X, Y, Select et Start sur une manette Xbox
+        let boutons = [
+            (Commande::Sauter, 0),
+            (Commande::Accroupir, 1),
+            (Commande::Camera, 2),
+            (Commande::ModeAerien, 3),
+            (Commande::Carte, 6),
+            (Commande::Menu, 7),
+        ];
+
+        for (commande, bouton) in boutons.iter() {
+            associations.ajouter(*commande, Entree::Manette(*bouton));
+        }
+
+        associations
+    }
+
+    // Les commandes absentes du fichier gardent leurs entrées par défaut
+    pub fn charger(chemin: &str) -> Associations {
+
+        match std::fs::read_to_string(chemin) {
+
+            Ok(contenu) => Associations::analyser(&contenu, chemin),
+            Err(_) => {
+                println!("Le fichier de touches {} est introuvable, les touches par défaut sont utilisées", chemin);
+                Associations::new()
+            }
+        }
+    }
+
+    // source: d'où vient le contenu, pour les erreurs
+    pub fn analyser(contenu: &str, source: &str) -> Associations {
+
+        let mut associations = Associations::new();
+
+        for (numero, ligne) in contenu.lines().enumerate() {
+
+            let ligne = ligne.trim();
+
+            if ligne.is_empty() || ligne.starts_with('#') {
+                continue;
+            }
+
+            let mut morceaux = ligne.splitn(2, '=');
+            let clef = morceaux.next().unwrap_or("").trim();
+            let valeur = morceaux.next().unwrap_or("").trim();
+
+            if let Err(erreur) = associations.modifier(clef, valeur) {
+                println!("{}, ligne {}: {}", source, numero + 1, erreur);
+            }
+        }
+
+        associations
+    }
+
+    pub fn enregistrer(&self, chemin: &str) -> std::io::Result<()> {
+
+        std::fs::write(chemin, String::from(EN_TETE) + &self.contenu())
+    }
+
+    // Une ligne par commande, comme dans le fichier, sans l'en-tête
+    pub fn contenu(&self) -> String {
+
+        let mut contenu = String::new();
+
+        for commande in Commande::TOUTES.iter() {
+
+            let noms: std::vec::Vec<String> = self.obtenir(*commande).iter().map(|entree| entree.nom()).collect();
+            contenu += &format!("{} = {}\n", commande.clef(), noms.join(", "));
+        }
+
+        contenu
+    }
+
+    pub fn obtenir(&self, commande: Commande) -> &[Entree] {
+
+        match self.entrees.get(&commande) {
+            Some(entrees) => entrees,
+            None => &[],
+        }
+    }
+
+    // Noms des entrées de la commande, pour les afficher
+    pub fn description(&self, commande: Commande, etiquettes: &Etiquettes) -> String {
+
+        let noms: std::vec::Vec<String> = self.obtenir(commande).iter().map(|entree| entree.libelle(etiquettes)).collect();
+
+        if noms.is_empty() {
+            return "aucune".to_string();
+        }
+
+        noms.join(", ")
+    }
+
+    // Au-delà de ENTREES_MAX, la plus ancienne entrée est remplacée
+    pub fn ajouter(&mut self, commande: Commande, entree: Entree) {
+
+        const ENTREES_MAX: usize = 3;
+
+        let entrees = self.entrees.entry(commande).or_default();
+        entrees.retain(|autre| *autre != entree);
+
+        if entrees.len() >= ENTREES_MAX {
+            entrees.remove(0);
+        }
+
+        entrees.push(entree);
+    }
+
+    pub fn effacer(&mut self, commande: Commande) {
+
+        self.entrees.insert(commande, std::vec::Vec::new());
+    }
+
+    // Remplace les entrées de la commande, à partir d'une ligne du fichier
+    fn modifier(&mut self, clef: &str, valeur: &str) -> Result<(), String> {
+
+        let commande = match Commande::TOUTES.iter().find(|commande| commande.clef() == clef) {
+            Some(commande) => *commande,
+            None => return Err(format!("la commande «{}» est inconnue", clef)),
+        };
+
+        let mut entrees = std::vec::Vec::new();
+
+        for nom in valeur.split(',').map(|nom| nom.trim()).filter(|nom| !nom.is_empty()) {
+            entrees.push(Entree::lire(nom)?);
+        }
+
+        self.entrees.insert(commande, entrees);
+
+        Ok(())
+    }
+}
+
+
+
+
+
+/*
+    Partie privée du module commandes
+*/
+
+const EN_TETE: &str = "\
+# Touches associées aux commandes, modifiables dans le menu (Réglages, Touches)
+# Plusieurs entrées sont séparées par des virgules; une commande peut aussi n'en avoir aucune
+# Boutons de la souris: souris_gauche, souris_droite, souris_milieu; boutons de la manette: manette_0, manette_1, etc.
+# position_W désigne la touche placée où se trouve W en QWERTY (Z en AZERTY), peu importe la disposition
+\n";
+
+// Associe chaque touche reconnue au nom de sa variante
+macro_rules! touches_nommees {
+    ($($touche:ident),* $(,)*) => {
+        const TOUCHES: &[(glutin::event::VirtualKeyCode, &str)] = &[
+            $((glutin::event::VirtualKeyCode::$touche, stringify!($touche))),*
+        ];
+    };
+}
+
+touches_nommees!(
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Escape, Tab, Capital, Space, Return, Back, Insert, Delete, Home, End, PageUp, PageDown,
+    Left, Up, Right, Down,
+    LShift, RShift, LControl, RControl, LAlt, RAlt,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    Add, Subtract, Multiply, Divide, Decimal, NumpadEnter,
+    Apostrophe, Backslash, Comma, Equals, Grave, LBracket, RBracket, Minus, Period, Semicolon, Slash,
+);
+
+// Codes de balayage des lettres et des chiffres, nommés d'après la disposition QWERTY
+// Linux et Windows donnent les codes du clavier PC, macOS a ses propres codes
+#[cfg(not(target_os = "macos"))]
+const POSITIONS: &[(u32, &str)] = &[
+    (0x02, "Key1"), (0x03, "Key2"), (0x04, "Key3"), (0x05, "Key4"), (0x06, "Key5"),
+    (0x07, "Key6"), (0x08, "Key7"), (0x09, "Key8"), (0x0A, "Key9"), (0x0B, "Key0"),
+    (0x10, "Q"), (0x11, "W"), (0x12, "E"), (0x13, "R"), (0x14, "T"),
+    (0x15, "Y"), (0x16, "U"), (0x17, "I"), (0x18, "O"), (0x19, "P"),
+    (0x1E, "A"), (0x1F, "S"), (0x20, "D"), (0x21, "F"), (0x22, "G"),
+    (0x23, "H"), (0x24, "J"), (0x25, "K"), (0x26, "L"),
+    (0x2C, "Z"), (0x2D, "X"), (0x2E, "C"), (0x2F, "V"), (0x30, "B"), (0x31, "N"), (0x32, "M"),
+];
+
+#[cfg(target_os = "macos")]
+const POSITIONS: &[(u32, &str)] = &[
+    (0x12, "Key1"), (0x13, "Key2"), (0x14, "Key3"), (0x15, "Key4"), (0x17, "Key5"),
+    (0x16, "Key6"), (0x1A, "Key7"), (0x1C, "Key8"), (0x19, "Key9"), (0x1D, "Key0"),
+    (0x0C, "Q"), (0x0D, "W"), (0x0E, "E"), (0x0F, "R"), (0x11, "T"),
+    (0x10, "Y"), (0x20, "U"), (0x22, "I"), (0x1F, "O"), (0x23, "P"),
+    (0x00, "A"), (0x01, "S"), (0x02, "D"), (0x03, "F"), (0x05, "G"),
+    (0x04, "H"), (0x26, "J"), (0x28, "K"), (0x25, "L"),
+    (0x06, "Z"), (0x07, "X"), (0x08, "C"), (0x09, "V"), (0x0B, "B"), (0x2D, "N"), (0x2E, "M"),
+];
@@ -0,0 +1,364 @@
+/*
+    Interface publique du module configuration
+
+    Sert à lire les paramètres du programme à partir d'un fichier texte
+
+    Chaque ligne du fichier a la forme «clef = valeur»
+    Les lignes vides et celles commençant par # sont ignorées
+*/
+
+#[derive(Clone)]
+pub struct Configuration {
+
+    pub anisotropie: u16, // Filtrage anisotrope des textures, 1 le désactive
+    pub sensibilite: f32, // Multiplie la vitesse de rotation du regard par la souris
+    pub inverser_y: bool, // Pousser la souris vers l'avant fait regarder vers le bas
+    pub lissage: f32, // Part du mouvement de la souris reportée à l'image suivante, entre 0 et LISSAGE_MAX
+    pub plein_ecran: bool,
+    pub mode_aerien: bool, // Au départ, plutôt que de marcher
+    pub balancement: bool, // La tête balance au rythme des pas
+    pub vitesse_survol: f32, // Voir survol::Survol
+    pub camera: ParametresCamera,
+    pub post_traitement: ParametresPostTraitement,
+    pub carte: ParametresCarte,
+    pub hud: ParametresHud,
+    pub manette: ParametresManette,
+}
+
+impl Configuration {
+
+    // Les valeurs par défaut sont utilisées si le fichier n'existe pas
+    pub fn charger(chemin: &str) -> Configuration {
+
+        match std::fs::read_to_string(chemin) {
+
+            Ok(contenu) => Configuration::analyser(&contenu, chemin),
+            Err(_) => {
+                println!("Le fichier de configuration {} est introuvable, les valeurs par défaut sont utilisées", chemin);
+                Configuration::new()
+            }
+        }
+    }
+
+    // source: d'où vient le contenu, pour les erreurs
+    pub fn analyser(contenu: &str, source: &str) -> Configuration {
+
+        let mut configuration = Configuration::new();
+
+        for (numero, ligne) in contenu.lines().enumerate() {
+
+            let ligne = ligne.trim();
+
+            if ligne.is_empty() || ligne.starts_with('#') {
+                continue;
+            }
+
+            let mut morceaux = ligne.splitn(2, '=');
+            let clef = morceaux.next().unwrap_or("").trim();
+            let valeur = morceaux.next().unwrap_or("").trim();
+
+            if let Err(erreur) = configuration.modifier(clef, valeur) {
+                println!("{}, ligne {}: {}", source, numero + 1, erreur);
+            }
+        }
+
+        configuration
+    }
+
+    pub fn new() -> Configuration {
+
+        Configuration {
+
+            anisotropie: 8,
+            sensibilite: 1.0,
+            inverser_y: false,
+            lissage: 0.0,
+            plein_ecran: false,
+            mode_aerien: false,
+            balancement: true,
+            vitesse_survol: 1.5,
+            camera: ParametresCamera::new(),
+            post_traitement: ParametresPostTraitement::new(),
+            carte: ParametresCarte::new(),
+            hud: ParametresHud::new(),
+            manette: ParametresManette::new(),
+        }
+    }
+
+    // Change un paramètre à partir de sa clef, tel qu'écrit dans le fichier
+    pub fn modifier(&mut self, clef: &str, valeur: &str) -> Result<(), String> {
+
+        let camera = &mut self.camera;
+        let post_traitement = &mut self.post_traitement;
+
+        match clef {
+
+            "anisotropie" => self.anisotropie = lire_anisotropie(valeur)?,
+            "sensibilite" => self.sensibilite = lire_nombre(valeur)?.clamp(SENSIBILITE_MIN, SENSIBILITE_MAX),
+            "inverser_y" => self.inverser_y = lire_booleen(valeur)?,
+            "lissage" => self.lissage = lire_nombre(valeur)?.clamp(0.0, LISSAGE_MAX),
+            "plein_ecran" => self.plein_ecran = lire_booleen(valeur)?,
+            "mode_aerien" => self.mode_aerien = lire_booleen(valeur)?,
+            "balancement_tete" => self.balancement = lire_booleen(valeur)?,
+            "survol_vitesse" => self.vitesse_survol = lire_nombre(valeur)?.clamp(0.1, 20.0),
+
+            "champ_vision" => camera.champ_vision = lire_nombre(valeur)?.clamp(CHAMP_VISION_MIN, CHAMP_VISION_MAX),
+            "plan_proche" => camera.plan_proche = lire_nombre(valeur)?.clamp(PLAN_PROCHE_MIN, PLAN_PROCHE_MAX),
+            "plan_loin" => camera.plan_loin = lire_nombre(valeur)?.clamp(PLAN_LOIN_MIN, PLAN_LOIN_MAX),
+            "echelle_rendu" => camera.echelle_rendu = lire_nombre(valeur)?.clamp(ECHELLE_RENDU_MIN, ECHELLE_RENDU_MAX),
+
+            "tonalite" => post_traitement.tonalite = lire_booleen(valeur)?,
+            "exposition" => post_traitement.exposition = lire_nombre(valeur)?,
+            "gamma" => post_traitement.gamma = lire_booleen(valeur)?,
+            "bloom" => post_traitement.bloom = lire_booleen(valeur)?,
+            "bloom_seuil" => post_traitement.seuil_bloom = lire_nombre(valeur)?,
+            "bloom_intensite" => post_traitement.intensite_bloom = lire_nombre(valeur)?,
+            "brouillard" => post_traitement.brouillard = lire_booleen(valeur)?,
+            "brouillard_densite" => post_traitement.densite_brouillard = lire_nombre(valeur)?,
+            "brouillard_couleur" => post_traitement.couleur_brouillard = lire_couleur(valeur)?,
+            "vignette" => post_traitement.vignette = lire_booleen(valeur)?,
+            "vignette_intensite" => post_traitement.intensite_vignette = lire_nombre(valeur)?,
+            "msaa" => post_traitement.msaa = lire_echantillons(valeur)?,
+            "fxaa" => post_traitement.fxaa = lire_booleen(valeur)?,
+
+            "minicarte" => self.carte.minicarte = lire_booleen(valeur)?,
+            "carte_taille" => self.carte.taille = lire_nombre(valeur)?.clamp(0.05, 1.0),
+            "carte_opacite" => self.carte.opacite = lire_nombre(valeur)?.clamp(0.0, 1.0),
+
+            "hud" => self.hud.visible = lire_booleen(valeur)?,
+            "hud_taille" => self.hud.taille = lire_nombre(valeur)?.max(4.0),
+
+            "manette" => self.manette.active = lire_booleen(valeur)?,
+            "manette_peripherique" => self.manette.peripherique = valeur.to_string(),
+            "manette_zone_morte" => self.manette.zone_morte = lire_nombre(valeur)?.clamp(0.0, 0.9),
+            "manette_vitesse_regard" => self.manette.vitesse_regard = lire_nombre(valeur)?.max(0.0),
+
+            _ => return Err(format!("la clef «{}» est inconnue", clef)),
+        }
+
+        Ok(())
+    }
+}
+
+// Limites des paramètres aussi modifiables dans le menu
+// Les intervalles des plans ne se chevauchent pas, le plan proche reste donc devant le plan loin
+pub const SENSIBILITE_MIN: f32 = 0.1;
+pub const SENSIBILITE_MAX: f32 = 5.0;
+pub const LISSAGE_MAX: f32 = 0.9;
+pub const CHAMP_VISION_MIN: f32 = 40.0;
+pub const CHAMP_VISION_MAX: f32 = 140.0;
+pub const PLAN_PROCHE_MIN: f32 = 0.001;
+pub const PLAN_PROCHE_MAX: f32 = 1.0;
+pub const PLAN_LOIN_MIN: f32 = 5.0;
+pub const PLAN_LOIN_MAX: f32 = 1000.0;
+pub const ECHELLE_RENDU_MIN: f32 = 0.25;
+pub const ECHELLE_RENDU_MAX: f32 = 2.0;
+
+#[derive(Clone)]
+pub struct ParametresCamera {
+
+    pub champ_vision: f32, // En degrés
+    pub plan_proche: f32, // Distances visibles, entre les deux plans
+    pub plan_loin: f32,
+    pub echelle_rendu: f32, // Résolution de la scène par rapport à la fenêtre; l'image est ensuite étirée
+}
+
+impl ParametresCamera {
+
+    pub fn new() -> ParametresCamera {
+
+        ParametresCamera {
+
+            champ_vision: 90.0,
+            plan_proche: 0.01,
+            plan_loin: 100.0,
+            echelle_rendu: 1.0,
+        }
+    }
+}
+
+// Chaque passe peut être activée ou désactivée
+#[derive(Clone)]
+pub struct ParametresPostTraitement {
+
+    pub tonalite: bool, // Ramène les couleurs HDR entre 0 et 1 selon l'exposition
+    pub exposition: f32,
+
+    pub gamma: bool, // Conversion vers sRGB
+
+    pub bloom: bool, // Halo autour des zones très lumineuses
+    pub seuil_bloom: f32,
+    pub intensite_bloom: f32,
+
+    pub brouillard: bool, // Selon la distance à la caméra
+    pub densite_brouillard: f32,
+    pub couleur_brouillard: [f32; 3],
+
+    pub vignette: bool, // Assombrit les bords de l'écran
+    pub intensite_vignette: f32,
+
+    pub msaa: u32, // Échantillons par pixel, 0 désactive le multiéchantillonnage
+    pub fxaa: bool, // Anticrénelage approximatif, moins coûteux
+}
+
+impl ParametresPostTraitement {
+
+    pub fn new() -> ParametresPostTraitement {
+
+        ParametresPostTraitement {
+
+            tonalite: true,
+            exposition: 1.5,
+
+            gamma: true,
+
+            bloom: true,
+            seuil_bloom: 0.8,
+            intensite_bloom: 0.6,
+
+            brouillard: true,
+            densite_brouillard: 0.15,
+            couleur_brouillard: [0.0, 0.0, 0.0],
+
+            vignette: true,
+            intensite_vignette: 0.35,
+
+            msaa: 4,
+            fxaa: false,
+        }
+    }
+}
+
+// La carte complète s'affiche avec M, peu importe minicarte
+#[derive(Clone)]
+pub struct ParametresCarte {
+
+    pub minicarte: bool, // Dans le coin supérieur droit
+    pub taille: f32, // Côté de la minicarte, en proportion de la hauteur de l'écran
+    pub opacite: f32, // Entre 0 et 1
+}
+
+impl ParametresCarte {
+
+    pub fn new() -> ParametresCarte {
+
+        ParametresCarte {
+
+            minicarte: true,
+            taille: 0.3,
+            opacite: 0.8,
+        }
+    }
+}
+
+// Les messages temporaires restent affichés même si le reste du HUD est caché
+#[derive(Clone)]
+pub struct ParametresHud {
+
+    pub visible: bool, // Temps, images par seconde et position
+    pub taille: f32, // Hauteur d'une ligne de texte, en pixels
+}
+
+impl ParametresHud {
+
+    pub fn new() -> ParametresHud {
+
+        ParametresHud {
+
+            visible: true,
+            taille: 24.0,
+        }
+    }
+}
+
+// Les boutons sont associés aux commandes dans le fichier de touches, voir commandes
+#[derive(Clone)]
+pub struct ParametresManette {
+
+    pub active: bool,
+    pub peripherique: String, // Interface joystick de Linux
+    pub zone_morte: f32, // Course des sticks et des gâchettes ignorée, entre 0 et 1
+    pub vitesse_regard: f32, // Radians par seconde avec le stick droit au bout de sa course
+}
+
+impl ParametresManette {
+
+    pub fn new() -> ParametresManette {
+
+        ParametresManette {
+
+            active: true,
+            peripherique: "/dev/input/js0".to_string(),
+            zone_morte: 0.2,
+            vitesse_regard: 3.0,
+        }
+    }
+}
+
+
+
+
+
+/*
+    Partie privée du module configuration
+*/
+
+const ANISOTROPIE_MAX: u32 = 16; // Le maximum courant des cartes graphiques
+
+fn lire_booleen(valeur: &str) -> Result<bool, String> {
+
+    match valeur {
+
+        "oui" | "vrai" | "true" | "1" => Ok(true),
+        "non" | "faux" | "false" | "0" => Ok(false),
+        _ => Err(format!("«{}» n'est pas un booléen (oui ou non)", valeur)),
+    }
+}
+
+fn lire_nombre(valeur: &str) -> Result<f32, String> {
+
+    valeur.parse::<f32>().map_err(|_| format!("«{}» n'est pas un nombre", valeur))
+}
+
+fn lire_entier(valeur: &str) -> Result<u32, String> {
+
+    valeur.parse::<u32>().map_err(|_| format!("«{}» n'est pas un entier positif", valeur))
+}
+
+// De 1 à 16; 0 équivaut à 1
+fn lire_anisotropie(valeur: &str) -> Result<u16, String> {
+
+    match lire_entier(valeur)? {
+
+        0 => Ok(1),
+        anisotropie @ 1..=ANISOTROPIE_MAX => Ok(anisotropie as u16),
+        _ => Err(format!("«{}» n'est pas une anisotropie (de 1 à {})", valeur, ANISOTROPIE_MAX)),
+    }
+}
+
+// 0, 2, 4, 8 ou 16; 1 équivaut à 0
+fn lire_echantillons(valeur: &str) -> Result<u32, String> {
+
+    match lire_entier(valeur)? {
+
+        0 | 1 => Ok(0),
+        echantillons @ 2 | echantillons @ 4 | echantillons @ 8 | echantillons @ 16 => Ok(echantillons),
+        _ => Err(format!("«{}» n'est pas un nombre d'échantillons (0, 2, 4, 8 ou 16)", valeur)),
+    }
+}
+
+// Format: rouge, vert, bleu
+fn lire_couleur(valeur: &str) -> Result<[f32; 3], String> {
+
+    let composantes: std::vec::Vec<&str> = valeur.split(',').collect();
+
+    if composantes.len() != 3 {
+        return Err(format!("«{}» n'est pas une couleur (rouge, vert, bleu)", valeur));
+    }
+
+    Ok([
+        lire_nombre(composantes[0].trim())?,
+        lire_nombre(composantes[1].trim())?,
+        lire_nombre(composantes[2].trim())?,
+    ])
+}
@@ -0,0 +1,178 @@
+extern crate nalgebra_glm as glm;
+
+use glium::{glutin};
+
+use ecran;
+use evenements;
+use hud;
+use labyrinthe;
+use observateur;
+
+/*
+    Interface publique du module debogage
+
+    Sert à voir ce que fait le rendu, par exemple pour régler l'éclairage
+    F1: fil de fer, F2: normales, F3: une seule lumière (chaque appui passe à la suivante), F4: profondeur
+    F5: positions des lumières et leur case, F6: boîtes de collision des murs proches
+
+    Sauf pour une lumière isolée, les modes de rendu remplacent la couleur de la scène et désactivent le post-traitement
+*/
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum ModeRendu {
+
+    Normal,
+    FilDeFer,
+    Normales,
+    Lumiere(usize), // Seule la lumière à cette case de ecran::Lumieres éclaire la scène
+    Profondeur,
+}
+
+impl ModeRendu {
+
+    pub fn nom(self) -> String {
+
+        match self {
+            ModeRendu::Normal => "rendu normal".to_string(),
+            ModeRendu::FilDeFer => "fil de fer".to_string(),
+            ModeRendu::Normales => "normales".to_string(),
+            ModeRendu::Lumiere(case) => format!("lumière {}", case),
+            ModeRendu::Profondeur => "profondeur".to_string(),
+        }
+    }
+
+    // Valeur de mode_debogage dans scene.frag
+    pub fn code(self) -> i32 {
+
+        match self {
+            ModeRendu::Normal => 0,
+            ModeRendu::FilDeFer => 1,
+            ModeRendu::Normales => 2,
+            ModeRendu::Lumiere(_) => 3,
+            ModeRendu::Profondeur => 4,
+        }
+    }
+
+    // Les modes qui remplacent la couleur de la scène sont montrés sans effets;
+    // une lumière isolée garde l'exposition pour être comparée au rendu normal
+    pub fn sans_post_traitement(self) -> bool {
+
+        !matches!(self, ModeRendu::Normal | ModeRendu::Lumiere(_))
+    }
+
+    // Case de la lumière isolée, 0 si aucune
+    pub fn lumiere(self) -> i32 {
+
+        match self {
+            ModeRendu::Lumiere(case) => case as i32,
+            _ => 0,
+        }
+    }
+}
+
+pub struct Debogage {
+
+    pub mode: ModeRendu,
+    pub lumieres: bool, // Positions des lumières, avec leur case dans ecran::Lumieres
+    pub collisions: bool, // Murs autour de l'observateur, tels que vus par labyrinthe::Labyrinthe::deplacer
+
+    boites: std::vec::Vec<labyrinthe::BoiteCollision>,
+}
+
+impl Debogage {
+
+    pub fn new() -> Debogage {
+
+        Debogage {
+
+            mode: ModeRendu::Normal,
+            lumieres: false,
+            collisions: false,
+
+            boites: std::vec::Vec::new(),
+        }
+    }
+
+    // Lit les raccourcis et suit les murs autour de l'observateur
+    pub fn mettre_a_jour(&mut self,
+                    gestionnaire: &evenements::GestionnaireEvenements,
+                    labyrinthe: &labyrinthe::Labyrinthe,
+                    observateur: &observateur::Observateur,
+                    hud: &mut hud::Hud,
+                    affichage: &dyn glium::backend::Facade)
+    {
+        let clavier = &gestionnaire.clavier;
+        let ancien_mode = self.mode;
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::F1) {
+
+            // glPolygonMode n'existe pas avec OpenGL ES
+            if fil_de_fer_supporte(affichage) {
+                self.basculer_mode(ModeRendu::FilDeFer);
+            }
+            else {
+                hud.afficher_message("Le fil de fer n'est pas supporté avec OpenGL ES");
+            }
+        }
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::F2) {
+            self.basculer_mode(ModeRendu::Normales);
+        }
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::F3) {
+
+            // On passe d'une lumière à la suivante, puis on revient au rendu normal
+            self.mode = match self.mode {
+                ModeRendu::Lumiere(case) if case + 1 < ecran::NOMBRE_LUMIERES => ModeRendu::Lumiere(case + 1),
+                ModeRendu::Lumiere(_) => ModeRendu::Normal,
+                _ => ModeRendu::Lumiere(0),
+            };
+        }
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::F4) {
+            self.basculer_mode(ModeRendu::Profondeur);
+        }
+
+        if self.mode != ancien_mode {
+            hud.afficher_message(&format!("Débogage: {}", self.mode.nom()));
+        }
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::F5) {
+
+            self.lumieres = !self.lumieres;
+            hud.afficher_message(if self.lumieres { "Positions des lumières affichées" } else { "Positions des lumières cachées" });
+        }
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::F6) {
+
+            self.collisions = !self.collisions;
+            hud.afficher_message(if self.collisions { "Boîtes de collision affichées" } else { "Boîtes de collision cachées" });
+        }
+
+        if self.collisions {
+            let voisinage = glm::Vec3::new(1.0, 0.0, 1.0);
+            self.boites = labyrinthe.boites_collision(&(observateur.position - voisinage), &(observateur.position + voisinage));
+        }
+    }
+
+    // Vide si les boîtes de collision ne sont pas affichées
+    pub fn boites(&self) -> &[labyrinthe::BoiteCollision] {
+
+        if self.collisions {
+            &self.boites
+        }
+        else {
+            &[]
+        }
+    }
+
+    fn basculer_mode(&mut self, mode: ModeRendu) {
+
+        self.mode = if self.mode == mode { ModeRendu::Normal } else { mode };
+    }
+}
+
+pub fn fil_de_fer_supporte(affichage: &dyn glium::backend::Facade) -> bool {
+
+    affichage.get_context().get_opengl_version().0 != glium::Api::GlEs
+}
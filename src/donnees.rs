@@ -1,5 +1,8 @@
 extern crate nalgebra_glm as glm;
 
+use configuration;
+use materiaux;
+
 /*
     Interface publique du module donnees
 
@@ -38,7 +41,7 @@ impl DonneesOpenGL {
         coin_bas_gauche: [f32; 3],
         coin_haut_gauche: [f32; 3],
         coin_bas_droit: [f32; 3],
-        texture: [f32; 3]) // longueur, hauteur, id 
+        materiau: materiaux::IdMateriau)
         {
 
         let sommets_par_rangee = divisions[0] + 1;
@@ -56,9 +59,18 @@ impl DonneesOpenGL {
             coin_bas_droit[1] - coin_bas_gauche[1],
             coin_bas_droit[2] - coin_bas_gauche[2]];
 
+        // Les coordonnées de texture sont en unités de distance; le matériau les met à l'échelle
+        let dimensions_texture = [
+            glm::length(&glm::make_vec3(&longueur)),
+            glm::length(&glm::make_vec3(&largeur))];
+
         let normale = glm::normalize(&glm::cross(&glm::make_vec3(&longueur), &glm::make_vec3(&largeur)));
         let normale = [normale.x, normale.y, normale.z];
 
+        // La coordonnée x de la texture suit la longueur du plan
+        let tangente = glm::normalize(&glm::make_vec3(&longueur));
+        let tangente = [tangente.x, tangente.y, tangente.z];
+
         // Ajout des sommets
         for rangee in 0..sommets_par_colonne {
 
@@ -75,11 +87,12 @@ impl DonneesOpenGL {
                         coin_bas_gauche[2] + interpolation_colonne * longueur[2] + interpolation_rangee * largeur[2],
                         ],
                     normale: normale.clone(),
+                    tangente,
                     coordonnees_texture: [
-                        interpolation_colonne * texture[0],
-                        interpolation_rangee * texture[1],
-                        texture[2]
-                        ]
+                        interpolation_colonne * dimensions_texture[0],
+                        interpolation_rangee * dimensions_texture[1],
+                        ],
+                    materiau: materiau.index(),
                 });
             }
         }
@@ -99,8 +112,37 @@ impl DonneesOpenGL {
         }
     }
 
+    // Cette fonction crée une boîte tournée vers la direction, par exemple pour montrer l'observateur de l'extérieur
+    // pieds: centre de la base; seule la partie horizontale de la direction compte
+    pub fn ajouter_silhouette(&mut self, pieds: &glm::Vec3, direction: &glm::Vec3, hauteur: f32, materiau: materiaux::IdMateriau) {
+
+        const DEMI_LARGEUR: f32 = 0.2;
+        const DEMI_EPAISSEUR: f32 = 0.12;
+
+        let haut = glm::Vec3::new(0.0, 1.0, 0.0);
+        let avant = glm::Vec3::new(direction.x, 0.0, direction.z);
+        let avant = if avant.norm() > 1e-3 { avant.normalize() } else { glm::Vec3::new(0.0, 0.0, 1.0) };
+        let droite = haut.cross(&avant);
+
+        // Comme les axes x et z, droite et avant gardent l'orientation des faces
+        let coin = |x: f32, y: f32, z: f32| -> [f32; 3] {
+            let point = pieds + droite * x + haut * y + avant * z;
+            [point.x, point.y, point.z]
+        };
+
+        let (gauche, droit) = (-DEMI_LARGEUR, DEMI_LARGEUR);
+        let (arriere, devant) = (-DEMI_EPAISSEUR, DEMI_EPAISSEUR);
+
+        // Mêmes faces qu'un mur plein, voir labyrinthe::Cellule::ajouter_geometrie, et le dessus comme le sol
+        self.ajouter_plan([1, 4], coin(gauche, 0.0, devant), coin(gauche, hauteur, devant), coin(gauche, 0.0, arriere), materiau);
+        self.ajouter_plan([1, 4], coin(droit, 0.0, devant), coin(droit, hauteur, devant), coin(gauche, 0.0, devant), materiau);
+        self.ajouter_plan([1, 4], coin(droit, 0.0, arriere), coin(droit, hauteur, arriere), coin(droit, 0.0, devant), materiau);
+        self.ajouter_plan([1, 4], coin(gauche, 0.0, arriere), coin(gauche, hauteur, arriere), coin(droit, 0.0, arriere), materiau);
+        self.ajouter_plan([1, 1], coin(gauche, hauteur, arriere), coin(gauche, hauteur, devant), coin(droit, hauteur, arriere), materiau);
+    }
+
     // Cette fonction crée 4 triangles formant un tétraèdre
-    pub fn ajouter_torche(&mut self, position_flamme: [f32; 3], position_bas: [f32; 3], texture_id: f32) {
+    pub fn ajouter_torche(&mut self, position_flamme: [f32; 3], position_bas: [f32; 3], materiau: materiaux::IdMateriau) {
         
         let premier_sommet = self.sommets.len() as u32;
 
@@ -124,32 +166,45 @@ impl DonneesOpenGL {
         let normale = glm::normalize(&glm::cross(&arrete2, &arrete1));
         let normale = [normale.x, normale.y, normale.z];
 
+        // La coordonnée x de la texture va de coin1 à coin2
+        let tangente = glm::normalize(&glm::Vec3::new(
+            coin2[0] - coin1[0], coin2[1] - coin1[1], coin2[2] - coin1[2]));
+        let tangente = [tangente.x, tangente.y, tangente.z];
+
         // Bas de la torche
         self.sommets.push(Sommet{
             position: position_bas,
             normale: normale.clone(),
-            coordonnees_texture: [0.5, 0.0, texture_id],
+            tangente,
+            coordonnees_texture: [0.5, 0.0],
+            materiau: materiau.index(),
         });
 
         // gauche du devant
         self.sommets.push(Sommet{
             position: coin1,
             normale: normale.clone(),
-            coordonnees_texture: [0.0, 1.0, texture_id],
+            tangente,
+            coordonnees_texture: [0.0, 1.0],
+            materiau: materiau.index(),
         });
 
         // droite du devant
         self.sommets.push(Sommet{
             position: coin2,
             normale: normale.clone(),
-            coordonnees_texture: [1.0, 1.0, texture_id],
+            tangente,
+            coordonnees_texture: [1.0, 1.0],
+            materiau: materiau.index(),
         });
 
         // derrière de la torche
         self.sommets.push(Sommet{
             position: coin3,
             normale: normale.clone(),
-            coordonnees_texture: [0.5, 1.0, texture_id],
+            tangente,
+            coordonnees_texture: [0.5, 1.0],
+            materiau: materiau.index(),
         });
 
         self.indices.push(premier_sommet + 1); // Créer un triangle «dégénéré» avec le mode «trianglestrip»
@@ -163,7 +218,7 @@ impl DonneesOpenGL {
     }
 
     // Cette fonction est nécessaire pour appeler correctement obtenir_vertex_buffer
-    pub fn generer_vertex_buffer(&mut self, affichage: &glium::Display) {
+    pub fn generer_vertex_buffer(&mut self, affichage: &dyn glium::backend::Facade) {
 
         self.vertex_buffer = Some(glium::VertexBuffer::new(affichage, &(self.sommets)).unwrap());
     }
@@ -174,7 +229,7 @@ impl DonneesOpenGL {
         self.vertex_buffer.as_ref().unwrap()
     }
 
-    pub fn obtenir_indices(&self, affichage: &glium::Display) -> glium::index::IndexBuffer<u32> {
+    pub fn obtenir_indices(&self, affichage: &dyn glium::backend::Facade) -> glium::index::IndexBuffer<u32> {
 
         glium::index::IndexBuffer::new(
             affichage,
@@ -190,16 +245,18 @@ impl DonneesOpenGL {
 pub struct Sommet {
     position: [f32; 3],
     normale: [f32; 3],
-    coordonnees_texture: [f32; 3], // x,y et index
+    tangente: [f32; 3], // Direction de la coordonnée x de la texture, pour les cartes de normales
+    coordonnees_texture: [f32; 2],
+    materiau: u32, // Voir materiaux::IdMateriau
 }
 // Permet à Glium de l'utiliser avec OpenGL
-implement_vertex!(Sommet, position, normale, coordonnees_texture);
+implement_vertex!(Sommet, position, normale, tangente, coordonnees_texture, materiau);
 
 // Matrice importante qui sera appliquée sur tous les sommets
-pub fn matrice_camera_perspective(position: &glm::Vec3, direction: &glm::Vec3, ratio: f32) -> [[f32; 4]; 4] {
+pub fn matrice_camera_perspective(position: &glm::Vec3, direction: &glm::Vec3, ratio: f32, camera: &configuration::ParametresCamera) -> [[f32; 4]; 4] {
 
-    // Champ de vision de 90° et visibilité entre 0.01 et 100
-    let resultat =  matrice_perspective(std::f32::consts::PI * 0.5, ratio, 0.01, 100.0) *
+    // Visibilité entre le plan proche et le plan loin
+    let resultat =  matrice_perspective(camera.champ_vision.to_radians(), ratio, camera.plan_proche, camera.plan_loin) *
                     matrice_camera(*position, *direction);
 
     matrice_opengl(resultat)
@@ -218,7 +275,8 @@ fn matrice_camera(position: glm::Vec3, direction: glm::Vec3) -> glm::Mat4 {
     let direction = glm::normalize(&direction);
     let haut = glm::Vec3::new(0.0, 1.0, 0.0);// Le haut est l'axe des y positif avec OpenGL
 
-    let droite = haut.cross(&direction);
+    // Normalisé pour que le champ de vision ne change pas en regardant vers le haut ou le bas
+    let droite = glm::normalize(&haut.cross(&direction));
     let haut = direction.cross(&droite);
 
     
@@ -1,7 +1,18 @@
 extern crate nalgebra_glm as glm;
+extern crate image;
 use glium::{Surface}; // Surface est un trait et doit être importé
 
+use carte;
+use configuration;
+use debogage;
 use donnees;
+use hud;
+use materiaux;
+use menu;
+use particules;
+use post_traitement;
+use texte;
+use textures;
 
 /*
     Interface publique du module ecran
@@ -13,16 +24,45 @@ pub struct Vue {
 
     position: glm::Vec3,
     direction: glm::Vec3,
+    brouillard: f32, // Multiplie la densité du brouillard, voir changer_camera
+    pub parametres_camera: configuration::ParametresCamera,
+
+    pub echantillonnage: Echantillonnage,
+    texture_manquante_signalee: std::cell::Cell<bool>, // Évite de répéter l'erreur à chaque image
+
+    pub parametres_post_traitement: configuration::ParametresPostTraitement,
+    post_traitement: Option<post_traitement::PostTraitement>, // Créé au premier affichage
+    image_scene: Option<ImageScene>, // Sans post-traitement, si la résolution de la scène diffère de celle de la surface
+
+    police: texte::Police,
+
+    pub debogage: debogage::Debogage,
 }
 
 impl Vue {
 
-    pub fn new() -> Vue {
+    pub fn new(configuration: &configuration::Configuration) -> Vue {
 
         Vue {
 
             position: glm::Vec3::new(0.0, 0.0, 0.0),
             direction: glm::Vec3::new(0.0, 0.0, 1.0),
+            brouillard: 1.0,
+            parametres_camera: configuration.camera.clone(),
+
+            echantillonnage: Echantillonnage {
+                anisotropie: configuration.anisotropie,
+                .. Echantillonnage::new()
+            },
+            texture_manquante_signalee: std::cell::Cell::new(false),
+
+            parametres_post_traitement: configuration.post_traitement.clone(),
+            post_traitement: None,
+            image_scene: None,
+
+            police: texte::Police::charger(),
+
+            debogage: debogage::Debogage::new(),
         }
     }
 
@@ -32,12 +72,294 @@ impl Vue {
         self.direction = direction.clone();
     }
 
+    // Entre 0 et 1; vu de loin, par exemple de dessus, le brouillard cacherait tout le labyrinthe
+    pub fn attenuer_brouillard(&mut self, brouillard: f32) {
+
+        self.brouillard = brouillard;
+    }
+
     // Permet de tout dessiner sur la fenêtre
-    pub fn dessiner(&self,
+    pub fn dessiner(&mut self,
                     lumieres: Lumieres,
-                    donnees_opengl: &donnees::DonneesOpenGL,
+                    particules: &particules::SystemeParticules,
+                    scene: &Scene,
                     programme_opengl: &::shaders::ProgrammeOpenGL,
+                    interface: &Interface,
                     affichage: &glium::Display)
+    {
+        let dimensions = affichage.get_framebuffer_dimensions();
+
+        // Rien à dessiner si la fenêtre est réduite
+        if dimensions.0 == 0 || dimensions.1 == 0 {
+            return;
+        }
+
+        // affichage.draw() retourne un struct Frame, sur lequel on applique le post-traitement
+        let mut cadre = affichage.draw();
+        self.dessiner_sur(&mut cadre, lumieres, particules, scene, programme_opengl, affichage);
+        self.dessiner_debogage(&mut cadre, &lumieres, programme_opengl, affichage);
+        self.dessiner_interface(&mut cadre, interface, programme_opengl, affichage);
+
+        cadre.finish().unwrap(); // Dessine sur la fenêtre
+    }
+
+    // Dessine hors de l'écran et retourne l'image obtenue, par exemple pour une capture d'écran
+    // Sans interface, seule la scène est dessinée
+    pub fn dessiner_image(&mut self,
+                    lumieres: Lumieres,
+                    particules: &particules::SystemeParticules,
+                    scene: &Scene,
+                    programme_opengl: &::shaders::ProgrammeOpenGL,
+                    interface: Option<&Interface>,
+                    facade: &dyn glium::backend::Facade) -> image::RgbaImage
+    {
+        let (largeur, hauteur) = facade.get_context().get_framebuffer_dimensions();
+
+        let image_finale = glium::texture::Texture2d::empty_with_format(
+            facade,
+            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+            glium::texture::MipmapsOption::NoMipmap,
+            largeur.max(1),
+            hauteur.max(1)).unwrap();
+
+        // Le tampon de profondeur ne sert que si la scène est dessinée directement, voir dessiner_sur
+        // Avec le stencil, ce format est aussi accepté par glium avec OpenGL ES
+        let profondeur = glium::framebuffer::DepthStencilRenderBuffer::new(
+            facade,
+            glium::texture::DepthStencilFormat::I24I8,
+            largeur.max(1),
+            hauteur.max(1)).unwrap();
+
+        {
+            let mut cible = glium::framebuffer::SimpleFrameBuffer::with_depth_stencil_buffer(
+                facade, &image_finale, &profondeur).unwrap();
+            self.dessiner_sur(&mut cible, lumieres, particules, scene, programme_opengl, facade);
+            self.dessiner_debogage(&mut cible, &lumieres, programme_opengl, facade);
+
+            if let Some(interface) = interface {
+                self.dessiner_interface(&mut cible, interface, programme_opengl, facade);
+            }
+        }
+
+        let pixels: glium::texture::RawImage2d<u8> = image_finale.read();
+
+        // OpenGL commence par la ligne du bas, contrairement aux fichiers d'images
+        let image = image::RgbaImage::from_raw(pixels.width, pixels.height, pixels.data.into_owned()).unwrap();
+        image::imageops::flip_vertical(&image)
+    }
+
+    // La carte, le texte et le menu sont dessinés par-dessus l'image finale, sans effets
+    fn dessiner_interface<S: Surface>(&mut self,
+                    surface: &mut S,
+                    interface: &Interface,
+                    programme_opengl: &::shaders::ProgrammeOpenGL,
+                    facade: &dyn glium::backend::Facade)
+    {
+        interface.carte.dessiner(surface, &programme_opengl.programme_carte, facade);
+
+        let texte = interface.hud.generer_texte(surface.get_dimensions());
+        self.police.dessiner(surface, &texte, &programme_opengl.programme_texte, facade);
+
+        // Le menu couvre tout le reste
+        interface.menu.dessiner(surface, &programme_opengl.programme_carte, facade);
+
+        let texte = interface.menu.generer_texte(surface.get_dimensions());
+        self.police.dessiner(surface, &texte, &programme_opengl.programme_texte, facade);
+    }
+
+    // Positions des lumières et boîtes de collision, dessinées sans test de profondeur pour voir à travers les murs
+    fn dessiner_debogage<S: Surface>(&mut self,
+                    surface: &mut S,
+                    lumieres: &Lumieres,
+                    programme_opengl: &::shaders::ProgrammeOpenGL,
+                    facade: &dyn glium::backend::Facade)
+    {
+        let dimensions = surface.get_dimensions();
+        let matrice_camera_perspective = ::donnees::matrice_camera_perspective(
+            &self.position,
+            &self.direction,
+            Vue::obtenir_ratio_ecran(dimensions),
+            &self.parametres_camera);
+
+        let mut sommets = std::vec::Vec::new();
+        let mut texte = texte::Texte::new();
+
+        if self.debogage.lumieres {
+
+            const TAILLE_CROIX: f32 = 0.08;
+            let taille_texte = (dimensions.1 as f32 / 40.0).max(12.0);
+
+            for (case, (position, couleur)) in lumieres.positions.iter().zip(lumieres.couleurs.iter()).enumerate() {
+
+                // Les cases inutilisées sont à l'infini; la lumière de l'observateur est sur la caméra
+                let centre = [position[0], position[1], position[2]];
+                let (x, y) = match projeter(&matrice_camera_perspective, centre, dimensions, self.parametres_camera.plan_proche) {
+                    Some(point) if position[0] < INFINI => point,
+                    _ => continue,
+                };
+
+                // Les lumières peuvent être plus intenses que 1
+                let maximum = couleur[0].max(couleur[1]).max(couleur[2]).max(0.001);
+                let couleur = [couleur[0] / maximum, couleur[1] / maximum, couleur[2] / maximum, 1.0];
+
+                // Une croix alignée sur les axes, avec le numéro de la case
+                for axe in 0..3 {
+
+                    let mut debut = centre;
+                    let mut fin = centre;
+                    debut[axe] -= TAILLE_CROIX;
+                    fin[axe] += TAILLE_CROIX;
+
+                    sommets.push(SommetLigne { position: debut, couleur });
+                    sommets.push(SommetLigne { position: fin, couleur });
+                }
+
+                texte.ajouter(&case.to_string(), (x + taille_texte * 0.25, y + taille_texte * 0.25), taille_texte, couleur);
+            }
+        }
+
+        const COULEUR_BOITE: [f32; 4] = [1.0, 0.25, 0.2, 0.8];
+
+        for boite in self.debogage.boites() {
+
+            // Le bit 0 choisit le x, le bit 1 le y et le bit 2 le z de chaque coin
+            let coin = |i: usize| -> [f32; 3] {
+                [
+                    if i & 1 == 0 { boite.min[0] } else { boite.max[0] },
+                    if i & 2 == 0 { boite.min[1] } else { boite.max[1] },
+                    if i & 4 == 0 { boite.min[2] } else { boite.max[2] },
+                ]
+            };
+
+            // Les 12 arêtes relient les coins qui diffèrent d'un seul bit
+            for i in 0..8 {
+                for bit in [1, 2, 4].iter() {
+
+                    if i & bit == 0 {
+                        sommets.push(SommetLigne { position: coin(i), couleur: COULEUR_BOITE });
+                        sommets.push(SommetLigne { position: coin(i | bit), couleur: COULEUR_BOITE });
+                    }
+                }
+            }
+        }
+
+        if !sommets.is_empty() {
+
+            let donnees_globales = uniform! {
+                camera_perspective: matrice_camera_perspective,
+            };
+
+            let parametres = glium::DrawParameters {
+                blend: glium::Blend::alpha_blending(),
+                .. Default::default()
+            };
+
+            surface.draw(
+                &glium::VertexBuffer::new(facade, &sommets).unwrap(),
+                glium::index::NoIndices(glium::index::PrimitiveType::LinesList),
+                &programme_opengl.programme_lignes,
+                &donnees_globales,
+                &parametres,
+            ).unwrap();
+        }
+
+        self.police.dessiner(surface, &texte, &programme_opengl.programme_texte, facade);
+    }
+
+    fn dessiner_sur<S: Surface>(&mut self,
+                    surface: &mut S,
+                    lumieres: Lumieres,
+                    particules: &particules::SystemeParticules,
+                    scene: &Scene,
+                    programme_opengl: &::shaders::ProgrammeOpenGL,
+                    facade: &dyn glium::backend::Facade)
+    {
+        let dimensions = surface.get_dimensions();
+        let dimensions_scene = self.dimensions_scene(dimensions);
+
+        // Sans post-traitement, la scène est dessinée directement sur la surface
+        // Certains modes de débogage montrent aussi la sortie du shader sans effets
+        if !post_traitement::est_supporte(facade) || self.debogage.mode.sans_post_traitement() {
+
+            // Les images du post-traitement seront recréées au besoin; le gamma n'est alors plus appliqué
+            self.post_traitement = None;
+
+            if dimensions_scene == dimensions {
+
+                self.image_scene = None;
+                self.dessiner_scene(surface, lumieres, particules, scene, programme_opengl, facade);
+                return;
+            }
+
+            // Sinon, elle est dessinée à la bonne résolution puis étirée sur la surface
+            let doit_recreer = match self.image_scene {
+                Some(ref image_scene) => image_scene.dimensions != dimensions_scene,
+                None => true,
+            };
+
+            if doit_recreer {
+                self.image_scene = Some(ImageScene::new(facade, dimensions_scene));
+            }
+
+            let image_scene = self.image_scene.as_ref().unwrap();
+            {
+                let mut cible = glium::framebuffer::SimpleFrameBuffer::with_depth_stencil_buffer(
+                    facade, &image_scene.couleur, &image_scene.profondeur).unwrap();
+                self.dessiner_scene(&mut cible, lumieres, particules, scene, programme_opengl, facade);
+            }
+
+            // glium ne sait pas copier d'un tampon à l'autre avec OpenGL ES, l'image est donc dessinée
+            let donnees_copie = uniform! {
+                image: glium::uniforms::Sampler::new(&image_scene.couleur)
+                    .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear),
+            };
+
+            post_traitement::dessiner_ecran(surface, &programme_opengl.programme_copie, &donnees_copie);
+            return;
+        }
+
+        // Les images intermédiaires suivent la résolution de la scène; la composition les étire sur la surface
+        let echantillons = self.parametres_post_traitement.msaa;
+        let doit_recreer = match self.post_traitement {
+            Some(ref post_traitement) => post_traitement.dimensions() != dimensions_scene || post_traitement.echantillons() != echantillons,
+            None => true,
+        };
+
+        if doit_recreer {
+            self.post_traitement = Some(post_traitement::PostTraitement::new(facade, dimensions_scene, echantillons));
+        }
+
+        let post_traitement = self.post_traitement.as_ref().unwrap();
+
+        // La scène est d'abord dessinée en HDR, hors de l'écran
+        {
+            let mut cible = post_traitement.cible_scene(facade);
+            self.dessiner_scene(&mut cible, lumieres, particules, scene, programme_opengl, facade);
+        }
+
+        let mut parametres = self.parametres_post_traitement.clone();
+        parametres.densite_brouillard *= self.brouillard;
+
+        post_traitement.appliquer(surface, &parametres, &self.parametres_camera, programme_opengl, facade);
+    }
+
+    // Dimensions de l'image de la scène selon l'échelle de rendu, jamais nulles
+    fn dimensions_scene(&self, dimensions: (u32, u32)) -> (u32, u32) {
+
+        let echelle = self.parametres_camera.echelle_rendu;
+        let mettre_a_l_echelle = |longueur: u32| ((longueur as f32 * echelle).round() as u32).max(1);
+
+        (mettre_a_l_echelle(dimensions.0), mettre_a_l_echelle(dimensions.1))
+    }
+
+    fn dessiner_scene<S: Surface>(&self,
+                    surface: &mut S,
+                    lumieres: Lumieres,
+                    particules: &particules::SystemeParticules,
+                    scene: &Scene,
+                    programme_opengl: &::shaders::ProgrammeOpenGL,
+                    affichage: &dyn glium::backend::Facade)
     {
         let mut tampon_lumieres: glium::uniforms::UniformBuffer<Lumieres> =
             glium::uniforms::UniformBuffer::empty(affichage).unwrap();
@@ -64,12 +386,9 @@ impl Vue {
         let matrice_camera_perspective = ::donnees::matrice_camera_perspective(
             &self.position,
             &self.direction,
-            Vue::obtenir_ratio_ecran(&affichage));
+            Vue::obtenir_ratio_ecran(surface.get_dimensions()),
+            &self.parametres_camera);
         
-         // affichage.draw() retourne un struct Frame, sur lequel on peut mettre à jour les tampons de couleur et de profondeur
-         // Une fois les tampons remplis, on peut dessiner le tout
-         let mut cadre = affichage.draw();
-
         /* ------------------------------------------------
             Les commandes ci-dessous permettent de calculer la profondeur avant de dessiner dans le tampon de couleur
             Cette technique améliore les performances du fragment shader qui s'éxécutera par après
@@ -80,7 +399,9 @@ impl Vue {
             camera_perspective: matrice_camera_perspective
         };
 
-        cadre.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+        surface.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+
+        let fil_de_fer = self.debogage.mode == debogage::ModeRendu::FilDeFer;
         
         let parametres_prepasse = glium::DrawParameters {
             depth: glium::Depth { // Permet de tenir compte de la profondeur
@@ -88,13 +409,14 @@ impl Vue {
                 write: true,
                 .. Default::default()
             },
+            color_mask: (false, false, false, false), // Le shader n'a pas de sortie; en fil de fer, la couleur resterait indéfinie
             backface_culling: glium::draw_parameters::BackfaceCullingMode::CullClockwise,
             .. Default::default()
         };
 
-        cadre.draw(
-            donnees_opengl.obtenir_vertex_buffer(),
-            &donnees_opengl.obtenir_indices(&affichage),
+        surface.draw(
+            scene.donnees_opengl.obtenir_vertex_buffer(),
+            &scene.donnees_opengl.obtenir_indices(affichage),
             &programme_opengl.programme_prepasse,
             &donnees_globales_prepasse,
             &parametres_prepasse,
@@ -105,40 +427,225 @@ impl Vue {
             Les commandes ci-dessous permettent de tout dessiner
         ------------------------------------------------ */
 
+        // Sans textures, on dessine un damier bien visible plutôt que de planter
+        let texture_manquante;
+        let tableau_textures = match scene.textures.obtenir_textures() {
+
+            Some(tableau_textures) => tableau_textures,
+            None => {
+
+                if !self.texture_manquante_signalee.replace(true) {
+                    println!("\n\nErreur: les textures n'ont pas été générées avant de dessiner.");
+                    println!("Voir Textures::generer_textures\n");
+                }
+
+                texture_manquante = textures::generer_texture_manquante(affichage);
+                &texture_manquante
+            }
+        };
+
         // Données globales à envoyer, vers le bloc uniform
-        let donnees_globales = uniform! {
-            camera_perspective: matrice_camera_perspective,
-            direction_regard: [self.direction.x, self.direction.y, self.direction.z],
-            lumieres: &*tampon_lumieres,
+        let donnees_globales = UniformesScene {
+
+            uniformes: uniform! {
+                camera_perspective: matrice_camera_perspective,
+                direction_regard: [self.direction.x, self.direction.y, self.direction.z],
+                lumieres: &*tampon_lumieres,
+                materiaux: scene.materiaux.obtenir_tampon(),
+                espace_lineaire: self.parametres_post_traitement.gamma && self.post_traitement.is_some(), // Le gamma est appliqué au post-traitement
+                textures: self.echantillonnage.echantillonner(tableau_textures),
+                mode_debogage: self.debogage.mode.code(),
+                lumiere_debogage: self.debogage.mode.lumiere(),
+                plan_proche: self.parametres_camera.plan_proche,
+                plan_loin: self.parametres_camera.plan_loin,
+            },
+
+            tableaux: if programme_opengl.tableaux_uniformes {
+                Some((&lumieres, scene.materiaux.obtenir_donnees()))
+            }
+            else {
+                None
+            },
         };
 
-        let parametres = glium::DrawParameters {
-            depth: glium::Depth {
-                test: glium::draw_parameters::DepthTest::IfEqual, // Permet d'utiliser la profondeur déjà calculée
-                write: false, // On n'a donc pas besoin d'écrire dans le depth buffer
+        let parametres = if fil_de_fer {
+
+            // Les arêtes sont légèrement rapprochées pour ne pas être cachées par les faces de la prépasse
+            glium::DrawParameters {
+                depth: glium::Depth {
+                    test: glium::draw_parameters::DepthTest::IfLessOrEqual,
+                    write: false,
+                    range: (0.0, 0.99998),
+                    .. Default::default()
+                },
+                backface_culling: glium::draw_parameters::BackfaceCullingMode::CullClockwise,
+                polygon_mode: glium::draw_parameters::PolygonMode::Line, // Voir debogage::fil_de_fer_supporte
                 .. Default::default()
-            },
-            backface_culling: glium::draw_parameters::BackfaceCullingMode::CullClockwise,
-            .. Default::default()
+            }
+        }
+        else {
+
+            glium::DrawParameters {
+                depth: glium::Depth {
+                    test: glium::draw_parameters::DepthTest::IfEqual, // Permet d'utiliser la profondeur déjà calculée
+                    write: false, // On n'a donc pas besoin d'écrire dans le depth buffer
+                    .. Default::default()
+                },
+                backface_culling: glium::draw_parameters::BackfaceCullingMode::CullClockwise,
+                .. Default::default()
+            }
         };
 
-        cadre.draw(
-            donnees_opengl.obtenir_vertex_buffer(),
-            &donnees_opengl.obtenir_indices(&affichage),
+        surface.draw(
+            scene.donnees_opengl.obtenir_vertex_buffer(),
+            &scene.donnees_opengl.obtenir_indices(affichage),
             &programme_opengl.programme,
             &donnees_globales,
             &parametres,
         ).unwrap(); // Mets à jour le tampon de couleur
-        
-        cadre.finish().unwrap(); // Dessine sur la fenêtre
+
+        // La silhouette n'est pas dans la prépasse: elle est dessinée et cachée par les murs normalement
+        if let Some(silhouette) = scene.silhouette {
+
+            let parametres_silhouette = glium::DrawParameters {
+                depth: glium::Depth {
+                    test: glium::draw_parameters::DepthTest::IfLess,
+                    write: true,
+                    .. Default::default()
+                },
+                backface_culling: glium::draw_parameters::BackfaceCullingMode::CullClockwise,
+                polygon_mode: parametres.polygon_mode,
+                .. Default::default()
+            };
+
+            surface.draw(
+                silhouette.obtenir_vertex_buffer(),
+                &silhouette.obtenir_indices(affichage),
+                &programme_opengl.programme,
+                &donnees_globales,
+                &parametres_silhouette,
+            ).unwrap();
+        }
+
+
+        /* ------------------------------------------------
+            Les commandes ci-dessous permettent de dessiner les flammes par-dessus la scène
+        ------------------------------------------------ */
+
+        let (sommets_particules, indices_particules) = particules.generer_sommets();
+
+        // Les flammes cacheraient ce que montrent les modes de débogage
+        if !sommets_particules.is_empty() && self.debogage.mode == debogage::ModeRendu::Normal {
+
+            // Les particules font face à la caméra, on a donc besoin de ses axes
+            let droite = glm::normalize(&glm::Vec3::new(0.0, 1.0, 0.0).cross(&self.direction));
+            let haut = self.direction.cross(&droite);
+
+            let donnees_globales_particules = uniform! {
+                camera_perspective: matrice_camera_perspective,
+                droite_camera: [droite.x, droite.y, droite.z],
+                haut_camera: [haut.x, haut.y, haut.z],
+            };
+
+            let parametres_particules = glium::DrawParameters {
+                depth: glium::Depth {
+                    test: glium::draw_parameters::DepthTest::IfLess, // Les murs cachent les flammes
+                    write: false, // Les particules sont transparentes
+                    .. Default::default()
+                },
+                blend: glium::Blend { // Mélange additif, la lumière des flammes s'accumule
+                    color: glium::BlendingFunction::Addition {
+                        source: glium::LinearBlendingFactor::SourceAlpha,
+                        destination: glium::LinearBlendingFactor::One,
+                    },
+                    alpha: glium::BlendingFunction::Addition {
+                        source: glium::LinearBlendingFactor::Zero,
+                        destination: glium::LinearBlendingFactor::One,
+                    },
+                    constant_value: (0.0, 0.0, 0.0, 0.0),
+                },
+                .. Default::default()
+            };
+
+            surface.draw(
+                &glium::VertexBuffer::new(affichage, &sommets_particules).unwrap(),
+                &glium::index::IndexBuffer::new(
+                    affichage,
+                    glium::index::PrimitiveType::TrianglesList,
+                    &indices_particules).unwrap(),
+                &programme_opengl.programme_particules,
+                &donnees_globales_particules,
+                &parametres_particules,
+            ).unwrap();
+        }
     }
 
     // Donne le ratio largeur / hauteur de l'écran
-    fn obtenir_ratio_ecran(affichage: &glium::Display) -> f32 {
+    fn obtenir_ratio_ecran(dimensions: (u32, u32)) -> f32 {
+
+        let (largeur, hauteur) = dimensions;
 
-        let fenetre = affichage.gl_window().window().inner_size();
+        // Une fenêtre réduite peut avoir une dimension nulle; la matrice de perspective doit rester valide
+        if largeur == 0 || hauteur == 0 {
+            return 1.0;
+        }
 
-        return (fenetre.width / fenetre.height) as f32;
+        largeur as f32 / hauteur as f32
+    }
+}
+
+
+// Données de la scène; seule la silhouette change d'une image à l'autre
+pub struct Scene<'a> {
+
+    pub donnees_opengl: &'a donnees::DonneesOpenGL,
+    pub textures: &'a textures::Textures,
+    pub materiaux: &'a materiaux::Materiaux,
+    pub silhouette: Option<&'a donnees::DonneesOpenGL>, // L'observateur vu de l'extérieur, voir camera::Camera::montre_observateur
+}
+
+// Éléments dessinés par-dessus la scène
+pub struct Interface<'a> {
+
+    pub carte: &'a carte::Carte,
+    pub hud: &'a hud::Hud,
+    pub menu: &'a menu::Menu,
+}
+
+// Paramètres d'échantillonnage des textures
+pub struct Echantillonnage {
+
+    pub mipmaps: bool, // Filtrage trilinéaire entre les niveaux de mipmaps
+    pub anisotropie: u16, // 1 désactive le filtrage anisotrope
+    pub repetition: glium::uniforms::SamplerWrapFunction,
+}
+
+impl Echantillonnage {
+
+    pub fn new() -> Echantillonnage {
+
+        Echantillonnage {
+
+            mipmaps: true,
+            anisotropie: 8,
+            repetition: glium::uniforms::SamplerWrapFunction::Repeat,
+        }
+    }
+
+    fn echantillonner<'t, T>(&self, texture: &'t T) -> glium::uniforms::Sampler<'t, T> {
+
+        let filtre_reduction = if self.mipmaps {
+            glium::uniforms::MinifySamplerFilter::LinearMipmapLinear
+        }
+        else {
+            glium::uniforms::MinifySamplerFilter::Linear
+        };
+
+        glium::uniforms::Sampler::new(texture)
+            .minify_filter(filtre_reduction)
+            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
+            .anisotropy(self.anisotropie)
+            .wrap_function(self.repetition)
     }
 }
 
@@ -147,6 +654,68 @@ impl Vue {
     Partie privée du module ecran
 */
 
+// Image de la scène à une autre résolution que la surface, lorsqu'il n'y a pas de post-traitement
+// Avec le stencil, ce format de profondeur est aussi accepté par glium avec OpenGL ES
+struct ImageScene {
+
+    dimensions: (u32, u32),
+    couleur: glium::texture::Texture2d,
+    profondeur: glium::framebuffer::DepthStencilRenderBuffer,
+}
+
+impl ImageScene {
+
+    fn new(facade: &dyn glium::backend::Facade, dimensions: (u32, u32)) -> ImageScene {
+
+        let (largeur, hauteur) = dimensions;
+
+        ImageScene {
+
+            dimensions,
+            couleur: glium::texture::Texture2d::empty_with_format(
+                facade,
+                glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                glium::texture::MipmapsOption::NoMipmap,
+                largeur,
+                hauteur).unwrap(),
+            profondeur: glium::framebuffer::DepthStencilRenderBuffer::new(
+                facade,
+                glium::texture::DepthStencilFormat::I24I8,
+                largeur,
+                hauteur).unwrap(),
+        }
+    }
+}
+
+// Position en pixels d'un point de la scène, à partir du coin inférieur gauche de l'écran
+// None si le point est derrière le plan proche
+fn projeter(matrice: &[[f32; 4]; 4], point: [f32; 3], dimensions: (u32, u32), plan_proche: f32) -> Option<(f32, f32)> {
+
+    // La matrice est rangée par colonnes, comme pour OpenGL
+    let composante = |ligne: usize| {
+        matrice[0][ligne] * point[0] + matrice[1][ligne] * point[1] + matrice[2][ligne] * point[2] + matrice[3][ligne]
+    };
+
+    let w = composante(3);
+    if w <= plan_proche {
+        return None;
+    }
+
+    Some((
+        (composante(0) / w + 1.0) / 2.0 * dimensions.0 as f32,
+        (composante(1) / w + 1.0) / 2.0 * dimensions.1 as f32,
+    ))
+}
+
+// Primitive pour OpenGL
+#[derive(Copy, Clone)]
+struct SommetLigne {
+    position: [f32; 3],
+    couleur: [f32; 4],
+}
+// Permet à Glium de l'utiliser avec OpenGL
+implement_vertex!(SommetLigne, position, couleur);
+
 const INFINI: f32 = 1000000.0;
 pub const NOMBRE_LUMIERES: usize = 8;
 
@@ -158,7 +727,28 @@ pub struct Lumieres {
     pub couleurs: [[f32; 4]; NOMBRE_LUMIERES],
 }
 
-implement_uniform_block!(Lumieres, positions, couleurs);
+implementer_bloc_uniforme!(Lumieres, positions, couleurs);
+
+// Ajoute les lumières et les matériaux élément par élément, voir ProgrammeOpenGL::tableaux_uniformes
+struct UniformesScene<'b, U: glium::uniforms::Uniforms> {
+
+    uniformes: U,
+    tableaux: Option<(&'b Lumieres, &'b materiaux::TamponMateriaux)>,
+}
+
+impl<'b, U: glium::uniforms::Uniforms> glium::uniforms::Uniforms for UniformesScene<'b, U> {
+
+    fn visit_values<'a, F: FnMut(&str, glium::uniforms::UniformValue<'a>)>(&'a self, mut visiteur: F) {
+
+        self.uniformes.visit_values(&mut visiteur);
+
+        if let Some((lumieres, materiaux)) = self.tableaux {
+
+            lumieres.visiter_tableaux(&mut visiteur);
+            materiaux.visiter_tableaux(&mut visiteur);
+        }
+    }
+}
 
 impl Lumieres {
 
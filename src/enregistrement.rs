@@ -0,0 +1,539 @@
+extern crate nalgebra_glm as glm;
+
+use aleatoire;
+use commandes;
+use configuration;
+use evenements;
+use labyrinthe;
+use manette;
+use observateur;
+
+/*
+    Interface publique du module enregistrement
+
+    Sert à enregistrer les événements reçus par evenements::GestionnaireEvenements pendant une partie (--record),
+    puis à les rejouer sans fenêtre en reproduisant la trajectoire de l'observateur (--replay)
+
+    Le fichier est du texte, une ligne par information: d'abord la configuration utilisée, puis pour chaque image
+    sa durée, la pause et le survol, les événements dans l'ordre reçu, ce qui a changé (labyrinthe, réglages du regard,
+    touches, manette), puis la position et la direction du regard de l'observateur à la fin de l'image
+
+    Le déplacement avance par pas fixes (voir observateur::Observateur::simuler): avec les mêmes durées d'image
+    et les mêmes événements, la trajectoire est identique. Le menu n'est pas rejoué, seulement ses effets
+*/
+
+// Ce qui est connu de l'image avant ses événements
+#[derive(Clone, Copy)]
+pub struct Image {
+
+    pub delta: f32, // Durée de l'image, en secondes
+    pub pause: bool, // Menu ouvert
+    pub survol: bool, // L'observateur attend la fin du survol
+}
+
+pub struct Enregistreur {
+
+    fichier: std::io::BufWriter<std::fs::File>,
+
+    // Dernières valeurs écrites; None pour les écrire à la prochaine image
+    labyrinthe: Option<String>, // Ligne à écrire, générée depuis la dernière image
+    regard: Option<(f32, bool, f32)>,
+    associations: Option<commandes::Associations>,
+    manette: Option<manette::EtatManette>,
+}
+
+impl Enregistreur {
+
+    // configuration: contenu du fichier de configuration, relu au rejeu
+    pub fn creer(chemin: &str, configuration: &str) -> std::io::Result<Enregistreur> {
+
+        use std::io::Write;
+
+        let mut fichier = std::io::BufWriter::new(std::fs::File::create(chemin)?);
+
+        writeln!(fichier, "{}", EN_TETE)?;
+
+        for ligne in configuration.lines() {
+            writeln!(fichier, "configuration {}", ligne)?;
+        }
+
+        Ok(Enregistreur {
+            fichier,
+
+            labyrinthe: None,
+            regard: None,
+            associations: None,
+            manette: None,
+        })
+    }
+
+    // Le générateur doit avoir été initialisé avec la graine juste avant (voir aleatoire::initialiser)
+    pub fn nouveau_labyrinthe(&mut self, graine: u64, longueur: u32, largeur: u32, algorithme: labyrinthe::Algorithme) {
+
+        self.labyrinthe = Some(format!("labyrinthe {} {} {} {}", graine, longueur, largeur, algorithme.nom()));
+    }
+
+    // À la fin de la logique de l'image, avant evenements::GestionnaireEvenements::mise_a_jour_post_logique
+    // Le fichier est vidé à chaque image: un plantage ne perd pas ce qui précède
+    pub fn enregistrer_image(&mut self,
+                    image: &Image,
+                    gestionnaire_evenements: &evenements::GestionnaireEvenements,
+                    observateur: &observateur::Observateur) -> std::io::Result<()>
+    {
+        use std::io::Write;
+
+        let fichier = &mut self.fichier;
+
+        writeln!(fichier, "image {} {} {}", image.delta, image.pause as u8, image.survol as u8)?;
+
+        for evenement in gestionnaire_evenements.evenements().iter() {
+            writeln!(fichier, "{}", ecrire_evenement(evenement))?;
+        }
+
+        if let Some(labyrinthe) = self.labyrinthe.take() {
+            writeln!(fichier, "{}", labyrinthe)?;
+        }
+
+        let regard = (observateur.sensibilite, observateur.inverser_y, observateur.lissage);
+
+        if self.regard != Some(regard) {
+
+            writeln!(fichier, "regard {} {} {}", regard.0, regard.1 as u8, regard.2)?;
+            self.regard = Some(regard);
+        }
+
+        let associations = &gestionnaire_evenements.associations;
+
+        if self.associations.as_ref() != Some(associations) {
+
+            for ligne in associations.contenu().lines() {
+                writeln!(fichier, "touches {}", ligne)?;
+            }
+
+            self.associations = Some(associations.clone());
+        }
+
+        let etat_manette = gestionnaire_evenements.manette.etat_brut();
+
+        if self.manette != Some(etat_manette) {
+
+            writeln!(fichier, "manette {}", etat_manette.texte())?;
+            self.manette = Some(etat_manette);
+        }
+
+        let position = observateur.position;
+        writeln!(fichier, "position {} {} {}", position.x, position.y, position.z)?;
+
+        let direction = observateur.direction();
+        writeln!(fichier, "direction {} {} {}", direction.x, direction.y, direction.z)?;
+
+        fichier.flush()
+    }
+}
+
+pub struct Rejeu {
+
+    chemin: String,
+    configuration: String, // Contenu du fichier de configuration enregistré
+    images: std::vec::Vec<ImageEnregistree>,
+}
+
+impl Rejeu {
+
+    pub fn charger(chemin: &str) -> Result<Rejeu, String> {
+
+        let contenu = std::fs::read_to_string(chemin).map_err(|erreur| erreur.to_string())?;
+        let mut lignes = contenu.lines().enumerate();
+
+        match lignes.next() {
+            Some((_, EN_TETE)) => (),
+            _ => return Err(format!("la première ligne doit être «{}»", EN_TETE)),
+        }
+
+        let mut configuration = String::new();
+        let mut images: std::vec::Vec<ImageEnregistree> = std::vec::Vec::new();
+
+        for (numero, ligne) in lignes {
+
+            let ligne = ligne.trim_end();
+
+            if ligne.is_empty() {
+                continue;
+            }
+
+            let (mot, reste) = ligne.split_once(' ').unwrap_or((ligne, ""));
+
+            // La configuration précède toutes les images
+            if mot == "configuration" && images.is_empty() {
+
+                configuration += reste;
+                configuration += "\n";
+                continue;
+            }
+
+            let resultat = match images.last_mut() {
+                _ if mot == "image" => lire_image(reste).map(|image| images.push(image)),
+                Some(image) => image.lire_ligne(mot, reste),
+                None => Err("une image est attendue".to_string()),
+            };
+
+            resultat.map_err(|erreur| format!("ligne {}: {}", numero + 1, erreur))?;
+        }
+
+        Ok(Rejeu {
+            chemin: chemin.to_string(),
+            configuration,
+            images,
+        })
+    }
+
+    pub fn configuration(&self) -> &str {
+
+        &self.configuration
+    }
+
+    // Rejoue toutes les images comme dans la boucle principale, et retourne la position finale
+    // verifier: échoue à la première position ou direction du regard qui diffère de celle enregistrée
+    // enregistreur: enregistre de nouveau, par exemple pour mettre à jour les positions
+    pub fn rejouer(&self, verifier: bool, mut enregistreur: Option<&mut Enregistreur>) -> Result<glm::Vec3, String> {
+
+        const TOLERANCE: f32 = 1e-4;
+
+        let configuration = configuration::Configuration::analyser(&self.configuration, &self.chemin);
+
+        // La manette donne l'état enregistré, une fois par image comme une vraie
+        let etat_manette = std::rc::Rc::new(std::cell::Cell::new(manette::EtatManette::new()));
+        let source = SourceRejouee {
+            etat: etat_manette.clone(),
+        };
+
+        let manette = manette::Manette::new(Box::new(source), &configuration.manette);
+        let mut gestionnaire_evenements = evenements::GestionnaireEvenements::new(manette);
+
+        let depart = glm::Vec3::new(observateur::POSITION_DEPART[0], observateur::POSITION_DEPART[1], observateur::POSITION_DEPART[2]);
+        let mut observateur = observateur::Observateur::new(depart, glm::Vec3::new(0.0, 0.0, 1.0));
+
+        if configuration.mode_aerien {
+            observateur.basculer_mode_aerien();
+        }
+
+        let mut labyrinthe: Option<labyrinthe::Labyrinthe> = None;
+        let mut temps_a_simuler = 0.0;
+
+        for (numero, image) in self.images.iter().enumerate() {
+
+            for evenement in image.evenements.iter() {
+                gestionnaire_evenements.traiter(*evenement);
+            }
+
+            if let Some(etat) = image.manette {
+                etat_manette.set(etat);
+            }
+
+            gestionnaire_evenements.mise_a_jour_pre_logique();
+
+            // Les changements faits par le menu, dans le même ordre que dans la boucle principale
+            if let Some((graine, longueur, largeur, algorithme)) = image.labyrinthe {
+
+                aleatoire::initialiser(graine);
+                labyrinthe = Some(labyrinthe::Labyrinthe::new(longueur, largeur, algorithme));
+
+                if numero > 0 {
+                    observateur.placer(depart);
+                }
+
+                if let Some(ref mut enregistreur) = enregistreur {
+                    enregistreur.nouveau_labyrinthe(graine, longueur, largeur, algorithme);
+                }
+            }
+
+            if let Some((sensibilite, inverser_y, lissage)) = image.regard {
+
+                observateur.sensibilite = sensibilite;
+                observateur.inverser_y = inverser_y;
+                observateur.lissage = lissage;
+            }
+
+            if let Some(ref touches) = image.touches {
+                gestionnaire_evenements.associations = commandes::Associations::analyser(touches, &self.chemin);
+            }
+
+            let labyrinthe = labyrinthe.as_ref().ok_or_else(|| format!("image {}: aucun labyrinthe n'a été généré", numero))?;
+
+            if !image.image.pause {
+
+                if image.image.survol {
+                    temps_a_simuler = 0.0;
+                }
+                else {
+                    observateur.simuler(&gestionnaire_evenements, labyrinthe, image.image.delta, &mut temps_a_simuler);
+                }
+
+                if gestionnaire_evenements.commande_declenchee(commandes::Commande::ModeAerien) {
+                    observateur.basculer_mode_aerien();
+                }
+            }
+
+            if let (true, Some(attendue)) = (verifier, image.position) {
+
+                if glm::distance(&attendue, &observateur.position) > TOLERANCE {
+
+                    let position = observateur.position;
+                    return Err(format!("image {}: l'observateur est à ({}, {}, {}) au lieu de ({}, {}, {})",
+                        numero, position.x, position.y, position.z, attendue.x, attendue.y, attendue.z));
+                }
+            }
+
+            if let (true, Some(attendue)) = (verifier, image.direction) {
+
+                if glm::distance(&attendue, observateur.direction()) > TOLERANCE {
+
+                    let direction = observateur.direction();
+                    return Err(format!("image {}: l'observateur regarde vers ({}, {}, {}) au lieu de ({}, {}, {})",
+                        numero, direction.x, direction.y, direction.z, attendue.x, attendue.y, attendue.z));
+                }
+            }
+
+            if let Some(ref mut enregistreur) = enregistreur {
+                enregistreur.enregistrer_image(&image.image, &gestionnaire_evenements, &observateur).map_err(|erreur| erreur.to_string())?;
+            }
+
+            gestionnaire_evenements.mise_a_jour_post_logique();
+        }
+
+        Ok(observateur.position)
+    }
+
+    pub fn nombre_images(&self) -> usize {
+
+        self.images.len()
+    }
+}
+
+
+
+
+
+/*
+    Partie privée du module enregistrement
+*/
+
+const EN_TETE: &str = "enregistrement_labyrinthe 1";
+
+struct ImageEnregistree {
+
+    image: Image,
+    evenements: std::vec::Vec<evenements::Evenement>,
+
+    // Changements, appliqués après les événements
+    labyrinthe: Option<(u64, u32, u32, labyrinthe::Algorithme)>, // Graine, longueur, largeur, algorithme
+    regard: Option<(f32, bool, f32)>, // Sensibilité, inversion de l'axe vertical, lissage
+    touches: Option<String>, // Lignes du fichier de touches
+    manette: Option<manette::EtatManette>,
+
+    position: Option<glm::Vec3>, // À la fin de l'image
+    direction: Option<glm::Vec3>,
+}
+
+impl ImageEnregistree {
+
+    fn lire_ligne(&mut self, mot: &str, reste: &str) -> Result<(), String> {
+
+        match mot {
+
+            "labyrinthe" => {
+
+                let morceaux: std::vec::Vec<&str> = reste.split_whitespace().collect();
+                let erreur = || format!("«{}» n'a pas la forme graine longueur largeur algorithme", reste);
+
+                if morceaux.len() != 4 {
+                    return Err(erreur());
+                }
+
+                let graine = morceaux[0].parse::<u64>().map_err(|_| erreur())?;
+                let longueur = morceaux[1].parse::<u32>().map_err(|_| erreur())?;
+                let largeur = morceaux[2].parse::<u32>().map_err(|_| erreur())?;
+                let algorithme = lire_algorithme(morceaux[3])?;
+
+                self.labyrinthe = Some((graine, longueur, largeur, algorithme));
+            },
+
+            "regard" => {
+
+                let valeurs = lire_nombres(reste, 3)?;
+                self.regard = Some((valeurs[0] as f32, valeurs[1] != 0.0, valeurs[2] as f32));
+            },
+
+            "touches" => {
+
+                let touches = self.touches.get_or_insert_with(String::new);
+                *touches += reste;
+                *touches += "\n";
+            },
+
+            "manette" => self.manette = Some(manette::EtatManette::lire(reste)?),
+
+            "position" => {
+
+                let valeurs = lire_nombres(reste, 3)?;
+                self.position = Some(glm::Vec3::new(valeurs[0] as f32, valeurs[1] as f32, valeurs[2] as f32));
+            },
+
+            "direction" => {
+
+                let valeurs = lire_nombres(reste, 3)?;
+                self.direction = Some(glm::Vec3::new(valeurs[0] as f32, valeurs[1] as f32, valeurs[2] as f32));
+            },
+
+            _ => self.evenements.push(lire_evenement(mot, reste)?),
+        }
+
+        Ok(())
+    }
+}
+
+// Remplace la manette pendant le rejeu
+struct SourceRejouee {
+
+    etat: std::rc::Rc<std::cell::Cell<manette::EtatManette>>,
+}
+
+impl manette::Source for SourceRejouee {
+
+    fn lire(&mut self) -> manette::EtatManette {
+
+        self.etat.get()
+    }
+}
+
+fn lire_image(reste: &str) -> Result<ImageEnregistree, String> {
+
+    let valeurs = lire_nombres(reste, 3)?;
+
+    Ok(ImageEnregistree {
+
+        image: Image {
+            delta: valeurs[0] as f32,
+            pause: valeurs[1] != 0.0,
+            survol: valeurs[2] != 0.0,
+        },
+        evenements: std::vec::Vec::new(),
+
+        labyrinthe: None,
+        regard: None,
+        touches: None,
+        manette: None,
+
+        position: None,
+        direction: None,
+    })
+}
+
+// Les nombres sont lus en f64, qui représente exactement les f32 écrits
+fn lire_nombres(texte: &str, nombre: usize) -> Result<std::vec::Vec<f64>, String> {
+
+    let valeurs = texte.split_whitespace()
+        .map(|valeur| valeur.parse::<f64>())
+        .collect::<Result<std::vec::Vec<f64>, _>>()
+        .map_err(|_| format!("«{}» contient une valeur qui n'est pas un nombre", texte))?;
+
+    if valeurs.len() != nombre {
+        return Err(format!("{} valeurs sont attendues dans «{}»", nombre, texte));
+    }
+
+    Ok(valeurs)
+}
+
+fn lire_algorithme(nom: &str) -> Result<labyrinthe::Algorithme, String> {
+
+    for algorithme in labyrinthe::Algorithme::TOUS.iter() {
+
+        if algorithme.nom() == nom {
+            return Ok(*algorithme);
+        }
+    }
+
+    Err(format!("l'algorithme «{}» est inconnu", nom))
+}
+
+// Une touche est nommée comme dans le fichier de touches; «-» si glutin ne la nomme pas
+fn ecrire_evenement(evenement: &evenements::Evenement) -> String {
+
+    match *evenement {
+
+        evenements::Evenement::Touche{code, touche, appuyee} => {
+
+            let nom = match touche {
+                Some(touche) => commandes::Entree::Touche(touche).nom(),
+                None => "-".to_string(),
+            };
+
+            format!("touche {} {} {}", code, nom, appuyee as u8)
+        },
+
+        evenements::Evenement::BoutonSouris{bouton, appuye} => format!("souris {} {}", commandes::Entree::Souris(bouton).nom(), appuye as u8),
+        evenements::Evenement::Curseur(x, y) => format!("curseur {} {}", x, y),
+        evenements::Evenement::Mouvement(x, y) => format!("mouvement {} {}", x, y),
+        evenements::Evenement::Caractere(caractere) => format!("caractere {}", caractere as u32),
+        evenements::Evenement::Capture(capturee) => format!("capture {}", capturee as u8),
+    }
+}
+
+fn lire_evenement(mot: &str, reste: &str) -> Result<evenements::Evenement, String> {
+
+    let morceaux: std::vec::Vec<&str> = reste.split_whitespace().collect();
+    let erreur = || format!("«{} {}» est invalide", mot, reste);
+
+    let appuye = |texte: &str| match texte {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(erreur()),
+    };
+
+    match (mot, morceaux.as_slice()) {
+
+        ("touche", [code, nom, appuyee]) => {
+
+            // Une touche absente du fichier de touches ne peut pas être relue; seule sa position compte alors
+            let touche = match commandes::Entree::lire(nom) {
+                Ok(commandes::Entree::Touche(touche)) => Some(touche),
+                _ => None,
+            };
+
+            Ok(evenements::Evenement::Touche {
+                code: code.parse::<u32>().map_err(|_| erreur())?,
+                touche,
+                appuyee: appuye(appuyee)?,
+            })
+        },
+
+        ("souris", [nom, appuyee]) => match commandes::Entree::lire(nom) {
+
+            Ok(commandes::Entree::Souris(bouton)) => Ok(evenements::Evenement::BoutonSouris {
+                bouton,
+                appuye: appuye(appuyee)?,
+            }),
+            _ => Err(erreur()),
+        },
+
+        ("curseur", _) | ("mouvement", _) => {
+
+            let valeurs = lire_nombres(reste, 2)?;
+
+            Ok(if mot == "curseur" {
+                evenements::Evenement::Curseur(valeurs[0], valeurs[1])
+            }
+            else {
+                evenements::Evenement::Mouvement(valeurs[0], valeurs[1])
+            })
+        },
+
+        ("caractere", [code]) => code.parse::<u32>().ok().and_then(std::char::from_u32)
+            .map(evenements::Evenement::Caractere)
+            .ok_or_else(erreur),
+
+        ("capture", [capturee]) => Ok(evenements::Evenement::Capture(appuye(capturee)?)),
+
+        _ => Err(format!("la ligne «{} {}» est inconnue", mot, reste)),
+    }
+}
@@ -1,96 +1,229 @@
 use glium::{glutin};
 
+use commandes;
+use manette;
+
 /*
     Interface publique du module evenements
 
     Sert à gérer tous les événèments importants tels que
     l'état du clavier et l'état de la souris
+
+    Les événements de glutin sont d'abord convertis en Evenement, puis traités; ceux de l'image en cours
+    sont gardés jusqu'à la fin de la logique, pour pouvoir les enregistrer et les rejouer (voir enregistrement)
 */
 
+// Ce qui modifie l'état du gestionnaire, dans l'ordre reçu
+#[derive(Clone, Copy, PartialEq)]
+pub enum Evenement {
+
+    Touche {
+        code: u32, // Code de balayage
+        touche: Option<glutin::event::VirtualKeyCode>,
+        appuyee: bool,
+    },
+    BoutonSouris {
+        bouton: glutin::event::MouseButton,
+        appuye: bool,
+    },
+    Curseur(f64, f64), // Position logique dans la fenêtre
+    Mouvement(f64, f64), // Mouvement brut de la souris
+    Caractere(char),
+    Capture(bool), // Voir capturer_souris
+}
+
 pub struct GestionnaireEvenements {
     
     pub clavier: Clavier,
     pub souris: Souris,
+    pub manette: manette::Manette,
+    pub associations: commandes::Associations, // Entrées de chaque commande
+
+    caracteres: std::vec::Vec<char>, // Tapés depuis la dernière image
+    evenements: std::vec::Vec<Evenement>, // Traités depuis la dernière image
 }
 
 impl GestionnaireEvenements {
 
-    pub fn new(affichage: &glium::Display) -> GestionnaireEvenements {
+    pub fn new(manette: manette::Manette) -> GestionnaireEvenements {
 
         GestionnaireEvenements {
             clavier: Clavier::new(),
-            souris: Souris::new(affichage),
+            souris: Souris::new(),
+            manette,
+            associations: commandes::Associations::charger(commandes::CHEMIN),
+
+            caracteres: std::vec::Vec::new(),
+            evenements: std::vec::Vec::new(),
+        }
+    }
+
+    pub fn caracteres(&self) -> &[char] {
+
+        &self.caracteres
+    }
+
+    pub fn evenements(&self) -> &[Evenement] {
+
+        &self.evenements
+    }
+
+    // Entre 0 et 1: une touche donne toujours 1, la manette donne aussi les valeurs intermédiaires
+    pub fn valeur_commande(&self, commande: commandes::Commande) -> f32 {
+
+        if self.entree_active(commande) {
+            return 1.0;
+        }
+
+        self.manette.valeur_commande(commande)
+    }
+
+    // Au moins une des entrées de la commande vient d'être appuyée
+    pub fn commande_declenchee(&self, commande: commandes::Commande) -> bool {
+
+        self.associations.obtenir(commande).iter().any(|entree| match entree {
+            commandes::Entree::Touche(touche) => self.clavier.vient_etre_appuyee(touche),
+            commandes::Entree::Position(code) => self.clavier.position_vient_etre_appuyee(*code),
+            commandes::Entree::Souris(bouton) => self.souris.vient_etre_appuye(bouton),
+            commandes::Entree::Manette(bouton) => self.manette.vient_etre_appuye(*bouton),
+        })
+    }
+
+    // Touche ou bouton appuyé depuis la dernière image, pour l'associer à une commande
+    // Une touche que glutin ne sait pas nommer est associée par sa position
+    pub fn derniere_entree(&self) -> Option<commandes::Entree> {
+
+        self.clavier.derniere_touche().map(commandes::Entree::Touche)
+            .or_else(|| self.clavier.derniere_position().map(commandes::Entree::Position))
+            .or_else(|| self.souris.dernier_bouton.map(commandes::Entree::Souris))
+            .or_else(|| self.manette.dernier_bouton().map(commandes::Entree::Manette))
+    }
+
+    // Noms des entrées de la commande, avec les lettres de la disposition du clavier
+    pub fn description(&self, commande: commandes::Commande) -> String {
+
+        self.associations.description(commande, self.clavier.etiquettes())
+    }
+
+    // Capturé, le curseur est caché et retenu dans la fenêtre; les mouvements de la souris dirigent le regard
+    pub fn capturer_souris(&mut self, affichage: &glium::Display, capturer: bool) {
+
+        let fenetre = affichage.gl_window();
+        let fenetre = fenetre.window();
+
+        self.traiter(Evenement::Capture(capturer));
+        fenetre.set_cursor_visible(!capturer);
+
+        // Sans capture, le curseur peut sortir de la fenêtre, mais les mouvements sont tout de même reçus
+        if let Err(erreur) = fenetre.set_cursor_grab(capturer) {
+            println!("Le curseur n'a pas pu être capturé: {:?}", erreur);
         }
     }
 
     pub fn gerer_evenement_fenetre(&mut self, evenement: &glutin::event::WindowEvent) {
 
-        match evenement {
+        let evenement = match evenement {
     
-            glutin::event::WindowEvent::KeyboardInput{input: entree, ..} => {
-                
-                if let Some(touche) = entree.virtual_keycode {
-                    self.clavier.mise_a_jour_touche(&touche, &entree.state);
-                }
+            glutin::event::WindowEvent::KeyboardInput{input: entree, ..} => Evenement::Touche {
+                code: entree.scancode,
+                touche: entree.virtual_keycode,
+                appuyee: entree.state == glutin::event::ElementState::Pressed,
             },
 
-            glutin::event::WindowEvent::CursorMoved{position, ..} => {
-                
-                self.souris.mise_a_jour_evenement(*position);
+            glutin::event::WindowEvent::CursorMoved{position, ..} => Evenement::Curseur(position.x, position.y),
+
+            glutin::event::WindowEvent::MouseInput{state, button, ..} => Evenement::BoutonSouris {
+                bouton: *button,
+                appuye: *state == glutin::event::ElementState::Pressed,
             },
+
+            glutin::event::WindowEvent::ReceivedCharacter(caractere) if !caractere.is_control() => Evenement::Caractere(*caractere),
     
             _ => return, // Sinon, rien à faire
+        };
+
+        self.traiter(evenement);
+    }
+
+    // Mouvements bruts de la souris, sans accélération ni limite de l'écran
+    pub fn gerer_evenement_peripherique(&mut self, evenement: &glutin::event::DeviceEvent) {
+
+        if let glutin::event::DeviceEvent::MouseMotion{delta} = evenement {
+            self.traiter(Evenement::Mouvement(delta.0, delta.1));
         }
     }
 
-    // Cette méthode devrait être appelée à la fin de la logique du programme
-    pub fn mise_a_jour_post_logique(&mut self, affichage: &glium::Display) {
+    // Aussi appelée directement pour rejouer un enregistrement
+    pub fn traiter(&mut self, evenement: Evenement) {
 
-        self.souris.mise_a_jour(affichage);
-        
-        // Sortir et entrer du mode plein écran
-        if self.clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Escape) {
-            
-            self.souris.mode_centre = !self.souris.mode_centre;
-            affichage.gl_window().window().set_cursor_visible(!self.souris.mode_centre);
+        self.evenements.push(evenement);
 
-            let mut plein_ecran = None;
+        match evenement {
 
-            if self.souris.mode_centre {
-                
-                plein_ecran = Some(glutin::window::Fullscreen::Borderless(
-                    affichage.gl_window().window().current_monitor()));
-            }
+            Evenement::Touche{code, touche, appuyee} => self.clavier.mise_a_jour_entree(code, touche, appuyee),
+            Evenement::BoutonSouris{bouton, appuye} => self.souris.mise_a_jour_bouton(bouton, appuye),
+            Evenement::Curseur(x, y) => self.souris.mise_a_jour_evenement(glutin::dpi::LogicalPosition::new(x, y)),
+            Evenement::Mouvement(x, y) => self.souris.mise_a_jour_mouvement((x, y)),
+            Evenement::Caractere(caractere) => self.caracteres.push(caractere),
 
-            affichage.gl_window().window().set_fullscreen(plein_ecran);
+            Evenement::Capture(capturee) => {
+                self.souris.capturee = capturee;
+                self.souris.mouvement = (0.0, 0.0);
+            },
         }
+    }
+
+    // Cette méthode devrait être appelée au début de la logique du programme
+    pub fn mise_a_jour_pre_logique(&mut self) {
+
+        self.manette.mettre_a_jour();
+    }
+
+    // Cette méthode devrait être appelée à la fin de la logique du programme
+    pub fn mise_a_jour_post_logique(&mut self) {
+
+        self.souris.mise_a_jour();
+        self.caracteres.clear();
+        self.evenements.clear();
 
         self.clavier.mise_a_jour_changement_etat();
     }
+
+    fn entree_active(&self, commande: commandes::Commande) -> bool {
+
+        self.associations.obtenir(commande).iter().any(|entree| match entree {
+            commandes::Entree::Touche(touche) => self.clavier.est_appuyee(touche),
+            commandes::Entree::Position(code) => self.clavier.position_appuyee(*code),
+            commandes::Entree::Souris(bouton) => self.souris.est_appuye(bouton),
+            commandes::Entree::Manette(bouton) => self.manette.est_appuye(*bouton),
+        })
+    }
 }
 
-// Mémorise l'état des touches
+// Mémorise l'état de toutes les touches appuyées au moins une fois,
+// selon la lettre qu'elles produisent et selon leur position sur le clavier
 pub struct Clavier {
 
     etats_touches: std::collections::HashMap<glutin::event::VirtualKeyCode, EtatTouche>,
+    derniere_touche: Option<glutin::event::VirtualKeyCode>, // Appuyée depuis la dernière image, répertoriée ou non
+
+    etats_positions: std::collections::HashMap<u32, EtatTouche>, // Par code de balayage
+    derniere_position: Option<u32>, // Seulement si la touche n'a pas de nom
+    etiquettes: commandes::Etiquettes,
 }
 
 impl Clavier {
 
     pub fn new() -> Clavier {
 
-        let mut clavier = Clavier {
+        Clavier {
             etats_touches: std::collections::HashMap::new(),
-        };
-
-        clavier.ajouter_touches_base();
-
-        clavier
-    }
-
-    pub fn ajouter_touche(&mut self, touche: glutin::event::VirtualKeyCode) {
+            derniere_touche: None,
 
-        self.etats_touches.insert(touche, EtatTouche::Inconnue);
+            etats_positions: std::collections::HashMap::new(),
+            derniere_position: None,
+            etiquettes: commandes::Etiquettes::new(),
+        }
     }
 
     pub fn est_appuyee(&self, touche: &glutin::event::VirtualKeyCode) -> bool {
@@ -119,111 +252,163 @@ impl Clavier {
         false
     }
 
-    fn mise_a_jour_touche(&mut self, touche: &glutin::event::VirtualKeyCode, etat_actuel: &glutin::event::ElementState) {
+    pub fn position_appuyee(&self, code: u32) -> bool {
 
-        // Si la touche est répertoriée, on change son état
-        if let Some(etat_touche) = self.etats_touches.get_mut(touche) {
+        matches!(self.etats_positions.get(&code), Some(EtatTouche::Appuyee{..}))
+    }
 
-            etat_touche.changer_etat(&etat_actuel);
-        }
+    pub fn position_vient_etre_appuyee(&self, code: u32) -> bool {
+
+        matches!(self.etats_positions.get(&code), Some(EtatTouche::Appuyee{vient_etre_appuyee: true}))
     }
 
-    // Mettre à jour le fait que la touche ne vient plus d'être appuyée/relâchée
-    fn mise_a_jour_changement_etat(&mut self) {
+    pub fn derniere_touche(&self) -> Option<glutin::event::VirtualKeyCode> {
 
-        for (_clef, etat) in self.etats_touches.iter_mut() {
+        self.derniere_touche
+    }
+
+    pub fn derniere_position(&self) -> Option<u32> {
 
-            *etat = match etat {
+        self.derniere_position
+    }
+
+    // Touches produites par les positions déjà appuyées
+    pub fn etiquettes(&self) -> &commandes::Etiquettes {
+
+        &self.etiquettes
+    }
 
-                EtatTouche::Appuyee{..} => EtatTouche::Appuyee{vient_etre_appuyee: false},
-                EtatTouche::Relachee{..} => EtatTouche::Relachee{vient_etre_relache: false},
-                _ => EtatTouche::Inconnue,
-            }
+    fn mise_a_jour_entree(&mut self, code: u32, touche: Option<glutin::event::VirtualKeyCode>, appuyee: bool) {
+
+        match touche {
+
+            Some(touche) => {
+
+                if appuyee {
+                    self.derniere_touche = Some(touche);
+                }
+
+                self.etiquettes.insert(code, touche);
+                self.etats_touches.entry(touche).or_insert(EtatTouche::Inconnue).changer_etat(appuyee);
+            },
+
+            None => if appuyee {
+                self.derniere_position = Some(code);
+            },
         }
+
+        self.etats_positions.entry(code).or_insert(EtatTouche::Inconnue).changer_etat(appuyee);
     }
 
-    fn ajouter_touches_base(&mut self) {
+    // Mettre à jour le fait que la touche ne vient plus d'être appuyée/relâchée
+    fn mise_a_jour_changement_etat(&mut self) {
+
+        self.derniere_touche = None;
+        self.derniere_position = None;
+
+        for (_clef, etat) in self.etats_touches.iter_mut() {
+            etat.oublier_changement();
+        }
 
-        self.ajouter_touche(glutin::event::VirtualKeyCode::A);
-        self.ajouter_touche(glutin::event::VirtualKeyCode::D);
-        self.ajouter_touche(glutin::event::VirtualKeyCode::S);
-        self.ajouter_touche(glutin::event::VirtualKeyCode::W);
-        self.ajouter_touche(glutin::event::VirtualKeyCode::LShift);
-        self.ajouter_touche(glutin::event::VirtualKeyCode::Space);
-        self.ajouter_touche(glutin::event::VirtualKeyCode::Escape);
+        for (_code, etat) in self.etats_positions.iter_mut() {
+            etat.oublier_changement();
+        }
     }
 }
 
 pub struct Souris {
 
-    position_actuelle: glutin::dpi::LogicalPosition,
-    position_origine: glutin::dpi::LogicalPosition,
+    position_actuelle: glutin::dpi::LogicalPosition, // Curseur, pour le menu
+    mouvement: (f64, f64), // Accumulé depuis la dernière image, seulement si capturée
+
+    pub capturee: bool,
 
-    pub mode_centre: bool,
+    etats_boutons: std::collections::HashMap<glutin::event::MouseButton, EtatTouche>,
+    dernier_bouton: Option<glutin::event::MouseButton>, // Appuyé depuis la dernière image
 }
 
 impl Souris {
 
-    pub fn new(affichage: &glium::Display) -> Souris {
+    pub fn new() -> Souris {
 
-        let mut souris = Souris{
+        Souris{
             position_actuelle: glutin::dpi::LogicalPosition::new( 0.0, 0.0),
-            position_origine: glutin::dpi::LogicalPosition::new( 0.0, 0.0),
-
-            mode_centre: false,
-        };
+            mouvement: (0.0, 0.0),
 
-        souris.centrer(affichage);
+            capturee: false,
 
-        souris
+            etats_boutons: std::collections::HashMap::new(),
+            dernier_bouton: None,
+        }
     }
 
     pub fn delta_x(&self) -> f32 {
 
-        Souris::stabiliser_delta(self.position_actuelle.x - self.position_origine.x)
+        self.mouvement.0 as f32
     }
 
     pub fn delta_y(&self) -> f32 {
-        // L'axe y de l'écran pointe vers le bas; on l'inverse
-        Souris::stabiliser_delta(self.position_origine.y - self.position_actuelle.y)
+        // L'axe y de la souris pointe vers le bas; on l'inverse
+        -self.mouvement.1 as f32
     }
 
-    pub fn centrer(&mut self, affichage: &glium::Display) {
+    // Le bouton gauche vient d'être appuyé
+    pub fn vient_etre_cliquee(&self) -> bool {
 
-        let position = affichage.gl_window().window().inner_size();
-        let centre = glutin::dpi::LogicalPosition::new(
-            position.width / 2.0,
-            position.height / 2.0);
+        self.vient_etre_appuye(&glutin::event::MouseButton::Left)
+    }
 
-        let _ = affichage.gl_window().window().set_cursor_position(centre);
+    pub fn est_appuye(&self, bouton: &glutin::event::MouseButton) -> bool {
 
-        self.position_origine = centre;
+        matches!(self.etats_boutons.get(bouton), Some(EtatTouche::Appuyee{..}))
     }
 
-    fn mise_a_jour(&mut self, affichage: &glium::Display) {
+    pub fn vient_etre_appuye(&self, bouton: &glutin::event::MouseButton) -> bool {
 
-        if self.mode_centre {
-            self.centrer(affichage);
-        }
+        matches!(self.etats_boutons.get(bouton), Some(EtatTouche::Appuyee{vient_etre_appuyee: true}))
     }
 
-    fn mise_a_jour_evenement(&mut self, position: glutin::dpi::LogicalPosition) {
+    // En pixels à partir du coin inférieur gauche, comme pour dessiner à l'écran
+    pub fn position_ecran(&self, affichage: &glium::Display) -> (f32, f32) {
 
-        self.position_actuelle = position;
+        let facteur = affichage.gl_window().window().hidpi_factor();
+        let position = self.position_actuelle.to_physical(facteur);
+        let (_, hauteur) = affichage.get_framebuffer_dimensions();
+
+        (position.x as f32, hauteur as f32 - position.y as f32)
     }
 
+    fn mise_a_jour(&mut self) {
 
-    // Cette fonction empêche la souris de se déplacer lentement à cause d'un delta minuscule
-    fn stabiliser_delta(delta: f64) -> f32 {
+        self.mouvement = (0.0, 0.0);
+        self.dernier_bouton = None;
 
-        const SEUIL: f64 = 0.5;
+        for (_bouton, etat) in self.etats_boutons.iter_mut() {
+            etat.oublier_changement();
+        }
+    }
 
-        if delta >= -SEUIL && delta <= SEUIL {
+    fn mise_a_jour_bouton(&mut self, bouton: glutin::event::MouseButton, appuye: bool) {
 
-            return 0.0;
+        if appuye {
+            self.dernier_bouton = Some(bouton);
         }
 
-        delta as f32
+        self.etats_boutons.entry(bouton).or_insert(EtatTouche::Inconnue).changer_etat(appuye);
+    }
+
+    fn mise_a_jour_evenement(&mut self, position: glutin::dpi::LogicalPosition) {
+
+        self.position_actuelle = position;
+    }
+
+    // Les mouvements hors capture, par exemple dans le menu, ne dirigent pas le regard
+    fn mise_a_jour_mouvement(&mut self, delta: (f64, f64)) {
+
+        if self.capturee {
+            self.mouvement.0 += delta.0;
+            self.mouvement.1 += delta.1;
+        }
     }
 }
 
@@ -245,11 +430,11 @@ enum EtatTouche {
 
 impl EtatTouche {
 
-    fn changer_etat(&mut self, etat_actuel: &glutin::event::ElementState) {
+    fn changer_etat(&mut self, appuyee: bool) {
 
-        *self = match etat_actuel {
+        *self = match appuyee {
                 
-            glutin::event::ElementState::Pressed => {
+            true => {
 
                 let etat_change = match self {
                     EtatTouche::Appuyee{vient_etre_appuyee} => *vient_etre_appuyee,
@@ -259,7 +444,7 @@ impl EtatTouche {
                 EtatTouche::Appuyee{vient_etre_appuyee: etat_change}
             },
             
-            glutin::event::ElementState::Released => {
+            false => {
 
                 let etat_change = match self {
                     EtatTouche::Relachee{vient_etre_relache} => *vient_etre_relache,
@@ -270,5 +455,16 @@ impl EtatTouche {
             },
         };
     }
+
+    // La touche ne vient plus d'être appuyée ou relâchée
+    fn oublier_changement(&mut self) {
+
+        *self = match self {
+
+            EtatTouche::Appuyee{..} => EtatTouche::Appuyee{vient_etre_appuyee: false},
+            EtatTouche::Relachee{..} => EtatTouche::Relachee{vient_etre_relache: false},
+            _ => EtatTouche::Inconnue,
+        }
+    }
     
 }
\ No newline at end of file
@@ -0,0 +1,263 @@
+use glium::{glutin};
+
+/*
+    Interface publique du module hors_ecran
+
+    Permet de créer un contexte OpenGL sans fenêtre, par exemple pour générer des images dans un script
+    Sous Linux, on essaie d'abord un contexte EGL sans surface (fonctionne avec Mesa llvmpipe, sans serveur graphique)
+*/
+
+pub struct ContexteHorsEcran {
+
+    facade: Box<dyn glium::backend::Facade>,
+    _boucle_evenements: Option<glutin::event_loop::EventLoop<()>>, // Doit survivre au contexte créé par glutin
+}
+
+impl ContexteHorsEcran {
+
+    pub fn new(dimensions: (u32, u32)) -> Result<ContexteHorsEcran, String> {
+
+        let mut erreurs = std::vec::Vec::new();
+
+        #[cfg(target_os = "linux")]
+        {
+            match egl::creer_contexte(dimensions) {
+
+                Ok(contexte) => {
+                    return Ok(ContexteHorsEcran {
+                        facade: Box::new(contexte),
+                        _boucle_evenements: None,
+                    });
+                },
+                Err(erreur) => erreurs.push(format!("EGL sans surface: {}", erreur)),
+            }
+        }
+
+        match ContexteHorsEcran::creer_contexte_glutin(dimensions) {
+
+            Ok(contexte) => return Ok(contexte),
+            Err(erreur) => erreurs.push(format!("glutin: {}", erreur)),
+        }
+
+        Err(format!("Aucun contexte OpenGL hors écran n'a pu être créé ({})", erreurs.join("; ")))
+    }
+
+    pub fn facade(&self) -> &dyn glium::backend::Facade {
+
+        &*self.facade
+    }
+
+    // Nécessite un serveur graphique, mais pas de fenêtre
+    fn creer_contexte_glutin(dimensions: (u32, u32)) -> Result<ContexteHorsEcran, String> {
+
+        // La création de la boucle d'événements panique s'il n'y a pas de serveur graphique
+        let boucle_evenements = std::panic::catch_unwind(glutin::event_loop::EventLoop::new)
+            .map_err(|_| "aucun serveur graphique".to_string())?;
+
+        let contexte = glutin::ContextBuilder::new()
+            .with_depth_buffer(24)
+            .build_headless(&boucle_evenements, glutin::dpi::PhysicalSize::new(dimensions.0 as f64, dimensions.1 as f64))
+            .map_err(|erreur| erreur.to_string())?;
+
+        let rendu = glium::HeadlessRenderer::new(contexte).map_err(|erreur| format!("{:?}", erreur))?;
+
+        Ok(ContexteHorsEcran {
+            facade: Box::new(rendu),
+            _boucle_evenements: Some(boucle_evenements),
+        })
+    }
+}
+
+
+
+
+
+/*
+    Partie privée du module hors_ecran
+*/
+
+// Liaison minimale avec libEGL, chargée à l'exécution pour ne pas en dépendre au démarrage
+#[cfg(target_os = "linux")]
+mod egl {
+
+    use std::os::raw::{c_char, c_int, c_void};
+
+    type Pointeur = *mut c_void;
+
+    const RTLD_NOW: c_int = 2;
+
+    const EGL_PLATFORM_SURFACELESS_MESA: u32 = 0x31DD;
+    const EGL_OPENGL_API: u32 = 0x30A2;
+    const EGL_OPENGL_ES_API: u32 = 0x30A0;
+    const EGL_CONTEXT_MAJOR_VERSION: i32 = 0x3098;
+    const EGL_CONTEXT_MINOR_VERSION: i32 = 0x30FB;
+    const EGL_CONTEXT_OPENGL_PROFILE_MASK: i32 = 0x30FD;
+    const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: i32 = 0x0001;
+    const EGL_NONE: i32 = 0x3038;
+
+    #[link(name = "dl")]
+    extern "C" {
+        fn dlopen(nom: *const c_char, options: c_int) -> Pointeur;
+        fn dlsym(bibliotheque: Pointeur, symbole: *const c_char) -> Pointeur;
+    }
+
+    // Fonctions d'EGL utilisées, obtenues avec dlsym
+    type FnGetProcAddress = unsafe extern "C" fn(*const c_char) -> Pointeur;
+    type FnGetPlatformDisplay = unsafe extern "C" fn(u32, Pointeur, *const isize) -> Pointeur;
+    type FnInitialize = unsafe extern "C" fn(Pointeur, *mut i32, *mut i32) -> u32;
+    type FnBindApi = unsafe extern "C" fn(u32) -> u32;
+    type FnCreateContext = unsafe extern "C" fn(Pointeur, Pointeur, Pointeur, *const i32) -> Pointeur;
+    type FnMakeCurrent = unsafe extern "C" fn(Pointeur, Pointeur, Pointeur, Pointeur) -> u32;
+    type FnGetCurrentContext = unsafe extern "C" fn() -> Pointeur;
+    type FnGetError = unsafe extern "C" fn() -> i32;
+
+    struct Fonctions {
+        get_proc_address: FnGetProcAddress,
+        get_platform_display: FnGetPlatformDisplay,
+        initialize: FnInitialize,
+        bind_api: FnBindApi,
+        create_context: FnCreateContext,
+        make_current: FnMakeCurrent,
+        get_current_context: FnGetCurrentContext,
+        get_error: FnGetError,
+    }
+
+    pub struct ContexteEgl {
+        fonctions: Fonctions,
+        affichage: Pointeur,
+        contexte: Pointeur,
+        dimensions: (u32, u32),
+    }
+
+    unsafe impl glium::backend::Backend for ContexteEgl {
+
+        fn swap_buffers(&self) -> Result<(), glium::SwapBuffersError> {
+            Ok(()) // Il n'y a pas d'écran
+        }
+
+        unsafe fn get_proc_address(&self, symbole: &str) -> *const c_void {
+
+            let symbole = std::ffi::CString::new(symbole).unwrap();
+            (self.fonctions.get_proc_address)(symbole.as_ptr()) as *const c_void
+        }
+
+        fn get_framebuffer_dimensions(&self) -> (u32, u32) {
+            self.dimensions
+        }
+
+        fn is_current(&self) -> bool {
+            unsafe { (self.fonctions.get_current_context)() == self.contexte }
+        }
+
+        unsafe fn make_current(&self) {
+            (self.fonctions.make_current)(self.affichage, std::ptr::null_mut(), std::ptr::null_mut(), self.contexte);
+        }
+    }
+
+    pub fn creer_contexte(dimensions: (u32, u32)) -> Result<std::rc::Rc<glium::backend::Context>, String> {
+
+        unsafe {
+
+            let fonctions = charger_fonctions()?;
+
+            let affichage = (fonctions.get_platform_display)(
+                EGL_PLATFORM_SURFACELESS_MESA, std::ptr::null_mut(), std::ptr::null());
+            if affichage.is_null() {
+                return Err("la plateforme sans surface n'est pas supportée".to_string());
+            }
+
+            let (mut majeur, mut mineur) = (0, 0);
+            if (fonctions.initialize)(affichage, &mut majeur, &mut mineur) == 0 {
+                return Err(format!("eglInitialize a échoué (0x{:x})", (fonctions.get_error)()));
+            }
+
+            // Les shaders s'adaptent aux versions plus anciennes, voir shaders::ProgrammeOpenGL::new
+            const VERSIONS: [(u32, i32, i32); 3] = [
+                (EGL_OPENGL_API, 4, 3),
+                (EGL_OPENGL_API, 3, 3),
+                (EGL_OPENGL_ES_API, 3, 0),
+            ];
+
+            let mut contexte = std::ptr::null_mut();
+
+            for (api, majeure, mineure) in VERSIONS.iter() {
+
+                (fonctions.bind_api)(*api);
+
+                let attributs = if *api == EGL_OPENGL_API {
+                    [
+                        EGL_CONTEXT_MAJOR_VERSION, *majeure,
+                        EGL_CONTEXT_MINOR_VERSION, *mineure,
+                        EGL_CONTEXT_OPENGL_PROFILE_MASK, EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                        EGL_NONE,
+                    ]
+                }
+                else {
+                    [
+                        EGL_CONTEXT_MAJOR_VERSION, *majeure,
+                        EGL_CONTEXT_MINOR_VERSION, *mineure,
+                        EGL_NONE, 0,
+                        EGL_NONE,
+                    ]
+                };
+
+                contexte = (fonctions.create_context)(affichage, std::ptr::null_mut(), std::ptr::null_mut(), attributs.as_ptr());
+                if !contexte.is_null() {
+                    break;
+                }
+            }
+
+            if contexte.is_null() {
+                return Err(format!("eglCreateContext a échoué (0x{:x})", (fonctions.get_error)()));
+            }
+
+            let backend = ContexteEgl {
+                fonctions,
+                affichage,
+                contexte,
+                dimensions,
+            };
+
+            glium::backend::Context::new(backend, true, Default::default())
+                .map_err(|erreur| format!("{:?}", erreur))
+        }
+    }
+
+    unsafe fn charger_fonctions() -> Result<Fonctions, String> {
+
+        let bibliotheque = dlopen(b"libEGL.so.1\0".as_ptr() as *const c_char, RTLD_NOW);
+        if bibliotheque.is_null() {
+            return Err("libEGL.so.1 est introuvable".to_string());
+        }
+
+        let symbole = |nom: &[u8]| -> Result<Pointeur, String> {
+
+            let pointeur = dlsym(bibliotheque, nom.as_ptr() as *const c_char);
+
+            if pointeur.is_null() {
+                return Err(format!("{} est introuvable", String::from_utf8_lossy(&nom[..nom.len() - 1])));
+            }
+
+            Ok(pointeur)
+        };
+
+        let get_proc_address = std::mem::transmute::<Pointeur, FnGetProcAddress>(symbole(b"eglGetProcAddress\0")?);
+
+        // Extension, donc obtenue avec eglGetProcAddress
+        let get_platform_display = get_proc_address(b"eglGetPlatformDisplayEXT\0".as_ptr() as *const c_char);
+        if get_platform_display.is_null() {
+            return Err("eglGetPlatformDisplayEXT est introuvable".to_string());
+        }
+
+        Ok(Fonctions {
+            get_proc_address,
+            get_platform_display: std::mem::transmute::<Pointeur, FnGetPlatformDisplay>(get_platform_display),
+            initialize: std::mem::transmute::<Pointeur, FnInitialize>(symbole(b"eglInitialize\0")?),
+            bind_api: std::mem::transmute::<Pointeur, FnBindApi>(symbole(b"eglBindAPI\0")?),
+            create_context: std::mem::transmute::<Pointeur, FnCreateContext>(symbole(b"eglCreateContext\0")?),
+            make_current: std::mem::transmute::<Pointeur, FnMakeCurrent>(symbole(b"eglMakeCurrent\0")?),
+            get_current_context: std::mem::transmute::<Pointeur, FnGetCurrentContext>(symbole(b"eglGetCurrentContext\0")?),
+            get_error: std::mem::transmute::<Pointeur, FnGetError>(symbole(b"eglGetError\0")?),
+        })
+    }
+}
@@ -0,0 +1,141 @@
+use configuration;
+use labyrinthe;
+use observateur;
+use texte;
+
+/*
+    Interface publique du module hud
+
+    Sert à afficher des informations par-dessus la scène: temps écoulé, images par seconde,
+    cellule de l'observateur et messages temporaires
+*/
+
+pub struct Hud {
+
+    temps: f32, // Secondes écoulées depuis le début
+    cellule: (i32, i32),
+
+    // Les mesures sont moyennées puis affichées quelques fois par seconde pour rester lisibles
+    images_mesurees: u32,
+    duree_mesuree: f32,
+    duree_image: f32, // Secondes par image, dernière moyenne affichée
+
+    messages: std::vec::Vec<Message>,
+    pub parametres: configuration::ParametresHud,
+}
+
+impl Hud {
+
+    pub fn new(parametres: &configuration::ParametresHud) -> Hud {
+
+        Hud {
+
+            temps: 0.0,
+            cellule: (0, 0),
+
+            images_mesurees: 0,
+            duree_mesuree: 0.0,
+            duree_image: 0.0,
+
+            messages: std::vec::Vec::new(),
+            parametres: parametres.clone(),
+        }
+    }
+
+    // temps: secondes écoulées depuis le début, delta: durée de la dernière image
+    pub fn mettre_a_jour(&mut self,
+                    temps: f32,
+                    delta: f32,
+                    labyrinthe: &labyrinthe::Labyrinthe,
+                    observateur: &observateur::Observateur)
+    {
+        const INTERVALLE_MESURE: f32 = 0.5;
+
+        self.temps = temps;
+
+        self.images_mesurees += 1;
+        self.duree_mesuree += delta;
+
+        if self.duree_mesuree >= INTERVALLE_MESURE {
+
+            self.duree_image = self.duree_mesuree / self.images_mesurees as f32;
+            self.images_mesurees = 0;
+            self.duree_mesuree = 0.0;
+        }
+
+        let (x, z) = labyrinthe.coordonnees_grille(&observateur.position);
+        self.cellule = (x.floor() as i32, z.floor() as i32);
+
+        self.messages.retain(|message| message.fin > temps);
+    }
+
+    // Le message disparaît après quelques secondes
+    pub fn afficher_message(&mut self, texte: &str) {
+
+        const DUREE_MESSAGE: f32 = 4.0;
+
+        self.messages.push(Message {
+            texte: texte.to_string(),
+            fin: self.temps + DUREE_MESSAGE,
+        });
+    }
+
+    // dimensions: taille de l'écran, en pixels
+    pub fn generer_texte(&self, dimensions: (u32, u32)) -> texte::Texte {
+
+        let mut texte = texte::Texte::new();
+
+        if !self.parametres.visible {
+            return texte;
+        }
+
+        let (largeur, hauteur) = (dimensions.0 as f32, dimensions.1 as f32);
+        let taille = self.parametres.taille;
+        let marge = taille * 0.5;
+
+        const BLANC: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+
+        // Informations dans le coin supérieur gauche
+        let secondes = self.temps as u32;
+        let images_par_seconde = if self.duree_image > 0.0 { 1.0 / self.duree_image } else { 0.0 };
+
+        let lignes = [
+            format!("Temps {:02}:{:02}", secondes / 60, secondes % 60),
+            format!("{:.0} i/s ({:.1} ms)", images_par_seconde, self.duree_image * 1000.0),
+            format!("Cellule {}, {}", self.cellule.0, self.cellule.1),
+        ];
+
+        for (i, ligne) in lignes.iter().enumerate() {
+            texte.ajouter(ligne, (marge, hauteur - marge - (i + 1) as f32 * taille), taille, BLANC);
+        }
+
+        // Messages centrés, le plus récent en bas; ils s'estompent avant de disparaître
+        const DUREE_FONDU: f32 = 0.5;
+        let taille_message = taille * 1.5;
+
+        for (i, message) in self.messages.iter().rev().enumerate() {
+
+            let opacite = ((message.fin - self.temps) / DUREE_FONDU).min(1.0);
+            let x = (largeur - texte::Police::largeur(&message.texte, taille_message)) / 2.0;
+            let y = hauteur * 0.6 + i as f32 * taille_message;
+
+            texte.ajouter(&message.texte, (x, y), taille_message, [1.0, 0.9, 0.6, opacite]);
+        }
+
+        texte
+    }
+}
+
+
+
+
+
+/*
+    Partie privée du module hud
+*/
+
+struct Message {
+
+    texte: String,
+    fin: f32, // Moment où le message disparaît, en secondes depuis le début
+}
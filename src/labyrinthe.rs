@@ -1,9 +1,11 @@
-extern crate rand;
 extern crate nalgebra_glm as glm;
 
+use aleatoire;
+use collision;
 use donnees;
-use observateur;
+use materiaux;
 use ecran;
+use observateur;
 
 /*
     Interface publique du module labyrinthe
@@ -21,11 +23,12 @@ pub struct Labyrinthe {
     cote: f32, // longueur et largeur d'une cellule
     cellules: std::vec::Vec<std::vec::Vec<Cellule>>,
     lumieres: std::vec::Vec<Lumiere>,
+    chemin_sortie: std::vec::Vec<(i32, i32)>,
 }
 
 impl Labyrinthe {
 
-    pub fn new(longueur: u32, largeur: u32) -> Labyrinthe{
+    pub fn new(longueur: u32, largeur: u32, algorithme: Algorithme) -> Labyrinthe{
 
         let mut cellules = std::vec::Vec::with_capacity(largeur as usize);
 
@@ -57,21 +60,26 @@ impl Labyrinthe {
             cote: COTE,
             cellules: cellules,
             lumieres: std::vec::Vec::new(),
+            chemin_sortie: std::vec::Vec::new(),
         };
 
-        labyrinthe.detruire_murs();
+        labyrinthe.detruire_murs(algorithme);
         labyrinthe.enlever_murs_inutiles();
         labyrinthe.ajouter_lumieres();
+        labyrinthe.ajouter_estrades();
+
+        // La sortie ne dépend que du labyrinthe, pas de l'endroit où se trouve l'observateur plus tard
+        let depart = glm::Vec3::new(observateur::POSITION_DEPART[0], observateur::POSITION_DEPART[1], observateur::POSITION_DEPART[2]);
+        labyrinthe.chemin_sortie = labyrinthe.calculer_chemin_sortie(&depart);
 
         labyrinthe
     }
 
-    // texture: longueur, hauteur, id 
     pub fn ajouter_geometrie(&self,
-        texture_plafond: [f32; 3],
-        texture_sol: [f32; 3],
-        texture_mur: [f32; 3],
-        texture_torche: f32,
+        materiau_plafond: materiaux::IdMateriau,
+        materiau_sol: materiaux::IdMateriau,
+        materiau_mur: materiaux::IdMateriau,
+        materiau_torche: materiaux::IdMateriau,
         donnees_opengl: &mut donnees::DonneesOpenGL) {
 
         let hauteur = self.hauteur;
@@ -84,7 +92,7 @@ impl Labyrinthe {
             for x in 0..self.longueur {
                 
                 let position = Position::new(x, z);
-                self.lire_cellule(&position).ajouter_geometrie(hauteur, cote, &decalage, &texture_mur, donnees_opengl);
+                self.lire_cellule(&position).ajouter_geometrie(hauteur, cote, &decalage, materiau_mur, donnees_opengl);
             }
         }
 
@@ -96,7 +104,7 @@ impl Labyrinthe {
             [decalage[0], decalage[1], decalage[2]],
             [decalage[0], decalage[1] + hauteur, decalage[2]],
             [decalage[0], decalage[1], decalage[2] + cote * self.largeur as f32],
-            [texture_mur[0] * self.longueur as f32, texture_mur[1], texture_mur[2]]
+            materiau_mur
         );
 
         // Ajoute le mur bas
@@ -105,7 +113,7 @@ impl Labyrinthe {
             [decalage[0] + cote * self.longueur as f32, decalage[1], decalage[2]],
             [decalage[0] + cote * self.longueur as f32, decalage[1] + hauteur, decalage[2]],
             [decalage[0], decalage[1], decalage[2]],
-            [texture_mur[0] * self.longueur as f32, texture_mur[1], texture_mur[2]]
+            materiau_mur
         );
 
         // Ajoute le mur droit
@@ -114,7 +122,7 @@ impl Labyrinthe {
             [decalage[0] + cote * self.longueur as f32, decalage[1], decalage[2] + cote * self.largeur as f32],
             [decalage[0] + cote * self.longueur as f32, decalage[1] + hauteur, decalage[2] + cote * self.largeur as f32],
             [decalage[0] + cote * self.longueur as f32, decalage[1], decalage[2]],
-            [texture_mur[0] * self.longueur as f32, texture_mur[1], texture_mur[2]]
+            materiau_mur
         );
 
         // Ajoute le mur haut
@@ -123,7 +131,7 @@ impl Labyrinthe {
             [decalage[0], decalage[1], decalage[2] + cote * self.largeur as f32],
             [decalage[0], decalage[1] + hauteur, decalage[2] + cote * self.largeur as f32],
             [decalage[0] + cote * self.longueur as f32, decalage[1], decalage[2] + cote * self.largeur as f32],
-            [texture_mur[0] * self.longueur as f32, texture_mur[1], texture_mur[2]]
+            materiau_mur
         );
 
         // Ajoute le plancher
@@ -132,7 +140,7 @@ impl Labyrinthe {
             [decalage[0], decalage[1], decalage[2]],
             [decalage[0], decalage[1], decalage[2] + cote * self.largeur as f32],
             [decalage[0] + cote * self.longueur as f32, decalage[1], decalage[2]],
-            [texture_sol[0] * self.longueur as f32, texture_sol[1] * self.largeur as f32, texture_sol[2]]
+            materiau_sol
         );
         
         // Ajoute le plafond
@@ -141,76 +149,155 @@ impl Labyrinthe {
             [decalage[0] + cote * self.longueur as f32, decalage[1] + hauteur, decalage[2] + cote * self.largeur as f32],
             [decalage[0], decalage[1] + hauteur, decalage[2] + cote * self.largeur as f32],
             [decalage[0] + cote * self.longueur as f32, decalage[1] + hauteur, decalage[2]],
-            [texture_plafond[0] * self.longueur as f32, texture_plafond[1] * self.largeur as f32, texture_plafond[2]]
+            materiau_plafond
         );
 
+        // Ajoute les estrades des culs-de-sac
+        for z in 0..self.largeur as i32 {
+
+            for x in 0..self.longueur as i32 {
+
+                self.ajouter_geometrie_estrade(x, z, materiau_sol, materiau_mur, donnees_opengl);
+            }
+        }
+
         // Ajoute les torches
         for i in 0..self.lumieres.len() {
 
-            self.lumieres[i].ajouter_geometrie(texture_torche, donnees_opengl);
+            self.lumieres[i].ajouter_geometrie(materiau_torche, donnees_opengl);
         }
     }
 
-    pub fn expulser_murs(&self, observateur: &mut observateur::Observateur) {
+    // Position atteinte par une capsule qui se déplace sans traverser les murs, en glissant le long de ceux-ci
+    pub fn deplacer(&self, position: &glm::Vec3, deplacement: &glm::Vec3, capsule: &collision::Capsule) -> glm::Vec3 {
 
-        // Position de la cellule la plus centrée sur l'observateur
-        let x_observateur = ((observateur.position.x - self.decalage[0]) / self.cote).round() as i32;
-        let z_observateur = ((observateur.position.z - self.decalage[2]) / self.cote).round() as i32;
+        collision::balayer(position, deplacement, capsule, &self.boites_chemin(position, deplacement, capsule))
+    }
 
-        for x in -1..2 as i32 {
+    // Proportion du déplacement possible avant de toucher un mur, entre 0 et 1
+    pub fn proportion_libre(&self, position: &glm::Vec3, deplacement: &glm::Vec3, capsule: &collision::Capsule) -> f32 {
 
-            for z in -1..2 as i32{
+        collision::lancer(position, deplacement, capsule, &self.boites_chemin(position, deplacement, capsule))
+    }
 
-                let verifier_collision = match self.essayer_cellule((x + x_observateur, z + z_observateur)) {
+    // Boîtes des murs dont les cellules touchent le rectangle entre min et max dans le plan xz
+    // L'extérieur du labyrinthe est considéré comme un mur, sur une cellule d'épaisseur
+    pub fn boites_collision(&self, min: &glm::Vec3, max: &glm::Vec3) -> std::vec::Vec<BoiteCollision> {
 
-                    Some(cellule) => !cellule.est_un_sentier(),
-                    None => true, // Si la cellule n'existe pas, c'est à l'extérieur du labyrinthe
-                };
+        let cellule_min = |valeur: f32, decalage: f32| (((valeur - decalage) / self.cote).floor() as i32).max(-1);
+        let cellule_max = |valeur: f32, decalage: f32, nombre: u32| (((valeur - decalage) / self.cote).floor() as i32).min(nombre as i32);
 
-                if verifier_collision {
+        let (x_min, x_max) = (cellule_min(min.x, self.decalage[0]), cellule_max(max.x, self.decalage[0], self.longueur));
+        let (z_min, z_max) = (cellule_min(min.z, self.decalage[2]), cellule_max(max.z, self.decalage[2], self.largeur));
 
-                    let ecart: f32 = self.cote * 0.2;
+        let mut boites = std::vec::Vec::new();
 
-                    let x_gauche = (((x + x_observateur) as f32) * self.cote) + self.decalage[0] as f32 - ecart;
-                    let x_droit = x_gauche + self.cote + ecart + ecart;
-                    let z_bas = (((z + z_observateur) as f32) * self.cote) + self.decalage[2] as f32 - ecart;
-                    let z_haut = z_bas + self.cote + ecart + ecart;
+        for x in x_min..(x_max + 1) {
 
-                    if x_gauche <= observateur.position.x && observateur.position.x <= x_droit &&
-                        z_bas <= observateur.position.z && observateur.position.z <= z_haut {
-                        
-                        let min = |a: f32, b: f32| -> f32 {if a < b {return a;} b};
-                        
-                        if min(observateur.position.x - x_gauche, x_droit - observateur.position.x) <
-                            min(observateur.position.z - z_bas, z_haut - observateur.position.z) {
+            for z in z_min..(z_max + 1) {
 
-                            if observateur.position.x - x_gauche < self.cote / 2.0 {
-                                observateur.position.x = x_gauche;
-                            }
-                            else {
-                                observateur.position.x = x_droit;
-                            }
-                        }
-                        else {
-
-                            if observateur.position.z - z_bas < self.cote / 2.0 {
-                                observateur.position.z = z_bas;
-                            }
-                            else {
-                                observateur.position.z = z_haut;
-                            }
-                        }
-                    }
+                if self.est_un_sentier(x, z) {
+                    continue;
                 }
+
+                let x_gauche = (x as f32) * self.cote + self.decalage[0];
+                let z_bas = (z as f32) * self.cote + self.decalage[2];
+
+                boites.push(BoiteCollision {
+                    min: [x_gauche, self.decalage[1], z_bas],
+                    max: [x_gauche + self.cote, self.decalage[1] + self.hauteur, z_bas + self.cote],
+                });
             }
         }
+
+        boites
+    }
+
+    // Seuls les murs que la capsule peut toucher en chemin sont considérés
+    fn boites_chemin(&self, position: &glm::Vec3, deplacement: &glm::Vec3, capsule: &collision::Capsule) -> std::vec::Vec<BoiteCollision> {
+
+        let destination = position + deplacement;
+        let rayon = glm::Vec3::new(capsule.rayon, 0.0, capsule.rayon);
+
+        self.boites_collision(
+            &(glm::min2(position, &destination) - rayon),
+            &(glm::max2(position, &destination) + rayon)
+        )
+    }
+
+    // Hauteur du sol sous la position, plus haute sur les estrades
+    pub fn hauteur_sol(&self, position: &glm::Vec3) -> f32 {
+
+        let (x, z) = self.coordonnees_grille(position);
+
+        match self.essayer_cellule((x.floor() as i32, z.floor() as i32)) {
+            Some(cellule) => self.decalage[1] + cellule.estrade,
+            None => self.decalage[1],
+        }
+    }
+
+    pub fn hauteur_plafond(&self, _position: &glm::Vec3) -> f32 {
+
+        self.decalage[1] + self.hauteur
+    }
+
+    // Nombre de cellules en x et en z
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.longueur, self.largeur)
+    }
+
+    // L'extérieur du labyrinthe est considéré comme un mur
+    pub fn est_un_sentier(&self, x: i32, z: i32) -> bool {
+
+        match self.essayer_cellule((x, z)) {
+            Some(cellule) => cellule.est_un_sentier(),
+            None => false,
+        }
+    }
+
+    // Position dans la grille, où la cellule (x, z) couvre l'intervalle [x, x + 1[ × [z, z + 1[
+    pub fn coordonnees_grille(&self, position: &glm::Vec3) -> (f32, f32) {
+
+        ((position.x - self.decalage[0]) / self.cote, (position.z - self.decalage[2]) / self.cote)
+    }
+
+    // Inverse de coordonnees_grille, à la hauteur du sol; le centre de la cellule (x, z) est à (x + 0.5, z + 0.5)
+    pub fn position_monde(&self, x: f32, z: f32) -> glm::Vec3 {
+
+        glm::Vec3::new(x * self.cote + self.decalage[0], self.decalage[1], z * self.cote + self.decalage[2])
+    }
+
+    // Cellules du chemin le plus court entre le départ et la sortie, les deux comprises; vide s'il n'y a aucun sentier
+    // Le départ est le sentier le plus proche de observateur::POSITION_DEPART; la sortie est le sentier le plus éloigné du départ
+    pub fn chemin_sortie(&self) -> &[(i32, i32)] {
+
+        &self.chemin_sortie
+    }
+
+    // Surélève le sol d'un sentier, par exemple pour une marche; 0 le remet à plat
+    // Sans effet sur un mur ou hors du labyrinthe
+    pub fn surelever_sol(&mut self, x: i32, z: i32, hauteur: f32) {
+
+        if self.est_un_sentier(x, z) {
+            self.obtenir_cellule(&Position::new(x as u32, z as u32)).estrade = hauteur;
+        }
     }
 
-    pub fn obtenir_lumieres_proches(&self, observateur: &observateur::Observateur) -> ecran::Lumieres {
+    // Positions des flammes de toutes les torches, pour y placer des particules
+    pub fn positions_flammes(&self) -> std::vec::Vec<[f32; 3]> {
+
+        self.lumieres.iter().map(|lumiere| {
+            [lumiere.position[0], lumiere.position[1], lumiere.position[2]]
+        }).collect()
+    }
+
+    // point_de_vue et direction: ceux de l'observateur, ou d'une autre caméra
+    // temps: secondes écoulées depuis le début, sert à animer les torches
+    pub fn obtenir_lumieres_proches(&self, point_de_vue: &glm::Vec3, direction: &glm::Vec3, temps: f32) -> ecran::Lumieres {
 
         const INFINI: f32 = 1000000.0;
 
-        let position = [observateur.position.x, observateur.position.y, observateur.position.z];
+        let position = [point_de_vue.x, point_de_vue.y, point_de_vue.z];
 
         struct LumiereProche {
             pub distance: f32,
@@ -252,7 +339,7 @@ impl Labyrinthe {
             let dz = self.lumieres[index].position[2] - position[2];
 
             let mut facteur_arriere = 0.0;
-            let distance_z = glm::dot(observateur.direction(), &glm::Vec3::new(dx, dy, dz));
+            let distance_z = glm::dot(direction, &glm::Vec3::new(dx, dy, dz));
             if distance_z < 0.0 {
                 facteur_arriere = 4.0 * distance_z * distance_z;// Permet de moins favoriser les lumières en arrière de l'observateur
             }
@@ -270,18 +357,19 @@ impl Labyrinthe {
 
         for i in 0..std::cmp::min(self.lumieres.len(), NOMBRE_LUMIERES) {
 
-            lumieres.positions[i] = self.lumieres[lumieres_proches[i].index as usize].position;
-            lumieres.couleurs[i] = self.lumieres[lumieres_proches[i].index as usize].couleur;
+            let (position, couleur) = self.lumieres[lumieres_proches[i].index as usize].animer(temps);
+            lumieres.positions[i] = position;
+            lumieres.couleurs[i] = couleur;
         }
 
         const VISION: f32 = 0.5;
-        lumieres.positions[NOMBRE_LUMIERES] = [observateur.position.x, observateur.position.y, observateur.position.z, 1.0];
+        lumieres.positions[NOMBRE_LUMIERES] = [point_de_vue.x, point_de_vue.y, point_de_vue.z, 1.0];
         lumieres.couleurs[NOMBRE_LUMIERES] = [VISION, VISION + 0.02, VISION + 0.04, 1.0];
 
         lumieres
     }
 
-    fn detruire_murs(&mut self) {
+    fn detruire_murs(&mut self, algorithme: Algorithme) {
 
         let position_depart = self.position_aleatoire();
 
@@ -294,7 +382,7 @@ impl Labyrinthe {
         
         while sentiers_explorables.len() > 0 {
 
-            let choix_sentier = entier_aleatoire(sentiers_explorables.len() as u32) as usize;
+            let choix_sentier = algorithme.choisir_sentier(sentiers_explorables.len());
 
             let position_courante = sentiers_explorables[choix_sentier];
 
@@ -321,7 +409,7 @@ impl Labyrinthe {
             }
             else { // On ouvre au hasard un sentier parmis les choix possibles
 
-                let position_choisie = sentiers_a_ouvrir_possibles[entier_aleatoire(sentiers_a_ouvrir_possibles.len() as u32) as usize];
+                let position_choisie = sentiers_a_ouvrir_possibles[aleatoire::entier(sentiers_a_ouvrir_possibles.len() as u32) as usize];
 
                 self.obtenir_cellule(
                     &Position::new(position_choisie.0 as u32, position_choisie.1 as u32)
@@ -408,7 +496,7 @@ impl Labyrinthe {
 
             for z in 1..self.largeur - 1 {
 
-                let doit_ajouter = entier_aleatoire(4) == 0;
+                let doit_ajouter = aleatoire::entier(4) == 0;
 
                 if doit_ajouter {
                     let lumiere = self.obtenir_cellule(&Position::new(x, z)).essayer_eclairer(hauteur, cote, &decalage);
@@ -422,9 +510,145 @@ impl Labyrinthe {
         }
     }
 
+    // Chaque cul-de-sac a une estrade, assez basse pour y monter en marchant
+    // Ne dépend pas du générateur aléatoire: le reste du labyrinthe est le même avec la même graine
+    fn ajouter_estrades(&mut self) {
+
+        const HAUTEUR_ESTRADE: f32 = 0.15;
+
+        for z in 0..self.largeur as i32 {
+
+            for x in 0..self.longueur as i32 {
+
+                let voisins = [(x - 1, z), (x + 1, z), (x, z - 1), (x, z + 1)].iter()
+                    .filter(|voisin| self.est_un_sentier(voisin.0, voisin.1))
+                    .count();
+
+                if voisins == 1 {
+                    self.surelever_sol(x, z, HAUTEUR_ESTRADE);
+                }
+            }
+        }
+    }
+
+    // Dessus de l'estrade, et une contremarche vers chaque sentier voisin plus bas
+    fn ajouter_geometrie_estrade(&self,
+        x: i32,
+        z: i32,
+        materiau_sol: materiaux::IdMateriau,
+        materiau_mur: materiaux::IdMateriau,
+        donnees_opengl: &mut donnees::DonneesOpenGL) {
+
+        let estrade = match self.essayer_cellule((x, z)) {
+            Some(cellule) if cellule.estrade > 0.0 => cellule.estrade,
+            _ => return,
+        };
+
+        let (x_gauche, z_bas) = (self.decalage[0] + x as f32 * self.cote, self.decalage[2] + z as f32 * self.cote);
+        let (x_droit, z_haut) = (x_gauche + self.cote, z_bas + self.cote);
+        let dessus = self.decalage[1] + estrade;
+
+        donnees_opengl.ajouter_plan(
+            [4, 4],
+            [x_gauche, dessus, z_bas],
+            [x_gauche, dessus, z_haut],
+            [x_droit, dessus, z_bas],
+            materiau_sol
+        );
+
+        // Mêmes orientations que les murs d'une cellule, voir Cellule::ajouter_geometrie
+        let contremarches = [
+            ((x - 1, z), [x_gauche, z_haut], [x_gauche, z_bas]),
+            ((x, z + 1), [x_droit, z_haut], [x_gauche, z_haut]),
+            ((x + 1, z), [x_droit, z_bas], [x_droit, z_haut]),
+            ((x, z - 1), [x_gauche, z_bas], [x_droit, z_bas]),
+        ];
+
+        for (voisin, debut, fin) in contremarches.iter() {
+
+            let bas = match self.essayer_cellule(*voisin) {
+                Some(cellule) if cellule.est_un_sentier() && cellule.estrade < estrade => self.decalage[1] + cellule.estrade,
+                _ => continue,
+            };
+
+            donnees_opengl.ajouter_plan(
+                [4, 1],
+                [debut[0], bas, debut[1]],
+                [debut[0], dessus, debut[1]],
+                [fin[0], bas, fin[1]],
+                materiau_mur
+            );
+        }
+    }
+
+    // Chemin le plus court du sentier le plus proche de la position jusqu'au sentier le plus éloigné
+    fn calculer_chemin_sortie(&self, position: &glm::Vec3) -> std::vec::Vec<(i32, i32)> {
+
+        let depart = match self.sentier_proche(position) {
+            Some(depart) => depart,
+            None => return std::vec::Vec::new(),
+        };
+
+        // Parcours en largeur: la dernière cellule atteinte est la plus éloignée
+        let mut precedentes = std::collections::HashMap::new();
+        let mut a_visiter = std::collections::VecDeque::new();
+        let mut sortie = depart;
+
+        precedentes.insert(depart, depart);
+        a_visiter.push_back(depart);
+
+        while let Some((x, z)) = a_visiter.pop_front() {
+
+            sortie = (x, z);
+
+            for voisine in [(x - 1, z), (x + 1, z), (x, z - 1), (x, z + 1)].iter() {
+
+                if self.est_un_sentier(voisine.0, voisine.1) && !precedentes.contains_key(voisine) {
+                    precedentes.insert(*voisine, (x, z));
+                    a_visiter.push_back(*voisine);
+                }
+            }
+        }
+
+        // On remonte de la sortie jusqu'au départ
+        let mut chemin = vec![sortie];
+        let mut cellule = sortie;
+
+        while cellule != depart {
+            cellule = precedentes[&cellule];
+            chemin.push(cellule);
+        }
+
+        chemin.reverse();
+        chemin
+    }
+
+    fn sentier_proche(&self, position: &glm::Vec3) -> Option<(i32, i32)> {
+
+        let (x_position, z_position) = self.coordonnees_grille(position);
+        let mut plus_proche = None;
+        let mut distance_min = f32::INFINITY;
+
+        for z in 0..self.largeur as i32 {
+
+            for x in 0..self.longueur as i32 {
+
+                let (dx, dz) = (x as f32 + 0.5 - x_position, z as f32 + 0.5 - z_position);
+                let distance = dx * dx + dz * dz;
+
+                if self.est_un_sentier(x, z) && distance < distance_min {
+                    plus_proche = Some((x, z));
+                    distance_min = distance;
+                }
+            }
+        }
+
+        plus_proche
+    }
+
     fn position_aleatoire(&self) -> Position {
 
-        Position::new(entier_aleatoire(self.longueur), entier_aleatoire(self.largeur))
+        Position::new(aleatoire::entier(self.longueur), aleatoire::entier(self.largeur))
     }
 
     fn lire_cellule(&self, position: &Position) -> &Cellule {
@@ -463,6 +687,68 @@ impl Labyrinthe {
     }
 }
 
+// Volume aligné sur les axes, voir Labyrinthe::boites_collision et collision::balayer
+pub struct BoiteCollision {
+
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+// Façon de choisir le prochain sentier à prolonger lors de la génération
+#[derive(Clone, Copy, PartialEq)]
+pub enum Algorithme {
+
+    Aleatoire, // Beaucoup de courts embranchements
+    Profondeur, // Longs couloirs sinueux, comme un parcours en profondeur
+    Mixte, // Entre les deux
+}
+
+impl Algorithme {
+
+    pub const TOUS: [Algorithme; 3] = [
+        Algorithme::Aleatoire,
+        Algorithme::Profondeur,
+        Algorithme::Mixte,
+    ];
+
+    pub fn nom(self) -> &'static str {
+
+        match self {
+            Algorithme::Aleatoire => "Aléatoire",
+            Algorithme::Profondeur => "Profondeur",
+            Algorithme::Mixte => "Mixte",
+        }
+    }
+
+    pub fn suivant(self) -> Algorithme {
+
+        match self {
+            Algorithme::Aleatoire => Algorithme::Profondeur,
+            Algorithme::Profondeur => Algorithme::Mixte,
+            Algorithme::Mixte => Algorithme::Aleatoire,
+        }
+    }
+
+    pub fn precedent(self) -> Algorithme {
+
+        self.suivant().suivant()
+    }
+
+    // Index parmi les sentiers explorables, le dernier étant le plus récent
+    fn choisir_sentier(self, nombre_sentiers: usize) -> usize {
+
+        let dernier = nombre_sentiers - 1;
+
+        match self {
+            Algorithme::Aleatoire => aleatoire::entier(nombre_sentiers as u32) as usize,
+            Algorithme::Profondeur => dernier,
+            Algorithme::Mixte => {
+                if aleatoire::entier(2) == 0 { dernier } else { aleatoire::entier(nombre_sentiers as u32) as usize }
+            },
+        }
+    }
+}
+
 
 
 
@@ -471,20 +757,26 @@ impl Labyrinthe {
     Partie privée du module labyrinthe
 */
 
-fn entier_aleatoire(limite: u32) -> u32 {
+// Bruit de valeur en une dimension, continu et compris entre -1 et 1
+fn bruit(x: f32) -> f32 {
 
-    use self::rand::{Rng};
-    let mut rng = rand::thread_rng();
-
-    rng.gen_range(0, limite)
-}
+    // Valeur pseudo-aléatoire associée à chaque entier
+    let valeur_entier = |n: i32| -> f32 {
+        let mut h = (n as u32).wrapping_mul(0x27d4_eb2d);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x85eb_ca6b);
+        h ^= h >> 13;
+        (h & 0xffff) as f32 / 32767.5 - 1.0
+    };
 
-fn nombre_aleatoire(limite: f32) -> f32 {
+    let entier = x.floor();
+    let fraction = x - entier;
+    let lissage = fraction * fraction * (3.0 - 2.0 * fraction); // Évite les cassures entre deux entiers
 
-    use self::rand::{Rng};
-    let mut rng = rand::thread_rng();
+    let a = valeur_entier(entier as i32);
+    let b = valeur_entier(entier as i32 + 1);
 
-    limite * rng.gen_range(0, 10000) as f32 / 10000.0
+    a + (b - a) * lissage
 }
 
 #[derive(Clone)]
@@ -511,6 +803,7 @@ struct Lumiere {
     pub position: [f32; 4],
     pub position_bas: [f32; 4],
     pub couleur: [f32; 4],
+    graine: f32, // Décale le bruit pour que les torches ne vacillent pas toutes ensemble
 }
 
 impl Lumiere {
@@ -524,19 +817,49 @@ impl Lumiere {
             position: [x, y, z, 1.0],
             position_bas: [x_bas, y_bas, z_bas, 1.0],
             couleur: [
-                LUMIERE_BASE + nombre_aleatoire(LUMIERE_ALEATOIRE),
-                LUMIERE_BASE + nombre_aleatoire(LUMIERE_ALEATOIRE),
-                LUMIERE_BASE + nombre_aleatoire(LUMIERE_ALEATOIRE),
-                1.0]
+                LUMIERE_BASE + aleatoire::nombre(LUMIERE_ALEATOIRE),
+                LUMIERE_BASE + aleatoire::nombre(LUMIERE_ALEATOIRE),
+                LUMIERE_BASE + aleatoire::nombre(LUMIERE_ALEATOIRE),
+                1.0],
+            graine: aleatoire::nombre(1000.0),
         }
     }
 
-    pub fn ajouter_geometrie(&self, texture_id: f32, donnees_opengl: &mut donnees::DonneesOpenGL) {
+    // Donne la position et la couleur de la lumière à un instant donné, pour simuler une flamme qui vacille
+    pub fn animer(&self, temps: f32) -> ([f32; 4], [f32; 4]) {
+
+        const VITESSE_VACILLEMENT: f32 = 6.0;
+        const AMPLITUDE_INTENSITE: f32 = 0.2;
+        const AMPLITUDE_POSITION: f32 = 0.015;
+
+        let t = temps * VITESSE_VACILLEMENT + self.graine;
+
+        // Deux octaves de bruit: une lente et une rapide
+        let intensite = 1.0 + AMPLITUDE_INTENSITE * (0.7 * bruit(t) + 0.3 * bruit(3.7 * t));
+
+        let position = [
+            self.position[0] + AMPLITUDE_POSITION * bruit(t + 31.0),
+            self.position[1] + AMPLITUDE_POSITION * bruit(t + 57.0),
+            self.position[2] + AMPLITUDE_POSITION * bruit(t + 83.0),
+            self.position[3]
+        ];
+
+        let couleur = [
+            self.couleur[0] * intensite,
+            self.couleur[1] * intensite,
+            self.couleur[2] * intensite,
+            self.couleur[3]
+        ];
+
+        (position, couleur)
+    }
+
+    pub fn ajouter_geometrie(&self, materiau: materiaux::IdMateriau, donnees_opengl: &mut donnees::DonneesOpenGL) {
 
         donnees_opengl.ajouter_torche(
             [self.position[0], self.position[1], self.position[2]],
             [self.position_bas[0], self.position_bas[1], self.position_bas[2]],
-            texture_id);
+            materiau);
     }
 }
 
@@ -548,6 +871,7 @@ struct Cellule {
 
     sentier: bool,
     eclaire: bool,
+    estrade: f32, // Hauteur du sol au-dessus de celui du labyrinthe, 0 le plus souvent
 
     // Sens selon une vue de dessus
     pub mur_gauche: bool,
@@ -567,6 +891,7 @@ impl Cellule {
 
             sentier: false,
             eclaire: false,
+            estrade: 0.0,
 
             mur_gauche: true,
             mur_haut: true,
@@ -609,13 +934,13 @@ impl Cellule {
             let ecart_bas = cote * 0.45;
             let hauteur_bas = y - hauteur * 0.2;
 
-            if self.mur_gauche && entier_aleatoire(4) == 0 {
+            if self.mur_gauche && aleatoire::entier(4) == 0 {
                 return Some(Lumiere::new(x - ecart_centre, y, z, x - ecart_bas, hauteur_bas, z));
             }
-            if self.mur_haut && entier_aleatoire(3) == 0 {
+            if self.mur_haut && aleatoire::entier(3) == 0 {
                 return Some(Lumiere::new(x, y, z + ecart_centre, x, hauteur_bas, z + ecart_bas));
             }
-            if self.mur_droit && entier_aleatoire(2) == 0 {
+            if self.mur_droit && aleatoire::entier(2) == 0 {
                 return Some(Lumiere::new(x + ecart_centre, y, z, x + ecart_bas, hauteur_bas, z));
             }
             if self.mur_bas {
@@ -631,7 +956,7 @@ impl Cellule {
         hauteur: f32, // dimension y
         cote: f32, // dimension x et z
         decalage: &[f32; 3],
-        texture: &[f32; 3], // longueur, hauteur, id 
+        materiau: materiaux::IdMateriau,
         donnees_opengl: &mut donnees::DonneesOpenGL)
         {
 
@@ -649,7 +974,7 @@ impl Cellule {
                 [decalage[0] + x * cote, decalage[1], decalage[2] + (z + 1.0) * cote],
                 [decalage[0] + x * cote, decalage[1] + hauteur, decalage[2] + (z + 1.0) * cote],
                 [decalage[0] + x * cote, decalage[1], decalage[2] + z * cote],
-                texture.clone(),
+                materiau,
             );
         }
 
@@ -660,7 +985,7 @@ impl Cellule {
                 [decalage[0] + (x + 1.0) * cote, decalage[1], decalage[2] + (z + 1.0) * cote],
                 [decalage[0] + (x + 1.0) * cote, decalage[1] + hauteur, decalage[2] + (z + 1.0) * cote],
                 [decalage[0] + x * cote, decalage[1], decalage[2] + (z + 1.0) * cote],
-                texture.clone(),
+                materiau,
             );
         }
 
@@ -671,7 +996,7 @@ impl Cellule {
                 [decalage[0] + (x + 1.0) * cote, decalage[1], decalage[2] + z * cote],
                 [decalage[0] + (x + 1.0) * cote, decalage[1] + hauteur, decalage[2] + z * cote],
                 [decalage[0] + (x + 1.0) * cote, decalage[1], decalage[2] + (z + 1.0) * cote],
-                texture.clone(),
+                materiau,
             );
         }
 
@@ -682,7 +1007,7 @@ impl Cellule {
                 [decalage[0] + x * cote, decalage[1], decalage[2] + z * cote],
                 [decalage[0] + x * cote, decalage[1] + hauteur, decalage[2] + z * cote],
                 [decalage[0] + (x + 1.0) * cote, decalage[1], decalage[2] + z * cote],
-                texture.clone(),
+                materiau,
             );
         }
     }
@@ -4,17 +4,64 @@ use glium::{glutin};
 extern crate nalgebra_glm as glm;
 extern crate image;
 
+#[macro_use]
+mod blocs_uniformes; // Décrire les blocs uniformes, doit précéder les modules qui l'utilisent
 mod labyrinthe; // Générer le labyrinthe
+mod collision; // Déplacer sans traverser les murs
 mod shaders; // Construire les shaders nécéssaires
 mod donnees; // Gérer les données associées avec OpenGL
 mod ecran; // Dessiner et d'interagir avec l'écran
 mod evenements; // Gérer le clavier, la souris, etc.
 mod observateur; // Contrôler la caméra
 mod textures; // Charger et utiliser des textures
+mod particules; // Animer les flammes des torches
+mod materiaux; // Décrire l'apparence des surfaces
+mod configuration; // Lire les paramètres du programme
+mod post_traitement; // Appliquer des effets sur l'image complète
+mod hors_ecran; // Dessiner sans fenêtre
+mod capture; // Enregistrer les images dessinées
+mod arguments; // Lire la ligne de commande
+mod aleatoire; // Générer des nombres aléatoires reproductibles
+mod carte; // Dessiner le labyrinthe vu de dessus
+mod texte; // Dessiner du texte avec une police bitmap
+mod hud; // Afficher des informations par-dessus la scène
+mod menu; // Mettre en pause et modifier les réglages
+mod debogage; // Visualiser ce que fait le rendu
+mod commandes; // Associer les touches aux commandes
+mod manette; // Lire une manette de jeu
+mod camera; // Choisir le point de vue
+mod survol; // Parcourir le chemin jusqu'à la sortie
+mod enregistrement; // Enregistrer et rejouer les événements
 
 fn main() {
 
-    // Avant d'ouvrir la fenêtre, on charge les images et on crée le labyrinthe
+    // Avant d'ouvrir la fenêtre, on charge la configuration, les images et on crée le labyrinthe
+
+    let arguments = arguments::Arguments::lire();
+
+    // Le rejeu se fait sans fenêtre, avec la configuration enregistrée
+    if let Some(ref chemin) = arguments.rejeu {
+
+        if let Err(erreur) = rejouer(chemin, &arguments) {
+
+            println!("Erreur: {}", erreur);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    let configuration = configuration::Configuration::charger(&arguments.configuration);
+
+    // Un enregistrement doit pouvoir générer le même labyrinthe; une graine est donc toujours choisie
+    let graine = match arguments.graine {
+        None if arguments.enregistrement.is_some() => Some(aleatoire::entier(u32::MAX) as u64),
+        graine => graine,
+    };
+
+    if let Some(graine) = graine {
+        aleatoire::initialiser(graine);
+    }
 
     const BRIQUES: &str = "briques";
     const PAVEE: &str = "pavee";
@@ -26,7 +73,78 @@ fn main() {
     const EXTENSION: &str = ".png";
     textures.charger_images(&[BRIQUES, PAVEE, BOIS, TORCHE], EXTENSION);
 
-    let labyrinthe = labyrinthe::Labyrinthe::new(12, 12);
+    // Les échelles sont en répétitions de texture par unité de distance
+    let mut materiaux = materiaux::Materiaux::new();
+    let materiau_plafond = materiaux.ajouter(materiaux::Materiau {
+        normales: Some(textures.obtenir_normales(BOIS, 2.0)),
+        speculaire: textures.obtenir_speculaire(BOIS),
+        intensite_speculaire: 0.15,
+        rugosite: 0.9,
+        .. materiaux::Materiau::new(textures.obtenir_id(BOIS))
+    });
+    let materiau_sol = materiaux.ajouter(materiaux::Materiau {
+        normales: Some(textures.obtenir_normales(PAVEE, 3.0)),
+        speculaire: textures.obtenir_speculaire(PAVEE),
+        echelle: [2.0, 2.0],
+        intensite_speculaire: 0.45,
+        rugosite: 0.4,
+        .. materiaux::Materiau::new(textures.obtenir_id(PAVEE))
+    });
+    let materiau_mur = materiaux.ajouter(materiaux::Materiau {
+        normales: Some(textures.obtenir_normales(BRIQUES, 4.0)),
+        speculaire: textures.obtenir_speculaire(BRIQUES),
+        echelle: [2.0, 1.0],
+        intensite_speculaire: 0.2,
+        rugosite: 0.8,
+        .. materiaux::Materiau::new(textures.obtenir_id(BRIQUES))
+    });
+    let materiau_torche = materiaux.ajouter(materiaux::Materiau {
+        emission: [0.15, 0.08, 0.02],
+        intensite_speculaire: 0.3,
+        rugosite: 0.6,
+        .. materiaux::Materiau::new(textures.obtenir_id(TORCHE))
+    });
+
+    let materiaux_labyrinthe = MateriauxLabyrinthe {
+        plafond: materiau_plafond,
+        sol: materiau_sol,
+        mur: materiau_mur,
+        torche: materiau_torche,
+    };
+
+    // Un autre labyrinthe peut être généré à partir du menu
+    let generation = menu::ParametresGeneration {
+        longueur: 12,
+        largeur: 12,
+        graine: arguments.graine,
+        algorithme: labyrinthe::Algorithme::Aleatoire,
+    };
+
+    let mut labyrinthe = labyrinthe::Labyrinthe::new(generation.longueur, generation.largeur, generation.algorithme);
+    let mut donnees_opengl = materiaux_labyrinthe.generer_geometrie(&labyrinthe);
+
+    // Sans fenêtre, on dessine une seule image ou le survol, puis on quitte
+    if arguments.rendu_image.is_some() || arguments.survol.is_some() {
+
+        let scene = SceneHorsEcran {
+            textures,
+            materiaux,
+            donnees_opengl,
+        };
+
+        let resultat = match arguments.survol {
+            Some(ref dossier) => enregistrer_survol(dossier, &arguments, &configuration, &labyrinthe, scene),
+            None => dessiner_hors_ecran(arguments.rendu_image.as_ref().unwrap(), &arguments, &configuration, &labyrinthe, scene),
+        };
+
+        if let Err(erreur) = resultat {
+
+            println!("Erreur: {}", erreur);
+            std::process::exit(1);
+        }
+
+        return;
+    }
 
 
     // Initialisation des composantes graphiques principales
@@ -40,41 +158,94 @@ fn main() {
         .with_vsync(true)    
         .with_depth_buffer(24);
         // 24 bits est un choix commun pour le depth buffer
-    let affichage = glium::Display::new(
-            parametres_fenetre,
-            contexte_opengl,
-            &boucle_evenements
-        ).unwrap();
+
+    // Le MSAA de la fenêtre sert lorsque la scène y est dessinée directement; sinon, voir post_traitement
+    // La prépasse de profondeur et la scène utilisent alors le même tampon multiéchantillonné
+    let echantillons = configuration.post_traitement.msaa as u16;
+    let affichage = match glium::Display::new(
+            parametres_fenetre.clone(),
+            contexte_opengl.clone().with_multisampling(echantillons),
+            &boucle_evenements)
+    {
+        Ok(affichage) => affichage,
+        Err(erreur) => {
+
+            println!("La fenêtre n'a pas pu être créée avec le MSAA à {} échantillons, il est désactivé: {}", echantillons, erreur);
+            glium::Display::new(parametres_fenetre, contexte_opengl, &boucle_evenements).unwrap()
+        }
+    };
     
     textures.generer_textures(&affichage);
+    materiaux.generer_tampon(&affichage);
     
     
     // Variables importantes pour OpenGL
 
-    let programme_opengl = shaders::ProgrammeOpenGL::new(&affichage);
-    let mut donnees_opengl = donnees::DonneesOpenGL::new();
-    labyrinthe.ajouter_geometrie(
-        [1.0, 1.0, textures.obtenir_id(BOIS)],
-        [2.0, 2.0, textures.obtenir_id(PAVEE)],
-        [2.0, 2.0, textures.obtenir_id(BRIQUES)],
-        textures.obtenir_id(TORCHE),
-        &mut donnees_opengl);
-
+    let mut programme_opengl = shaders::ProgrammeOpenGL::new(&affichage);
     donnees_opengl.generer_vertex_buffer(&affichage);
     
-    let mut vue = ecran::Vue::new();
+    let mut vue = ecran::Vue::new(&configuration);
+    let mut particules = particules::SystemeParticules::new(&labyrinthe.positions_flammes());
+    let mut carte = carte::Carte::new(&labyrinthe, &configuration.carte);
+    let mut hud = hud::Hud::new(&configuration.hud);
     
     
     // Variables utiles à la logique du programme
 
-    let mut gestionnaire_evenements = evenements::GestionnaireEvenements::new(&affichage);
+    let manette = manette::Manette::ouvrir(&configuration.manette, arguments.script_manette.as_deref());
+    let mut gestionnaire_evenements = evenements::GestionnaireEvenements::new(manette);
+
+    hud.afficher_message(&format!("{}: menu, {}: carte, {}: capture d'écran",
+        gestionnaire_evenements.description(commandes::Commande::Menu),
+        gestionnaire_evenements.description(commandes::Commande::Carte),
+        gestionnaire_evenements.description(commandes::Commande::Capture)));
 
     let mut observateur = observateur::Observateur::new(
-        glm::Vec3::new(0.0, 1.5, 0.0),
+        glm::Vec3::new(observateur::POSITION_DEPART[0], observateur::POSITION_DEPART[1], observateur::POSITION_DEPART[2]),
         glm::Vec3::new(0.0, 0.0, 1.0),
     );
 
-    const TAUX_RAFRAICHISSEMENT: u64 = 60;
+    observateur.balancement = configuration.balancement;
+
+    if configuration.mode_aerien {
+        observateur.basculer_mode_aerien();
+    }
+
+    let mut camera = camera::Camera::new();
+    let mut survol: Option<survol::Survol> = None; // Remplace la caméra jusqu'à la sortie
+
+    let mut menu = menu::Menu::new(&configuration, generation);
+    menu.reglages.appliquer(&configuration, &mut vue, &mut observateur, &mut carte, &mut hud, &affichage);
+
+    // Une erreur d'écriture arrête l'enregistrement, mais pas le programme
+    let mut enregistreur = match arguments.enregistrement {
+
+        Some(ref chemin) => {
+
+            let contenu_configuration = std::fs::read_to_string(&arguments.configuration).unwrap_or_default();
+
+            match enregistrement::Enregistreur::creer(chemin, &contenu_configuration) {
+
+                Ok(mut enregistreur) => {
+
+                    let generation = &menu.generation;
+                    enregistreur.nouveau_labyrinthe(graine.unwrap(), generation.longueur, generation.largeur, generation.algorithme);
+                    Some(enregistreur)
+                },
+                Err(erreur) => {
+                    println!("L'enregistrement {} n'a pas pu être créé: {}", chemin, erreur);
+                    None
+                }
+            }
+        },
+
+        None => None,
+    };
+
+    // Les animations suivent le temps réel, peu importe le taux de rafraîchissement
+    let mut date_derniere_image = std::time::Instant::now();
+    let mut temps = 0.0; // Secondes écoulées hors de la pause
+    let mut temps_a_simuler = 0.0; // Temps écoulé qui n'a pas encore donné un pas complet
     
     // Cette closure représente la boucle principale du programme
     boucle_evenements.run(move |evenement, _, flot_controle| {
@@ -93,36 +264,400 @@ fn main() {
                 gestionnaire_evenements.gerer_evenement_fenetre(&evenement);
                 return;
             },
+
+            glutin::event::Event::DeviceEvent{event: evenement, ..} => {
+
+                gestionnaire_evenements.gerer_evenement_peripherique(&evenement);
+                return;
+            },
             
-            // On peut procéder à l'affichage dans ces scénarios
-            glutin::event::Event::NewEvents(glutin::event::StartCause::ResumeTimeReached{..}) => (),
-            glutin::event::Event::NewEvents(glutin::event::StartCause::Init) => (),
+            // Une image est dessinée après chaque groupe d'événements;
+            // la synchronisation verticale règle le rythme, la boucle reprend donc aussitôt
+            // Réduite, la fenêtre n'est pas dessinée et rien n'attend la synchronisation: la boucle attend alors un peu
+            glutin::event::Event::EventsCleared => {
+
+                const ATTENTE_REDUITE: std::time::Duration = std::time::Duration::from_millis(100);
+
+                let (largeur, hauteur) = affichage.get_framebuffer_dimensions();
+
+                *flot_controle = if largeur == 0 || hauteur == 0 {
+                    glutin::event_loop::ControlFlow::WaitUntil(std::time::Instant::now() + ATTENTE_REDUITE)
+                }
+                else {
+                    glutin::event_loop::ControlFlow::Poll
+                };
+            },
 
             // Sinon, on ignore l'évènement
             _ => return 
         }
-        
-        // Permet de redéclencher la boucle pour la prochaine fois
-        let prochaine_date_affichage = std::time::Instant::now() +
-            std::time::Duration::from_nanos(1_000_000_000 / TAUX_RAFRAICHISSEMENT);
-        *flot_controle = glutin::event_loop::ControlFlow::WaitUntil(prochaine_date_affichage);
 
 
         // Logique du programme
 
-        observateur.deplacer(&gestionnaire_evenements, TAUX_RAFRAICHISSEMENT);
-        labyrinthe.expulser_murs(&mut observateur);
+        let maintenant = std::time::Instant::now();
+        let delta = maintenant.duration_since(date_derniere_image).as_secs_f32();
+        date_derniere_image = maintenant;
 
-        if gestionnaire_evenements.souris.mode_centre {
-            observateur.ajuster_direction(&gestionnaire_evenements, TAUX_RAFRAICHISSEMENT);
+        gestionnaire_evenements.mise_a_jour_pre_logique();
+
+        match menu.mettre_a_jour(&mut gestionnaire_evenements, &affichage) {
+
+            Some(menu::Action::Pause) => gestionnaire_evenements.capturer_souris(&affichage, false),
+            Some(menu::Action::Reprendre) => gestionnaire_evenements.capturer_souris(&affichage, true),
+            Some(menu::Action::ReglagesModifies) => {
+                menu.reglages.appliquer(&configuration, &mut vue, &mut observateur, &mut carte, &mut hud, &affichage);
+            },
+            Some(menu::Action::NouveauLabyrinthe) => {
+
+                // La graine est affichée pour pouvoir retrouver le labyrinthe
+                let graine = menu.generation.graine.unwrap_or_else(|| aleatoire::entier(u32::MAX) as u64);
+                aleatoire::initialiser(graine);
+
+                let generation = &menu.generation;
+                labyrinthe = labyrinthe::Labyrinthe::new(generation.longueur, generation.largeur, generation.algorithme);
+
+                if let Some(ref mut enregistreur) = enregistreur {
+                    enregistreur.nouveau_labyrinthe(graine, generation.longueur, generation.largeur, generation.algorithme);
+                }
+
+                donnees_opengl = materiaux_labyrinthe.generer_geometrie(&labyrinthe);
+                donnees_opengl.generer_vertex_buffer(&affichage);
+
+                particules = particules::SystemeParticules::new(&labyrinthe.positions_flammes());
+                carte = carte::Carte::new(&labyrinthe, &carte.parametres);
+                observateur.placer(glm::Vec3::new(observateur::POSITION_DEPART[0], observateur::POSITION_DEPART[1], observateur::POSITION_DEPART[2]));
+                camera.changer_mode(camera.mode, &observateur, &labyrinthe); // L'orbite suit le nouveau départ
+                survol = None;
+
+                hud.afficher_message(&format!("Nouveau labyrinthe, graine {}", graine));
+                gestionnaire_evenements.capturer_souris(&affichage, true);
+            },
+            Some(menu::Action::Quitter) => {
+
+                *flot_controle = glutin::event_loop::ControlFlow::Exit;
+                return;
+            },
+            None => (),
         }
+
+        // Le menu met le programme en pause
+        let en_pause = menu.est_ouvert();
+        let survol_actif = survol.is_some();
+
+        if !en_pause {
+
+            temps += delta;
+
+            // Pendant le survol, l'observateur attend où il était
+            if let Some(ref mut survol) = survol {
+
+                survol.avancer(delta);
+                temps_a_simuler = 0.0;
+            }
+            else {
+
+                observateur.simuler(&gestionnaire_evenements, &labyrinthe, delta, &mut temps_a_simuler);
+            }
+
+            if survol.as_ref().is_some_and(|survol| survol.termine()) {
+
+                survol = None;
+                hud.afficher_message("Sortie atteinte");
+            }
+        }
+
+        carte.mettre_a_jour(&labyrinthe, &observateur);
+        hud.mettre_a_jour(temps, delta, &labyrinthe, &observateur);
         
-        gestionnaire_evenements.mise_a_jour_post_logique(&affichage);
+        if gestionnaire_evenements.commande_declenchee(commandes::Commande::Carte) {
+            carte.basculer_plein_ecran();
+        }
+
+        if !en_pause && gestionnaire_evenements.commande_declenchee(commandes::Commande::ModeAerien) {
+
+            let mode_aerien = observateur.basculer_mode_aerien();
+            hud.afficher_message(if mode_aerien { "Mode aérien" } else { "Mode marche" });
+        }
+
+        if !en_pause && gestionnaire_evenements.commande_declenchee(commandes::Commande::Camera) {
+
+            camera.changer_mode(camera.mode.suivant(), &observateur, &labyrinthe);
+            hud.afficher_message(&format!("Caméra: {}", camera.mode.nom()));
+        }
+
+        if !en_pause && gestionnaire_evenements.commande_declenchee(commandes::Commande::Survol) {
+
+            if survol.is_some() {
+                survol = None;
+                hud.afficher_message("Survol interrompu");
+            }
+            else {
+                survol = Some(survol::Survol::new(&labyrinthe, configuration.vitesse_survol));
+                hud.afficher_message("Survol du chemin vers la sortie");
+            }
+        }
+
+        vue.debogage.mettre_a_jour(&gestionnaire_evenements, &labyrinthe, &observateur, &mut hud, &affichage);
+
+        let capture_demandee = gestionnaire_evenements.commande_declenchee(commandes::Commande::Capture);
+
+        let image = enregistrement::Image {
+            delta,
+            pause: en_pause,
+            survol: survol_actif,
+        };
+        let erreur = enregistreur.as_mut()
+            .and_then(|enregistreur| enregistreur.enregistrer_image(&image, &gestionnaire_evenements, &observateur).err());
+
+        if let Some(erreur) = erreur {
+
+            println!("L'enregistrement est interrompu: {}", erreur);
+            enregistreur = None;
+        }
+
+        gestionnaire_evenements.mise_a_jour_post_logique();
+
+        if !en_pause {
+            particules.mettre_a_jour(delta);
+        }
 
         // Affichage du programme
-        vue.changer_camera(&observateur.position, observateur.direction());
+        programme_opengl.recharger_modifies(&affichage);
+        let yeux = observateur.position_camera(temps_a_simuler / observateur::PAS_SIMULATION);
+        camera.mettre_a_jour(&yeux, &observateur, &labyrinthe, &vue.parametres_camera, delta);
+
+        // Les lumières sont choisies autour du point de vue: vu de dessus, tout le labyrinthe doit être éclairé
+        let lumieres = match survol {
 
-        let lumieres = labyrinthe.obtenir_lumieres_proches(&observateur);
-        vue.dessiner(lumieres, &donnees_opengl, &programme_opengl, &affichage);
+            Some(ref survol) => {
+
+                vue.changer_camera(survol.position(), survol.direction());
+                vue.attenuer_brouillard(1.0);
+                labyrinthe.obtenir_lumieres_proches(survol.position(), survol.direction(), temps)
+            },
+
+            None => {
+
+                vue.changer_camera(camera.position(), camera.direction());
+                vue.attenuer_brouillard(camera.brouillard());
+                labyrinthe.obtenir_lumieres_proches(camera.position(), camera.direction(), temps)
+            },
+        };
+
+        // Vu de l'extérieur, une silhouette montre où est l'observateur
+        let mut silhouette = None;
+
+        if survol.is_none() && camera.montre_observateur() {
+
+            let mut donnees_silhouette = donnees::DonneesOpenGL::new();
+            let pieds = yeux - glm::Vec3::new(0.0, observateur.hauteur_yeux(), 0.0);
+
+            donnees_silhouette.ajouter_silhouette(&pieds, observateur.direction(), observateur.hauteur_yeux() + 0.1, materiaux_labyrinthe.torche);
+            donnees_silhouette.generer_vertex_buffer(&affichage);
+            silhouette = Some(donnees_silhouette);
+        }
+
+        let scene = ecran::Scene {
+            donnees_opengl: &donnees_opengl,
+            textures: &textures,
+            materiaux: &materiaux,
+            silhouette: silhouette.as_ref(),
+        };
+        let interface = ecran::Interface {
+            carte: &carte,
+            hud: &hud,
+            menu: &menu,
+        };
+        vue.dessiner(lumieres, &particules, &scene, &programme_opengl, &interface, &affichage);
+
+        // La capture est dessinée à nouveau hors de l'écran, à la taille de la fenêtre
+        if capture_demandee {
+
+            let image = vue.dessiner_image(lumieres, &particules, &scene, &programme_opengl, Some(&interface), &affichage);
+            let chemin = capture::chemin_capture();
+
+            match capture::enregistrer(&image, &chemin) {
+                Ok(()) => {
+                    println!("Capture d'écran enregistrée: {}", chemin);
+                    hud.afficher_message("Capture d'écran enregistrée");
+                },
+                Err(erreur) => println!("Erreur lors de la capture d'écran: {}", erreur),
+            }
+        }
     });
-}
\ No newline at end of file
+}
+
+// Matériaux de la géométrie du labyrinthe, conservés pour en générer un autre
+struct MateriauxLabyrinthe {
+
+    plafond: materiaux::IdMateriau,
+    sol: materiaux::IdMateriau,
+    mur: materiaux::IdMateriau,
+    torche: materiaux::IdMateriau,
+}
+
+impl MateriauxLabyrinthe {
+
+    fn generer_geometrie(&self, labyrinthe: &labyrinthe::Labyrinthe) -> donnees::DonneesOpenGL {
+
+        let mut donnees_opengl = donnees::DonneesOpenGL::new();
+        labyrinthe.ajouter_geometrie(
+            self.plafond,
+            self.sol,
+            self.mur,
+            self.torche,
+            &mut donnees_opengl);
+
+        donnees_opengl
+    }
+}
+
+// Données de la scène préparées avant la création du contexte OpenGL
+struct SceneHorsEcran {
+
+    textures: textures::Textures,
+    materiaux: materiaux::Materiaux,
+    donnees_opengl: donnees::DonneesOpenGL,
+}
+
+// Dessine une seule image selon les arguments --camera et --size, puis l'enregistre
+fn dessiner_hors_ecran(
+    chemin: &str,
+    arguments: &arguments::Arguments,
+    configuration: &configuration::Configuration,
+    labyrinthe: &labyrinthe::Labyrinthe,
+    mut scene: SceneHorsEcran) -> Result<(), String>
+{
+    let contexte = hors_ecran::ContexteHorsEcran::new(arguments.dimensions)?;
+    let facade = contexte.facade();
+
+    scene.textures.generer_textures(facade);
+    scene.materiaux.generer_tampon(facade);
+    scene.donnees_opengl.generer_vertex_buffer(facade);
+
+    let programme_opengl = shaders::ProgrammeOpenGL::new(facade);
+
+    // Même point de départ que dans la fenêtre si la caméra n'est pas précisée
+    let camera = arguments.camera.unwrap_or(arguments::Camera {
+        position: observateur::POSITION_DEPART,
+        lacet: 0.0,
+        tangage: 0.0,
+    });
+
+    let mut observateur = observateur::Observateur::new(
+        glm::Vec3::new(camera.position[0], camera.position[1], camera.position[2]),
+        glm::Vec3::new(0.0, 0.0, 1.0),
+    );
+    observateur.orienter(camera.lacet, camera.tangage);
+
+    // Les flammes ont besoin d'un peu de temps pour apparaître
+    const TEMPS_IMAGE: f32 = 2.0;
+    const PAS_SIMULATION: f32 = 0.05;
+
+    let mut particules = particules::SystemeParticules::new(&labyrinthe.positions_flammes());
+    for _ in 0..(TEMPS_IMAGE / PAS_SIMULATION) as u32 {
+        particules.mettre_a_jour(PAS_SIMULATION);
+    }
+
+    let mut vue = ecran::Vue::new(configuration);
+    vue.changer_camera(&observateur.position, observateur.direction());
+
+    let lumieres = labyrinthe.obtenir_lumieres_proches(&observateur.position, observateur.direction(), TEMPS_IMAGE);
+    let scene_vue = ecran::Scene {
+        donnees_opengl: &scene.donnees_opengl,
+        textures: &scene.textures,
+        materiaux: &scene.materiaux,
+        silhouette: None,
+    };
+
+    let image = vue.dessiner_image(lumieres, &particules, &scene_vue, &programme_opengl, None, facade);
+
+    capture::enregistrer(&image, chemin)?;
+    println!("Image enregistrée: {}", chemin);
+
+    Ok(())
+}
+
+// Enregistre le survol du départ jusqu'à la sortie, une image à la fois, au rythme de --fps
+// Les images sont numérotées dans l'ordre, par exemple pour en faire une vidéo
+fn enregistrer_survol(
+    dossier: &str,
+    arguments: &arguments::Arguments,
+    configuration: &configuration::Configuration,
+    labyrinthe: &labyrinthe::Labyrinthe,
+    mut scene: SceneHorsEcran) -> Result<(), String>
+{
+    let contexte = hors_ecran::ContexteHorsEcran::new(arguments.dimensions)?;
+    let facade = contexte.facade();
+
+    scene.textures.generer_textures(facade);
+    scene.materiaux.generer_tampon(facade);
+    scene.donnees_opengl.generer_vertex_buffer(facade);
+
+    let programme_opengl = shaders::ProgrammeOpenGL::new(facade);
+
+    let mut survol = survol::Survol::new(labyrinthe, configuration.vitesse_survol);
+
+    // Les flammes ont besoin d'un peu de temps pour apparaître
+    const TEMPS_DEPART: f32 = 2.0;
+    const PAS_SIMULATION: f32 = 0.05;
+
+    let mut particules = particules::SystemeParticules::new(&labyrinthe.positions_flammes());
+    for _ in 0..(TEMPS_DEPART / PAS_SIMULATION) as u32 {
+        particules.mettre_a_jour(PAS_SIMULATION);
+    }
+
+    let mut vue = ecran::Vue::new(configuration);
+    let scene_vue = ecran::Scene {
+        donnees_opengl: &scene.donnees_opengl,
+        textures: &scene.textures,
+        materiaux: &scene.materiaux,
+        silhouette: None,
+    };
+
+    let duree_image = 1.0 / arguments.images_par_seconde;
+    let nombre_images = survol.nombre_images(arguments.images_par_seconde);
+
+    for numero in 0..nombre_images {
+
+        if numero > 0 {
+            survol.avancer(duree_image);
+            particules.mettre_a_jour(duree_image);
+        }
+
+        let temps = TEMPS_DEPART + numero as f32 * duree_image;
+
+        vue.changer_camera(survol.position(), survol.direction());
+        let lumieres = labyrinthe.obtenir_lumieres_proches(survol.position(), survol.direction(), temps);
+
+        let image = vue.dessiner_image(lumieres, &particules, &scene_vue, &programme_opengl, None, facade);
+        capture::enregistrer(&image, &format!("{}/survol_{:05}.png", dossier.trim_end_matches('/'), numero))?;
+    }
+
+    println!("{} images enregistrées dans {}", nombre_images, dossier);
+
+    Ok(())
+}
+
+// Rejoue l'enregistrement --replay; avec --verify, une trajectoire différente est une erreur
+fn rejouer(chemin: &str, arguments: &arguments::Arguments) -> Result<(), String> {
+
+    let rejeu = enregistrement::Rejeu::charger(chemin).map_err(|erreur| format!("{}: {}", chemin, erreur))?;
+
+    // Un nouvel enregistrement garde la configuration de l'ancien
+    let mut enregistreur = match arguments.enregistrement {
+        Some(ref destination) => Some(enregistrement::Enregistreur::creer(destination, rejeu.configuration()).map_err(|erreur| erreur.to_string())?),
+        None => None,
+    };
+
+    let position = rejeu.rejouer(arguments.verifier, enregistreur.as_mut())?;
+
+    println!("{} images rejouées, position finale ({}, {}, {})", rejeu.nombre_images(), position.x, position.y, position.z);
+
+    if arguments.verifier {
+        println!("La trajectoire est identique à celle enregistrée");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,529 @@
+use commandes;
+use configuration;
+
+/*
+    Interface publique du module manette
+
+    Sert à lire une manette de jeu et à la présenter comme les autres entrées:
+    le stick gauche déplace, le stick droit dirige le regard, les gâchettes montent et descendent en mode aérien
+    Les boutons sont associés aux commandes comme les touches, voir commandes::Entree::Manette
+
+    L'état de la manette vient d'une Source, lue une fois par image:
+    SourceJoystick lit un périphérique Linux (/dev/input/js0), SourceScriptee rejoue des valeurs écrites
+    dans un fichier pour vérifier le comportement sans manette, et SourceAbsente ne fait rien
+*/
+
+// Valeurs brutes, avant les zones mortes
+#[derive(Clone, Copy, PartialEq)]
+pub struct EtatManette {
+
+    pub stick_gauche: (f32, f32), // x vers la droite, y vers l'avant, entre -1 et 1
+    pub stick_droit: (f32, f32), // x vers la droite, y vers le haut
+    pub gachettes: (f32, f32), // Gauche et droite, entre 0 et 1
+    pub boutons: u32, // Un bit par bouton appuyé
+}
+
+impl EtatManette {
+
+    // Sticks au centre et rien d'appuyé
+    pub fn new() -> EtatManette {
+
+        EtatManette {
+            stick_gauche: (0.0, 0.0),
+            stick_droit: (0.0, 0.0),
+            gachettes: (0.0, 0.0),
+            boutons: 0,
+        }
+    }
+
+    // «gauche_x gauche_y droit_x droit_y gachette_gauche gachette_droite [boutons appuyés]»
+    pub fn lire(texte: &str) -> Result<EtatManette, String> {
+
+        let mut morceaux = texte.split_whitespace();
+        let mut valeurs = [0.0; 6];
+
+        for valeur in valeurs.iter_mut() {
+
+            *valeur = morceaux.next().and_then(|valeur| valeur.parse::<f32>().ok())
+                .ok_or_else(|| "six valeurs sont attendues".to_string())?;
+        }
+
+        let mut boutons = 0;
+
+        for bouton in morceaux {
+
+            match bouton.parse::<u8>() {
+                Ok(bouton) if bouton < 32 => boutons |= 1 << bouton,
+                _ => return Err(format!("«{}» n'est pas un bouton (0 à 31)", bouton)),
+            }
+        }
+
+        Ok(EtatManette {
+            stick_gauche: (valeurs[0], valeurs[1]),
+            stick_droit: (valeurs[2], valeurs[3]),
+            gachettes: (valeurs[4], valeurs[5]),
+            boutons,
+        })
+    }
+
+    // Inverse de lire
+    pub fn texte(&self) -> String {
+
+        let mut texte = format!("{} {} {} {} {} {}",
+            self.stick_gauche.0, self.stick_gauche.1,
+            self.stick_droit.0, self.stick_droit.1,
+            self.gachettes.0, self.gachettes.1);
+
+        for bouton in 0..32 {
+            if self.boutons & (1 << bouton) != 0 {
+                texte += &format!(" {}", bouton);
+            }
+        }
+
+        texte
+    }
+}
+
+pub trait Source {
+
+    // Appelée une fois par image
+    fn lire(&mut self) -> EtatManette;
+}
+
+pub struct Manette {
+
+    source: Box<dyn Source>,
+    brut: EtatManette, // Tel que lu de la source, pour l'enregistrer
+    etat: EtatManette, // Zones mortes appliquées
+    boutons_precedents: u32,
+
+    pub parametres: configuration::ParametresManette,
+}
+
+impl Manette {
+
+    pub fn new(source: Box<dyn Source>, parametres: &configuration::ParametresManette) -> Manette {
+
+        Manette {
+            source,
+            brut: EtatManette::new(),
+            etat: EtatManette::new(),
+            boutons_precedents: 0,
+
+            parametres: parametres.clone(),
+        }
+    }
+
+    // Un script remplace la manette réelle
+    pub fn ouvrir(parametres: &configuration::ParametresManette, script: Option<&str>) -> Manette {
+
+        if let Some(chemin) = script {
+
+            match SourceScriptee::charger(chemin) {
+
+                Ok(source) => return Manette::new(Box::new(source), parametres),
+                Err(erreur) => println!("Le script de manette {} n'a pas pu être lu: {}", chemin, erreur),
+            }
+        }
+
+        if !parametres.active {
+            return Manette::new(Box::new(SourceAbsente), parametres);
+        }
+
+        match SourceJoystick::ouvrir(&parametres.peripherique) {
+
+            Ok(source) => Manette::new(Box::new(source), parametres),
+            Err(_) => {
+                println!("Aucune manette trouvée à {}", parametres.peripherique);
+                Manette::new(Box::new(SourceAbsente), parametres)
+            }
+        }
+    }
+
+    pub fn mettre_a_jour(&mut self) {
+
+        let etat = self.source.lire();
+        let zone_morte = self.parametres.zone_morte;
+
+        self.brut = etat;
+        self.boutons_precedents = self.etat.boutons;
+
+        self.etat = EtatManette {
+            stick_gauche: appliquer_zone_morte(etat.stick_gauche, zone_morte),
+            stick_droit: appliquer_zone_morte(etat.stick_droit, zone_morte),
+            gachettes: (
+                appliquer_zone_morte((etat.gachettes.0, 0.0), zone_morte).0,
+                appliquer_zone_morte((etat.gachettes.1, 0.0), zone_morte).0,
+            ),
+            boutons: etat.boutons,
+        };
+    }
+
+    pub fn etat_brut(&self) -> EtatManette {
+
+        self.brut
+    }
+
+    // Entre -1 et 1; les petites valeurs sont adoucies pour viser plus précisément
+    pub fn regard(&self) -> (f32, f32) {
+
+        let (x, y) = self.etat.stick_droit;
+        (x * x.abs(), y * y.abs())
+    }
+
+    // Intensité entre 0 et 1 de la commande selon les sticks et les gâchettes
+    pub fn valeur_commande(&self, commande: commandes::Commande) -> f32 {
+
+        let (x, y) = self.etat.stick_gauche;
+        let (gauche, droite) = self.etat.gachettes;
+
+        match commande {
+            commandes::Commande::Avancer => y.max(0.0),
+            commandes::Commande::Reculer => (-y).max(0.0),
+            commandes::Commande::Gauche => (-x).max(0.0),
+            commandes::Commande::Droite => x.max(0.0),
+            commandes::Commande::Monter => droite,
+            commandes::Commande::Descendre => gauche,
+            _ => 0.0,
+        }
+    }
+
+    pub fn est_appuye(&self, bouton: u8) -> bool {
+
+        bouton < 32 && self.etat.boutons & (1 << bouton) != 0
+    }
+
+    pub fn vient_etre_appuye(&self, bouton: u8) -> bool {
+
+        bouton < 32 && (self.etat.boutons & !self.boutons_precedents) & (1 << bouton) != 0
+    }
+
+    // Bouton appuyé depuis la dernière image, pour l'associer à une commande
+    pub fn dernier_bouton(&self) -> Option<u8> {
+
+        let nouveaux = self.etat.boutons & !self.boutons_precedents;
+
+        if nouveaux == 0 {
+            return None;
+        }
+
+        Some(nouveaux.trailing_zeros() as u8)
+    }
+}
+
+// Pour les plateformes sans manette, ou si elle est désactivée
+pub struct SourceAbsente;
+
+impl Source for SourceAbsente {
+
+    fn lire(&mut self) -> EtatManette {
+
+        EtatManette::new()
+    }
+}
+
+// Interface joystick de Linux: chaque événement fait 8 octets (temps, valeur, type, numéro)
+// Les événements sont lus dans un fil d'exécution séparé, puisque la lecture bloque
+pub struct SourceJoystick {
+
+    evenements: std::sync::mpsc::Receiver<(u8, u8, i16)>, // Type, numéro, valeur
+    axes: [f32; AXES_JOYSTICK],
+    boutons: u32,
+}
+
+impl SourceJoystick {
+
+    pub fn ouvrir(chemin: &str) -> std::io::Result<SourceJoystick> {
+
+        use std::io::Read;
+
+        let mut fichier = std::fs::File::open(chemin)?;
+        let (envoi, reception) = std::sync::mpsc::channel();
+
+        // Le fil se termine lorsque la manette est débranchée ou que le programme quitte
+        std::thread::spawn(move || {
+
+            let mut octets = [0u8; 8];
+
+            while fichier.read_exact(&mut octets).is_ok() {
+
+                let valeur = i16::from_ne_bytes([octets[4], octets[5]]);
+
+                if envoi.send((octets[6], octets[7], valeur)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Les gâchettes sont au repos à -1 tant que la manette n'a pas donné leur état
+        let mut axes = [0.0; AXES_JOYSTICK];
+        axes[AXE_GACHETTE_GAUCHE] = -1.0;
+        axes[AXE_GACHETTE_DROITE] = -1.0;
+
+        Ok(SourceJoystick {
+            evenements: reception,
+            axes,
+            boutons: 0,
+        })
+    }
+}
+
+impl Source for SourceJoystick {
+
+    fn lire(&mut self) -> EtatManette {
+
+        // L'état initial de chaque axe et bouton est marqué de 0x80
+        const BOUTON: u8 = 0x01;
+        const AXE: u8 = 0x02;
+        const INITIAL: u8 = 0x80;
+
+        while let Ok((genre, numero, valeur)) = self.evenements.try_recv() {
+
+            match genre & !INITIAL {
+
+                BOUTON if numero < 32 => {
+                    if valeur != 0 {
+                        self.boutons |= 1 << numero;
+                    }
+                    else {
+                        self.boutons &= !(1 << numero);
+                    }
+                },
+
+                AXE if (numero as usize) < AXES_JOYSTICK => {
+                    self.axes[numero as usize] = valeur as f32 / i16::MAX as f32;
+                },
+
+                _ => (),
+            }
+        }
+
+        // L'axe vertical des sticks pointe vers le bas
+        EtatManette {
+            stick_gauche: (self.axes[AXE_GAUCHE_X], -self.axes[AXE_GAUCHE_Y]),
+            stick_droit: (self.axes[AXE_DROIT_X], -self.axes[AXE_DROIT_Y]),
+            gachettes: (
+                (self.axes[AXE_GACHETTE_GAUCHE] + 1.0) / 2.0,
+                (self.axes[AXE_GACHETTE_DROITE] + 1.0) / 2.0,
+            ),
+            boutons: self.boutons,
+        }
+    }
+}
+
+/*
+    Rejoue des états écrits dans un fichier, sans manette, par exemple pour vérifier les zones mortes
+    Chaque ligne donne un nombre d'images, puis l'état à garder pendant ces images:
+    «images gauche_x gauche_y droit_x droit_y gachette_gauche gachette_droite [boutons appuyés]»
+    Après la dernière ligne, la manette est au repos
+*/
+pub struct SourceScriptee {
+
+    etats: std::collections::VecDeque<(u32, EtatManette)>, // Images restantes, état
+}
+
+impl SourceScriptee {
+
+    pub fn new(etats: std::vec::Vec<(u32, EtatManette)>) -> SourceScriptee {
+
+        SourceScriptee {
+            etats: etats.into_iter().collect(),
+        }
+    }
+
+    pub fn charger(chemin: &str) -> Result<SourceScriptee, String> {
+
+        let contenu = std::fs::read_to_string(chemin).map_err(|erreur| erreur.to_string())?;
+        let mut etats = std::vec::Vec::new();
+
+        for (numero, ligne) in contenu.lines().enumerate() {
+
+            let ligne = ligne.trim();
+
+            if ligne.is_empty() || ligne.starts_with('#') {
+                continue;
+            }
+
+            etats.push(lire_ligne_script(ligne).map_err(|erreur| format!("ligne {}: {}", numero + 1, erreur))?);
+        }
+
+        Ok(SourceScriptee::new(etats))
+    }
+}
+
+impl Source for SourceScriptee {
+
+    fn lire(&mut self) -> EtatManette {
+
+        while let Some((images, etat)) = self.etats.front_mut() {
+
+            if *images > 0 {
+                *images -= 1;
+                return *etat;
+            }
+
+            self.etats.pop_front();
+        }
+
+        EtatManette::new()
+    }
+}
+
+
+
+
+
+/*
+    Partie privée du module manette
+*/
+
+// Disposition des manettes Xbox avec le pilote xpad; d'autres manettes peuvent différer
+const AXES_JOYSTICK: usize = 8;
+const AXE_GAUCHE_X: usize = 0;
+const AXE_GAUCHE_Y: usize = 1;
+const AXE_GACHETTE_GAUCHE: usize = 2;
+const AXE_DROIT_X: usize = 3;
+const AXE_DROIT_Y: usize = 4;
+const AXE_GACHETTE_DROITE: usize = 5;
+
+// Zone morte radiale: sous le seuil, le stick est au centre; au-delà, la course restante est ramenée entre 0 et 1
+fn appliquer_zone_morte(stick: (f32, f32), zone_morte: f32) -> (f32, f32) {
+
+    let norme = (stick.0 * stick.0 + stick.1 * stick.1).sqrt();
+
+    if norme <= zone_morte || zone_morte >= 1.0 {
+        return (0.0, 0.0);
+    }
+
+    let echelle = ((norme - zone_morte) / (1.0 - zone_morte)).min(1.0) / norme;
+    (stick.0 * echelle, stick.1 * echelle)
+}
+
+fn lire_ligne_script(ligne: &str) -> Result<(u32, EtatManette), String> {
+
+    let (images, etat) = ligne.split_once(char::is_whitespace).unwrap_or((ligne, ""));
+
+    let images = images.parse::<u32>().map_err(|_| "le nombre d'images est invalide".to_string())?;
+    let etat = EtatManette::lire(etat).map_err(|erreur| format!("après le nombre d'images, {}", erreur))?;
+
+    Ok((images, etat))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const ZONE_MORTE: f32 = 0.2;
+
+    // Une manette qui rejoue les états donnés, une image chacun
+    fn manette_scriptee(etats: &[EtatManette]) -> Manette {
+
+        let mut parametres = configuration::ParametresManette::new();
+        parametres.zone_morte = ZONE_MORTE;
+
+        let etats = etats.iter().map(|etat| (1, *etat)).collect();
+        Manette::new(Box::new(SourceScriptee::new(etats)), &parametres)
+    }
+
+    fn etat(stick_gauche: (f32, f32), gachettes: (f32, f32), boutons: u32) -> EtatManette {
+
+        EtatManette {
+            stick_gauche,
+            gachettes,
+            boutons,
+            .. EtatManette::new()
+        }
+    }
+
+    fn proche(a: f32, b: f32) -> bool {
+
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn zone_morte_radiale() {
+
+        assert_eq!(appliquer_zone_morte((0.1, 0.1), ZONE_MORTE), (0.0, 0.0));
+        assert_eq!(appliquer_zone_morte((0.0, ZONE_MORTE), ZONE_MORTE), (0.0, 0.0));
+
+        // Sur la diagonale, chaque axe est sous le seuil mais la distance au centre le dépasse
+        let (x, y) = appliquer_zone_morte((0.15, 0.15), ZONE_MORTE);
+        let course = ((0.15_f32 * 0.15 * 2.0).sqrt() - ZONE_MORTE) / (1.0 - ZONE_MORTE);
+        assert!(x > 0.0 && proche(x, y) && proche((x * x + y * y).sqrt(), course));
+    }
+
+    #[test]
+    fn course_restante_ramenee_entre_0_et_1() {
+
+        // Au-delà du seuil, la course restante va de 0 à 1, dans la même direction
+        let (x, y) = appliquer_zone_morte((0.0, 0.6), ZONE_MORTE);
+        assert!(proche(x, 0.0) && proche(y, 0.5));
+
+        let (x, y) = appliquer_zone_morte((0.3, -0.4), ZONE_MORTE);
+        assert!(proche(x, 0.3 * 0.375 / 0.5) && proche(y, -0.4 * 0.375 / 0.5));
+
+        // Dans les coins, la course dépasse 1 mais est limitée
+        let (x, y) = appliquer_zone_morte((1.0, 1.0), ZONE_MORTE);
+        assert!(proche((x * x + y * y).sqrt(), 1.0) && proche(x, y));
+    }
+
+    #[test]
+    fn sticks_donnent_les_commandes_de_deplacement() {
+
+        let mut manette = manette_scriptee(&[
+            etat((0.0, 0.6), (0.0, 0.0), 0),
+            etat((-1.0, 0.0), (0.0, 0.0), 0),
+            etat((0.1, -0.1), (0.0, 0.0), 0),
+        ]);
+
+        manette.mettre_a_jour();
+        assert!(proche(manette.valeur_commande(commandes::Commande::Avancer), 0.5));
+        assert_eq!(manette.valeur_commande(commandes::Commande::Reculer), 0.0);
+
+        manette.mettre_a_jour();
+        assert!(proche(manette.valeur_commande(commandes::Commande::Gauche), 1.0));
+        assert_eq!(manette.valeur_commande(commandes::Commande::Droite), 0.0);
+
+        manette.mettre_a_jour();
+        assert_eq!(manette.valeur_commande(commandes::Commande::Droite), 0.0);
+        assert_eq!(manette.valeur_commande(commandes::Commande::Reculer), 0.0);
+    }
+
+    #[test]
+    fn gachettes_montent_et_descendent() {
+
+        let mut manette = manette_scriptee(&[
+            etat((0.0, 0.0), (0.1, 0.6), 0),
+            etat((0.0, 0.0), (1.0, 0.0), 0),
+        ]);
+
+        manette.mettre_a_jour();
+        assert_eq!(manette.valeur_commande(commandes::Commande::Descendre), 0.0);
+        assert!(proche(manette.valeur_commande(commandes::Commande::Monter), 0.5));
+
+        manette.mettre_a_jour();
+        assert!(proche(manette.valeur_commande(commandes::Commande::Descendre), 1.0));
+        assert_eq!(manette.valeur_commande(commandes::Commande::Monter), 0.0);
+    }
+
+    #[test]
+    fn boutons_et_fin_du_script() {
+
+        let mut manette = manette_scriptee(&[
+            etat((0.0, 1.0), (0.0, 0.0), 1 << 3),
+            etat((0.0, 1.0), (0.0, 0.0), 1 << 3),
+        ]);
+
+        manette.mettre_a_jour();
+        assert!(manette.vient_etre_appuye(3) && manette.dernier_bouton() == Some(3));
+
+        manette.mettre_a_jour();
+        assert!(manette.est_appuye(3) && !manette.vient_etre_appuye(3));
+
+        // Après le script, la manette est au repos
+        manette.mettre_a_jour();
+        assert!(!manette.est_appuye(3));
+        assert_eq!(manette.valeur_commande(commandes::Commande::Avancer), 0.0);
+    }
+}
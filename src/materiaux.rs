@@ -0,0 +1,140 @@
+/*
+    Interface publique du module materiaux
+
+    Sert à décrire l'apparence des surfaces et à l'envoyer aux shaders
+*/
+
+// Décrit comment une surface est texturée et réfléchit la lumière
+#[derive(Clone)]
+pub struct Materiau {
+
+    pub couleur: u32, // Couche de la texture de couleur, voir Textures::obtenir_id
+    pub normales: Option<u32>, // Couche de la carte de normales
+    pub speculaire: Option<u32>, // Couche de la carte de spécularité (rouge) et de rugosité (vert)
+
+    pub echelle: [f32; 2], // Nombre de répétitions de la texture par unité de distance
+    pub emission: [f32; 3], // Lumière émise par la surface elle-même
+    pub intensite_speculaire: f32, // Entre 0 et 1
+    pub rugosite: f32, // Entre 0 (reflet concentré) et 1 (reflet diffus)
+}
+
+impl Materiau {
+
+    pub fn new(couleur: u32) -> Materiau {
+
+        Materiau {
+
+            couleur,
+            normales: None,
+            speculaire: None,
+
+            echelle: [1.0, 1.0],
+            emission: [0.0, 0.0, 0.0],
+            intensite_speculaire: 0.45,
+            rugosite: 0.5,
+        }
+    }
+}
+
+// Permet de faire référence à un matériau sans le copier
+#[derive(Clone, Copy)]
+pub struct IdMateriau(u32);
+
+impl IdMateriau {
+
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+pub struct Materiaux {
+
+    materiaux: std::vec::Vec<Materiau>,
+    tampon: Option<glium::uniforms::UniformBuffer<TamponMateriaux>>,
+    donnees: Option<TamponMateriaux>,
+}
+
+impl Materiaux {
+
+    pub fn new() -> Materiaux {
+
+        Materiaux {
+
+            materiaux: std::vec::Vec::with_capacity(NOMBRE_MATERIAUX),
+            tampon: None, // Doit être créé après avoir ajouté les matériaux
+            donnees: None,
+        }
+    }
+
+    pub fn ajouter(&mut self, materiau: Materiau) -> IdMateriau {
+
+        if self.materiaux.len() >= NOMBRE_MATERIAUX {
+            panic!("Il ne peut pas y avoir plus de {} matériaux", NOMBRE_MATERIAUX);
+        }
+
+        self.materiaux.push(materiau);
+
+        IdMateriau(self.materiaux.len() as u32 - 1)
+    }
+
+    // Cette fonction est nécessaire pour appeler correctement obtenir_tampon
+    pub fn generer_tampon(&mut self, affichage: &dyn glium::backend::Facade) {
+
+        const ABSENTE: f32 = -1.0; // Indique au shader qu'une carte est absente
+
+        let mut donnees = TamponMateriaux {
+
+            couches: [[0.0, ABSENTE, ABSENTE, 0.0]; NOMBRE_MATERIAUX],
+            echelles: [[1.0, 1.0, 0.0, 0.0]; NOMBRE_MATERIAUX],
+            emissions: [[0.0, 0.0, 0.0, 0.0]; NOMBRE_MATERIAUX],
+            reflets: [[0.0, 1.0, 0.0, 0.0]; NOMBRE_MATERIAUX],
+        };
+
+        for (i, materiau) in self.materiaux.iter().enumerate() {
+
+            let couche_optionnelle = |couche: Option<u32>| -> f32 {
+                couche.map(|couche| couche as f32).unwrap_or(ABSENTE)
+            };
+
+            donnees.couches[i] = [
+                materiau.couleur as f32,
+                couche_optionnelle(materiau.normales),
+                couche_optionnelle(materiau.speculaire),
+                0.0];
+            donnees.echelles[i] = [materiau.echelle[0], materiau.echelle[1], 0.0, 0.0];
+            donnees.emissions[i] = [materiau.emission[0], materiau.emission[1], materiau.emission[2], 0.0];
+            donnees.reflets[i] = [materiau.intensite_speculaire, materiau.rugosite, 0.0, 0.0];
+        }
+
+        self.tampon = Some(glium::uniforms::UniformBuffer::new(affichage, donnees).unwrap());
+        self.donnees = Some(donnees);
+    }
+
+    // Cette fonction requiert d'avoir appelé generer_tampon
+    pub fn obtenir_tampon(&self) -> &glium::uniforms::UniformBuffer<TamponMateriaux> {
+
+        self.tampon.as_ref().unwrap()
+    }
+
+    // Copie du contenu du tampon, pour les contextes sans blocs uniformes
+    pub fn obtenir_donnees(&self) -> &TamponMateriaux {
+
+        self.donnees.as_ref().unwrap()
+    }
+}
+
+// Doit correspondre à NBR_MATERIAUX dans les shaders
+pub const NOMBRE_MATERIAUX: usize = 16;
+
+// Représentation des matériaux envoyée aux shaders
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TamponMateriaux {
+
+    couches: [[f32; 4]; NOMBRE_MATERIAUX], // couleur, normales, spéculaire
+    echelles: [[f32; 4]; NOMBRE_MATERIAUX],
+    emissions: [[f32; 4]; NOMBRE_MATERIAUX],
+    reflets: [[f32; 4]; NOMBRE_MATERIAUX], // intensité spéculaire, rugosité
+}
+
+implementer_bloc_uniforme!(TamponMateriaux, couches, echelles, emissions, reflets);
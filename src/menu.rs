@@ -0,0 +1,839 @@
+use glium::{glutin};
+use glium::{Surface}; // Surface est un trait et doit être importé
+
+use carte;
+use commandes;
+use configuration;
+use ecran;
+use evenements;
+use hud;
+use labyrinthe;
+use observateur;
+use texte;
+
+/*
+    Interface publique du module menu
+
+    Sert à afficher les menus par-dessus la scène: pause, réglages, graphismes, touches et nouveau labyrinthe
+    La navigation se fait avec les flèches, Entrée et Échap, ou avec la souris
+    Le programme est en pause tant qu'un menu est ouvert
+*/
+
+pub struct Menu {
+
+    ecran: Option<Ecran>, // Aucun si le menu est fermé
+    selection: usize, // Élément choisi dans l'écran courant
+    attente_touche: bool, // La prochaine touche ou le prochain bouton appuyé sera associé à la commande choisie
+    position_souris: (f32, f32), // Le survol ne change la sélection que si la souris bouge
+
+    // Copies affichées dans l'écran des touches
+    associations: commandes::Associations,
+    etiquettes: commandes::Etiquettes,
+
+    pub reglages: Reglages,
+    pub generation: ParametresGeneration,
+}
+
+// Résultat d'une interaction avec le menu, à appliquer par le programme
+pub enum Action {
+
+    Pause, // Le menu vient de s'ouvrir
+    Reprendre, // Le menu vient de se fermer
+    ReglagesModifies,
+    NouveauLabyrinthe,
+    Quitter,
+}
+
+impl Menu {
+
+    // Le menu est ouvert au démarrage
+    pub fn new(configuration: &configuration::Configuration, generation: ParametresGeneration) -> Menu {
+
+        Menu {
+
+            ecran: Some(Ecran::Pause),
+            selection: 0,
+            attente_touche: false,
+            position_souris: (-1.0, -1.0),
+
+            associations: commandes::Associations::new(),
+            etiquettes: commandes::Etiquettes::new(),
+
+            reglages: Reglages::new(configuration),
+            generation,
+        }
+    }
+
+    pub fn est_ouvert(&self) -> bool {
+
+        self.ecran.is_some()
+    }
+
+    // Doit être appelée avant mise_a_jour_post_logique du gestionnaire d'événements
+    pub fn mettre_a_jour(&mut self, gestionnaire_evenements: &mut evenements::GestionnaireEvenements, affichage: &glium::Display) -> Option<Action> {
+
+        let ecran = match self.ecran {
+
+            Some(ecran) => ecran,
+            None => {
+
+                // Échap ouvre toujours le menu, même si la commande n'a plus de touche
+                if gestionnaire_evenements.commande_declenchee(commandes::Commande::Menu) ||
+                    gestionnaire_evenements.clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Escape) {
+
+                    self.aller_a(Ecran::Pause);
+                    return Some(Action::Pause);
+                }
+
+                return None;
+            }
+        };
+
+        let elements = ecran.elements();
+
+        if self.attente_touche {
+
+            if let Some(entree) = gestionnaire_evenements.derniere_entree() {
+
+                self.attente_touche = false;
+
+                // Échap annule le changement
+                let annulee = entree == commandes::Entree::Touche(glutin::event::VirtualKeyCode::Escape);
+
+                if let (Element::Touche(commande), false) = (elements[self.selection], annulee) {
+
+                    gestionnaire_evenements.associations.ajouter(commande, entree);
+                    enregistrer_associations(&gestionnaire_evenements.associations);
+                }
+            }
+
+            self.copier_associations(gestionnaire_evenements);
+            return None;
+        }
+
+        self.copier_associations(gestionnaire_evenements);
+
+        // Souris
+        let disposition = Disposition::new(affichage.get_framebuffer_dimensions(), elements.len());
+        let position = gestionnaire_evenements.souris.position_ecran(affichage);
+        let survol = disposition.element_a(position);
+
+        if position != self.position_souris {
+
+            self.position_souris = position;
+
+            if let Some(index) = survol {
+                self.selection = index;
+            }
+        }
+
+        if gestionnaire_evenements.souris.vient_etre_cliquee() {
+
+            if let Some(index) = survol {
+
+                self.selection = index;
+
+                // Un clic sur la moitié gauche de la valeur la diminue
+                let ligne = &disposition.lignes[index];
+                let sens = if position.0 > ligne.coin.0 + ligne.taille.0 * 0.5 && position.0 < ligne.coin.0 + ligne.taille.0 * 0.75 { -1 } else { 1 };
+
+                // L'écran a pu changer, le clavier est ignoré jusqu'à la prochaine image
+                return self.activer(elements[index], sens);
+            }
+        }
+
+        // Clavier
+        let mut action = None;
+        let clavier = &gestionnaire_evenements.clavier;
+        let nombre = elements.len();
+        let element = elements[self.selection];
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Up) {
+            self.selection = (self.selection + nombre - 1) % nombre;
+        }
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Down) {
+            self.selection = (self.selection + 1) % nombre;
+        }
+
+        if element.est_ajustable() {
+
+            if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Left) {
+                action = self.activer(element, -1);
+            }
+            if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Right) {
+                action = self.activer(element, 1);
+            }
+        }
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Return) {
+            action = self.activer(element, 1);
+        }
+
+        // La graine peut être tapée; vide, elle est choisie au hasard
+        if element == Element::Graine {
+
+            for chiffre in gestionnaire_evenements.caracteres().iter().filter_map(|caractere| caractere.to_digit(10)) {
+
+                let graine = self.generation.graine.unwrap_or(0);
+                self.generation.graine = Some(graine.saturating_mul(10).saturating_add(chiffre as u64));
+            }
+
+            if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Back) {
+                self.generation.graine = self.generation.graine.map(|graine| graine / 10).filter(|graine| *graine > 0);
+            }
+        }
+
+        // Retour arrière enlève toutes les entrées de la commande
+        if let Element::Touche(commande) = element {
+
+            if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Back) {
+
+                gestionnaire_evenements.associations.effacer(commande);
+                enregistrer_associations(&gestionnaire_evenements.associations);
+                self.copier_associations(gestionnaire_evenements);
+            }
+        }
+
+        if clavier.vient_etre_appuyee(&glutin::event::VirtualKeyCode::Escape) ||
+            gestionnaire_evenements.commande_declenchee(commandes::Commande::Menu) {
+
+            action = match ecran.precedent() {
+                Some(precedent) => {
+                    self.aller_a(precedent);
+                    None
+                },
+                None => self.fermer(),
+            };
+        }
+
+        action
+    }
+
+    // Les rectangles sont dessinés avec le programme de la carte, avant le texte
+    pub fn dessiner<S: Surface>(&self, surface: &mut S, programme: &glium::Program, affichage: &dyn glium::backend::Facade) {
+
+        let ecran = match self.ecran {
+            Some(ecran) => ecran,
+            None => return,
+        };
+
+        let dimensions = surface.get_dimensions();
+        let disposition = Disposition::new(dimensions, ecran.elements().len());
+        let ligne = &disposition.lignes[self.selection];
+
+        let rectangles = [
+            carte::Rectangle { coin: (0.0, 0.0), taille: (dimensions.0 as f32, dimensions.1 as f32), couleur: [0.0, 0.0, 0.0, 0.5] },
+            carte::Rectangle { coin: disposition.panneau.coin, taille: disposition.panneau.taille, couleur: [0.08, 0.07, 0.06, 0.9] },
+            carte::Rectangle { coin: ligne.coin, taille: ligne.taille, couleur: [0.9, 0.6, 0.2, 0.3] },
+        ];
+
+        carte::dessiner_rectangles(surface, &rectangles, programme, affichage);
+    }
+
+    // dimensions: taille de l'écran, en pixels
+    pub fn generer_texte(&self, dimensions: (u32, u32)) -> texte::Texte {
+
+        let mut texte = texte::Texte::new();
+
+        let ecran = match self.ecran {
+            Some(ecran) => ecran,
+            None => return texte,
+        };
+
+        let elements = ecran.elements();
+        let disposition = Disposition::new(dimensions, elements.len());
+        let taille = disposition.taille;
+        let panneau = &disposition.panneau;
+
+        const BLANC: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+        const JAUNE: [f32; 4] = [1.0, 0.85, 0.5, 1.0];
+        const GRIS: [f32; 4] = [0.7, 0.7, 0.7, 0.9];
+
+        // Le texte est centré verticalement dans sa ligne
+        let decalage = |hauteur_ligne: f32, taille: f32| (hauteur_ligne - taille) / 2.0;
+
+        let titre = ecran.titre();
+        let taille_titre = taille * 1.3;
+        let x_titre = panneau.coin.0 + (panneau.taille.0 - texte::Police::largeur(titre, taille_titre)) / 2.0;
+        let y_titre = panneau.coin.1 + panneau.taille.1 - disposition.hauteur_ligne * 1.5;
+        texte.ajouter(titre, (x_titre, y_titre + decalage(disposition.hauteur_ligne, taille_titre)), taille_titre, BLANC);
+
+        for (index, (element, ligne)) in elements.iter().zip(disposition.lignes.iter()).enumerate() {
+
+            let couleur = if index == self.selection { JAUNE } else { BLANC };
+            let y = ligne.coin.1 + decalage(ligne.taille.1, taille);
+
+            texte.ajouter(&self.libelle(*element), (ligne.coin.0 + taille * 0.5, y), taille, couleur);
+
+            if let Some(valeur) = self.valeur(*element, index == self.selection) {
+
+                let x = ligne.coin.0 + ligne.taille.0 - taille * 0.5 - texte::Police::largeur(&valeur, taille);
+                texte.ajouter(&valeur, (x, y), taille, couleur);
+            }
+        }
+
+        let aide = if self.attente_touche {
+            "Appuyez sur une touche ou un bouton (Échap: annuler)"
+        }
+        else if let Ecran::Touches = ecran {
+            "Entrée: ajouter une touche, Retour arrière: effacer, Échap: retour"
+        }
+        else {
+            "Flèches: choisir et modifier, Entrée: valider, Échap: retour"
+        };
+
+        let taille_aide = taille * 0.7;
+        let x_aide = panneau.coin.0 + (panneau.taille.0 - texte::Police::largeur(aide, taille_aide)) / 2.0;
+        texte.ajouter(aide, (x_aide, panneau.coin.1 + taille_aide * 0.6), taille_aide, GRIS);
+
+        texte
+    }
+
+    fn copier_associations(&mut self, gestionnaire_evenements: &evenements::GestionnaireEvenements) {
+
+        self.associations = gestionnaire_evenements.associations.clone();
+        self.etiquettes = gestionnaire_evenements.clavier.etiquettes().clone();
+    }
+
+    fn aller_a(&mut self, ecran: Ecran) {
+
+        self.ecran = Some(ecran);
+        self.selection = 0;
+        self.attente_touche = false;
+    }
+
+    fn fermer(&mut self) -> Option<Action> {
+
+        self.ecran = None;
+        self.attente_touche = false;
+
+        Some(Action::Reprendre)
+    }
+
+    // sens: 1 pour augmenter ou valider, -1 pour diminuer
+    fn activer(&mut self, element: Element, sens: i32) -> Option<Action> {
+
+        let reglages = &mut self.reglages;
+        let generation = &mut self.generation;
+        let sens_reel = sens as f32;
+
+        match element {
+
+            Element::Reprendre => return self.fermer(),
+            Element::Reglages => self.aller_a(Ecran::Reglages),
+            Element::NouveauLabyrinthe => self.aller_a(Ecran::NouveauLabyrinthe),
+            Element::Quitter => return Some(Action::Quitter),
+
+            Element::Sensibilite => {
+                reglages.sensibilite = (reglages.sensibilite + 0.1 * sens_reel)
+                    .clamp(configuration::SENSIBILITE_MIN, configuration::SENSIBILITE_MAX);
+                return Some(Action::ReglagesModifies);
+            },
+            Element::InverserY => {
+                reglages.inverser_y = !reglages.inverser_y;
+                return Some(Action::ReglagesModifies);
+            },
+            Element::Lissage => {
+                reglages.lissage = (reglages.lissage + 0.1 * sens_reel).clamp(0.0, configuration::LISSAGE_MAX);
+                return Some(Action::ReglagesModifies);
+            },
+            Element::ChampVision => {
+                reglages.camera.champ_vision = (reglages.camera.champ_vision + 5.0 * sens_reel)
+                    .clamp(configuration::CHAMP_VISION_MIN, configuration::CHAMP_VISION_MAX);
+                return Some(Action::ReglagesModifies);
+            },
+            Element::PlanProche => {
+                // Le plan proche varie par facteurs de deux, sa précision compte surtout près de zéro
+                reglages.camera.plan_proche = (reglages.camera.plan_proche * 2.0f32.powi(sens))
+                    .clamp(configuration::PLAN_PROCHE_MIN, configuration::PLAN_PROCHE_MAX);
+                return Some(Action::ReglagesModifies);
+            },
+            Element::PlanLoin => {
+                reglages.camera.plan_loin = (reglages.camera.plan_loin + 10.0 * sens_reel)
+                    .clamp(configuration::PLAN_LOIN_MIN, configuration::PLAN_LOIN_MAX);
+                return Some(Action::ReglagesModifies);
+            },
+            Element::EchelleRendu => {
+                reglages.camera.echelle_rendu = (reglages.camera.echelle_rendu + 0.25 * sens_reel)
+                    .clamp(configuration::ECHELLE_RENDU_MIN, configuration::ECHELLE_RENDU_MAX);
+                return Some(Action::ReglagesModifies);
+            },
+            Element::Qualite => {
+                reglages.qualite = if sens > 0 { reglages.qualite.suivante() } else { reglages.qualite.precedente() };
+                return Some(Action::ReglagesModifies);
+            },
+            Element::PleinEcran => {
+                reglages.plein_ecran = !reglages.plein_ecran;
+                return Some(Action::ReglagesModifies);
+            },
+            Element::Minicarte => {
+                reglages.minicarte = !reglages.minicarte;
+                return Some(Action::ReglagesModifies);
+            },
+            Element::Hud => {
+                reglages.hud = !reglages.hud;
+                return Some(Action::ReglagesModifies);
+            },
+            Element::Graphismes => self.aller_a(Ecran::Graphismes),
+            Element::Msaa => {
+                reglages.msaa = ajuster_echantillons(reglages.msaa, sens);
+                return Some(Action::ReglagesModifies);
+            },
+            Element::Fxaa => {
+                reglages.fxaa = !reglages.fxaa;
+                return Some(Action::ReglagesModifies);
+            },
+            Element::Touches => self.aller_a(Ecran::Touches),
+            Element::Touche(_) => self.attente_touche = true,
+
+            Element::Longueur => generation.longueur = ajuster_dimension(generation.longueur, sens),
+            Element::Largeur => generation.largeur = ajuster_dimension(generation.largeur, sens),
+            Element::Graine => {
+                generation.graine = generation.graine.map(|graine| if sens > 0 { graine.saturating_add(1) } else { graine.saturating_sub(1) });
+            },
+            Element::Algorithme => {
+                generation.algorithme = if sens > 0 { generation.algorithme.suivant() } else { generation.algorithme.precedent() };
+            },
+            Element::Generer => {
+                self.fermer();
+                return Some(Action::NouveauLabyrinthe);
+            },
+
+            Element::Retour => {
+                if let Some(precedent) = self.ecran.and_then(|ecran| ecran.precedent()) {
+                    self.aller_a(precedent);
+                }
+            },
+        }
+
+        None
+    }
+
+    fn libelle(&self, element: Element) -> String {
+
+        let libelle = match element {
+
+            Element::Reprendre => "Reprendre",
+            Element::Reglages => "Réglages",
+            Element::NouveauLabyrinthe => "Nouveau labyrinthe",
+            Element::Quitter => "Quitter",
+
+            Element::Sensibilite => "Sensibilité de la souris",
+            Element::InverserY => "Inverser l'axe vertical",
+            Element::Lissage => "Lissage de la souris",
+            Element::ChampVision => "Champ de vision",
+            Element::PlanProche => "Distance minimale",
+            Element::PlanLoin => "Distance maximale",
+            Element::EchelleRendu => "Échelle de rendu",
+            Element::Qualite => "Qualité",
+            Element::PleinEcran => "Plein écran",
+            Element::Minicarte => "Minicarte",
+            Element::Hud => "Informations",
+            Element::Graphismes => "Graphismes",
+            Element::Msaa => "Multiéchantillonnage (MSAA)",
+            Element::Fxaa => "Anticrénelage rapide (FXAA)",
+            Element::Touches => "Touches",
+            Element::Touche(commande) => commande.nom(),
+
+            Element::Longueur => "Longueur",
+            Element::Largeur => "Largeur",
+            Element::Graine => "Graine",
+            Element::Algorithme => "Algorithme",
+            Element::Generer => "Générer",
+
+            Element::Retour => "Retour",
+        };
+
+        libelle.to_string()
+    }
+
+    // Les valeurs ajustables sont entourées de flèches
+    fn valeur(&self, element: Element, choisi: bool) -> Option<String> {
+
+        let oui_non = |valeur: bool| if valeur { "oui" } else { "non" }.to_string();
+
+        let valeur = match element {
+
+            Element::Sensibilite => format!("{:.1}", self.reglages.sensibilite),
+            Element::InverserY => oui_non(self.reglages.inverser_y),
+            Element::Lissage if self.reglages.lissage < 0.05 => "non".to_string(),
+            Element::Lissage => format!("{:.1}", self.reglages.lissage),
+            Element::ChampVision => format!("{:.0}°", self.reglages.camera.champ_vision),
+            Element::PlanProche => format!("{}", self.reglages.camera.plan_proche),
+            Element::PlanLoin => format!("{:.0}", self.reglages.camera.plan_loin),
+            Element::EchelleRendu => format!("{:.0} %", self.reglages.camera.echelle_rendu * 100.0),
+            Element::Qualite => self.reglages.qualite.nom().to_string(),
+            Element::PleinEcran => oui_non(self.reglages.plein_ecran),
+            Element::Minicarte => oui_non(self.reglages.minicarte),
+            Element::Hud => oui_non(self.reglages.hud),
+            Element::Msaa if self.reglages.msaa == 0 => "non".to_string(),
+            Element::Msaa => format!("{}x", self.reglages.msaa),
+            Element::Fxaa => oui_non(self.reglages.fxaa),
+
+            Element::Touche(_) if choisi && self.attente_touche => "...".to_string(),
+            Element::Touche(commande) => self.associations.description(commande, &self.etiquettes),
+
+            Element::Longueur => self.generation.longueur.to_string(),
+            Element::Largeur => self.generation.largeur.to_string(),
+            Element::Graine => self.generation.graine.map(|graine| graine.to_string()).unwrap_or_else(|| "aléatoire".to_string()),
+            Element::Algorithme => self.generation.algorithme.nom().to_string(),
+
+            _ => return None,
+        };
+
+        if element.est_ajustable() {
+            return Some(format!("< {} >", valeur));
+        }
+
+        Some(valeur)
+    }
+}
+
+// Réglages modifiables dans le menu, appliqués immédiatement
+#[derive(Clone)]
+pub struct Reglages {
+
+    pub sensibilite: f32,
+    pub inverser_y: bool,
+    pub lissage: f32,
+    pub camera: configuration::ParametresCamera,
+    pub qualite: Qualite,
+    pub msaa: u32,
+    pub fxaa: bool,
+    pub plein_ecran: bool,
+    pub minicarte: bool,
+    pub hud: bool,
+}
+
+impl Reglages {
+
+    pub fn new(configuration: &configuration::Configuration) -> Reglages {
+
+        Reglages {
+
+            sensibilite: configuration.sensibilite,
+            inverser_y: configuration.inverser_y,
+            lissage: configuration.lissage,
+            camera: configuration.camera.clone(),
+            qualite: Qualite::Fichier,
+            msaa: configuration.post_traitement.msaa,
+            fxaa: configuration.post_traitement.fxaa,
+            plein_ecran: configuration.plein_ecran,
+            minicarte: configuration.carte.minicarte,
+            hud: configuration.hud.visible,
+        }
+    }
+
+    // La configuration donne les valeurs de la qualité Fichier
+    pub fn appliquer(&self,
+                    configuration: &configuration::Configuration,
+                    vue: &mut ecran::Vue,
+                    observateur: &mut observateur::Observateur,
+                    carte: &mut carte::Carte,
+                    hud: &mut hud::Hud,
+                    affichage: &glium::Display)
+    {
+        observateur.sensibilite = self.sensibilite;
+        observateur.inverser_y = self.inverser_y;
+        observateur.lissage = self.lissage;
+        vue.parametres_camera = self.camera.clone();
+
+        let (anisotropie, bloom) = match self.qualite {
+            Qualite::Basse => (1, false),
+            Qualite::Moyenne => (4, true),
+            Qualite::Haute => (16, true),
+            Qualite::Fichier => (configuration.anisotropie, configuration.post_traitement.bloom),
+        };
+
+        vue.echantillonnage.anisotropie = anisotropie;
+        vue.parametres_post_traitement.bloom = bloom;
+        vue.parametres_post_traitement.msaa = self.msaa;
+        vue.parametres_post_traitement.fxaa = self.fxaa;
+
+        carte.parametres.minicarte = self.minicarte;
+        hud.parametres.visible = self.hud;
+
+        let fenetre = affichage.gl_window();
+        let fenetre = fenetre.window();
+
+        if self.plein_ecran != fenetre.fullscreen().is_some() {
+
+            let plein_ecran = if self.plein_ecran {
+                Some(glutin::window::Fullscreen::Borderless(fenetre.current_monitor()))
+            }
+            else {
+                None
+            };
+
+            fenetre.set_fullscreen(plein_ecran);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Qualite {
+
+    Basse, // Sans filtrage anisotrope ni bloom
+    Moyenne,
+    Haute,
+    Fichier, // Selon le fichier de configuration
+}
+
+impl Qualite {
+
+    pub fn nom(self) -> &'static str {
+
+        match self {
+            Qualite::Basse => "Basse",
+            Qualite::Moyenne => "Moyenne",
+            Qualite::Haute => "Haute",
+            Qualite::Fichier => "Configuration",
+        }
+    }
+
+    pub fn suivante(self) -> Qualite {
+
+        match self {
+            Qualite::Basse => Qualite::Moyenne,
+            Qualite::Moyenne => Qualite::Haute,
+            Qualite::Haute => Qualite::Fichier,
+            Qualite::Fichier => Qualite::Basse,
+        }
+    }
+
+    pub fn precedente(self) -> Qualite {
+
+        self.suivante().suivante().suivante()
+    }
+}
+
+// Paramètres du prochain labyrinthe généré à partir du menu
+#[derive(Clone)]
+pub struct ParametresGeneration {
+
+    pub longueur: u32,
+    pub largeur: u32,
+    pub graine: Option<u64>, // Choisie au hasard si absente
+    pub algorithme: labyrinthe::Algorithme,
+}
+
+
+
+
+
+/*
+    Partie privée du module menu
+*/
+
+#[derive(Clone, Copy)]
+enum Ecran {
+
+    Pause,
+    Reglages,
+    Graphismes,
+    Touches,
+    NouveauLabyrinthe,
+}
+
+impl Ecran {
+
+    fn titre(self) -> &'static str {
+
+        match self {
+            Ecran::Pause => "Pause",
+            Ecran::Reglages => "Réglages",
+            Ecran::Graphismes => "Graphismes",
+            Ecran::Touches => "Touches",
+            Ecran::NouveauLabyrinthe => "Nouveau labyrinthe",
+        }
+    }
+
+    fn elements(self) -> std::vec::Vec<Element> {
+
+        match self {
+
+            Ecran::Pause => vec![
+                Element::Reprendre,
+                Element::Reglages,
+                Element::NouveauLabyrinthe,
+                Element::Quitter,
+            ],
+
+            Ecran::Reglages => vec![
+                Element::Sensibilite,
+                Element::InverserY,
+                Element::Lissage,
+                Element::ChampVision,
+                Element::PleinEcran,
+                Element::Minicarte,
+                Element::Hud,
+                Element::Graphismes,
+                Element::Touches,
+                Element::Retour,
+            ],
+
+            Ecran::Graphismes => vec![
+                Element::Qualite,
+                Element::EchelleRendu,
+                Element::Msaa,
+                Element::Fxaa,
+                Element::PlanProche,
+                Element::PlanLoin,
+                Element::Retour,
+            ],
+
+            Ecran::Touches => {
+                let mut elements: std::vec::Vec<Element> = commandes::Commande::TOUTES.iter().map(|commande| Element::Touche(*commande)).collect();
+                elements.push(Element::Retour);
+                elements
+            },
+
+            Ecran::NouveauLabyrinthe => vec![
+                Element::Longueur,
+                Element::Largeur,
+                Element::Graine,
+                Element::Algorithme,
+                Element::Generer,
+                Element::Retour,
+            ],
+        }
+    }
+
+    // Écran affiché par Échap ou Retour; le menu se ferme à partir de la pause
+    fn precedent(self) -> Option<Ecran> {
+
+        match self {
+            Ecran::Pause => None,
+            Ecran::Reglages => Some(Ecran::Pause),
+            Ecran::Graphismes => Some(Ecran::Reglages),
+            Ecran::Touches => Some(Ecran::Reglages),
+            Ecran::NouveauLabyrinthe => Some(Ecran::Pause),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Element {
+
+    // Pause
+    Reprendre,
+    Reglages,
+    NouveauLabyrinthe,
+    Quitter,
+
+    // Réglages
+    Sensibilite,
+    InverserY,
+    Lissage,
+    ChampVision,
+    PleinEcran,
+    Minicarte,
+    Hud,
+    Graphismes,
+    Touches,
+
+    // Graphismes
+    Qualite,
+    EchelleRendu,
+    Msaa,
+    Fxaa,
+    PlanProche,
+    PlanLoin,
+
+    // Touches
+    Touche(commandes::Commande),
+
+    // Nouveau labyrinthe
+    Longueur,
+    Largeur,
+    Graine,
+    Algorithme,
+    Generer,
+
+    Retour,
+}
+
+impl Element {
+
+    // Modifiable avec les flèches gauche et droite
+    fn est_ajustable(self) -> bool {
+
+        matches!(self,
+            Element::Sensibilite | Element::InverserY | Element::Lissage | Element::ChampVision | Element::PleinEcran | Element::Minicarte | Element::Hud |
+            Element::Qualite | Element::EchelleRendu | Element::Msaa | Element::Fxaa | Element::PlanProche | Element::PlanLoin |
+            Element::Longueur | Element::Largeur | Element::Graine | Element::Algorithme)
+    }
+}
+
+// Position des éléments à l'écran, en pixels à partir du coin inférieur gauche
+struct Disposition {
+
+    taille: f32, // Hauteur du texte
+    hauteur_ligne: f32,
+    panneau: carte::Rectangle,
+    lignes: std::vec::Vec<carte::Rectangle>, // Une par élément, de haut en bas
+}
+
+impl Disposition {
+
+    fn new(dimensions: (u32, u32), nombre_elements: usize) -> Disposition {
+
+        let (largeur_ecran, hauteur_ecran) = (dimensions.0 as f32, dimensions.1 as f32);
+
+        // Le panneau contient le titre, les éléments puis l'aide
+        let taille = (hauteur_ecran / 24.0).clamp(12.0, 40.0);
+        let hauteur_ligne = taille * 1.5;
+        let largeur = (taille * 24.0).min(largeur_ecran * 0.95);
+        let hauteur = hauteur_ligne * (nombre_elements as f32 + 3.0);
+        let coin = ((largeur_ecran - largeur) / 2.0, (hauteur_ecran - hauteur) / 2.0);
+
+        let lignes = (0..nombre_elements).map(|index| {
+            carte::Rectangle {
+                coin: (coin.0 + taille * 0.5, coin.1 + hauteur - hauteur_ligne * (index as f32 + 2.5)),
+                taille: (largeur - taille, hauteur_ligne),
+                couleur: [0.0; 4],
+            }
+        }).collect();
+
+        Disposition {
+
+            taille,
+            hauteur_ligne,
+            panneau: carte::Rectangle { coin, taille: (largeur, hauteur), couleur: [0.0; 4] },
+            lignes,
+        }
+    }
+
+    fn element_a(&self, position: (f32, f32)) -> Option<usize> {
+
+        self.lignes.iter().position(|ligne| {
+            position.0 >= ligne.coin.0 && position.0 < ligne.coin.0 + ligne.taille.0 &&
+            position.1 >= ligne.coin.1 && position.1 < ligne.coin.1 + ligne.taille.1
+        })
+    }
+}
+
+fn enregistrer_associations(associations: &commandes::Associations) {
+
+    if let Err(erreur) = associations.enregistrer(commandes::CHEMIN) {
+        println!("Les touches n'ont pas pu être enregistrées dans {}: {}", commandes::CHEMIN, erreur);
+    }
+}
+
+// Sans MSAA, puis 2, 4 et 8 échantillons
+fn ajuster_echantillons(echantillons: u32, sens: i32) -> u32 {
+
+    const CHOIX: [u32; 4] = [0, 2, 4, 8];
+
+    let index = CHOIX.iter().position(|choix| *choix >= echantillons).unwrap_or(CHOIX.len() - 1) as i32;
+    CHOIX[(index + sens).clamp(0, CHOIX.len() as i32 - 1) as usize]
+}
+
+// Entre 4 et 64 cellules
+fn ajuster_dimension(dimension: u32, sens: i32) -> u32 {
+
+    const MINIMUM: i32 = 4;
+    const MAXIMUM: i32 = 64;
+
+    (dimension as i32 + sens).clamp(MINIMUM, MAXIMUM) as u32
+}
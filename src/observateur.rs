@@ -1,18 +1,38 @@
 extern crate nalgebra_glm as glm;
-use glium::{glutin};
 
+use collision;
+use commandes;
 use evenements;
+use labyrinthe;
 
 /*
     Interface publique du module observateur
 
     Permet de gérer les paramètres de la caméra
+
+    Les durées sont en secondes et les vitesses en unités par seconde
+    Le déplacement avance par pas fixes (voir deplacer); le regard suit chaque image
+
+    En marchant, l'observateur tombe jusqu'au sol, peut sauter, s'accroupir et monter de petites marches
+    La position est celle des yeux; le mode aérien permet de voler librement, par exemple pour déboguer
+    Dans les deux modes, les murs arrêtent l'observateur, qui glisse le long de ceux-ci (voir collision)
 */
 
+// Le déplacement est simulé par pas fixes; l'affichage interpole entre les deux derniers pas
+pub const PAS_SIMULATION: f32 = 1.0 / 60.0;
+pub const RETARD_MAX: f32 = 0.25; // Après un long blocage, le retard n'est pas rattrapé d'un coup
+
+pub const POSITION_DEPART: [f32; 3] = [0.0, 1.5, 0.0];
+
 #[derive(Clone)]
 pub struct Observateur {
 
     pub position: glm::Vec3,
+    position_precedente: glm::Vec3, // Avant le dernier pas de déplacement, pour interpoler l'affichage
+    pub sensibilite: f32, // Multiplie la vitesse de rotation du regard
+    pub inverser_y: bool,
+    pub lissage: f32, // Part du mouvement de la souris reportée à l'image suivante
+    pub balancement: bool, // La tête balance au rythme des pas
     
     deplacement: Deplacement,
     regard: Regard,
@@ -35,6 +55,11 @@ impl Observateur {
         Observateur {
             
             position: position,
+            position_precedente: position,
+            sensibilite: 1.0,
+            inverser_y: false,
+            lissage: 0.0,
+            balancement: true,
             
             deplacement: Deplacement::new(),
             regard: Regard::new(),
@@ -45,15 +70,112 @@ impl Observateur {
         }
     }
 
-    pub fn deplacer(&mut self, gestionnaire_evenements: &evenements::GestionnaireEvenements, taux_rafraichissement: u64) {
-        
-        self.deplacement.ajuster_vitesse(&self.clone(), gestionnaire_evenements, taux_rafraichissement);
-        self.position += self.deplacement.delta(taux_rafraichissement);
+    // À chaque image, avant les pas de la simulation: un appui bref ne doit pas être perdu
+    // si aucun pas n'a lieu pendant cette image
+    fn lire_commandes(&mut self, gestionnaire_evenements: &evenements::GestionnaireEvenements) {
+
+        if gestionnaire_evenements.commande_declenchee(commandes::Commande::Sauter) {
+            self.deplacement.saut_demande = true;
+        }
+
+        self.deplacement.accroupi = gestionnaire_evenements.valeur_commande(commandes::Commande::Accroupir) > 0.0;
+    }
+
+    // Une image hors de la pause: les commandes, les pas complets dans le temps écoulé, puis le regard
+    // temps_a_simuler: temps écoulé qui n'a pas encore donné un pas complet, gardé d'une image à l'autre
+    pub fn simuler(&mut self,
+                    gestionnaire_evenements: &evenements::GestionnaireEvenements,
+                    labyrinthe: &labyrinthe::Labyrinthe,
+                    delta: f32,
+                    temps_a_simuler: &mut f32)
+    {
+        *temps_a_simuler += delta.min(RETARD_MAX);
+
+        self.lire_commandes(gestionnaire_evenements);
+
+        while *temps_a_simuler >= PAS_SIMULATION {
+
+            self.deplacer(gestionnaire_evenements, labyrinthe, PAS_SIMULATION);
+
+            *temps_a_simuler -= PAS_SIMULATION;
+        }
+
+        if gestionnaire_evenements.souris.capturee {
+            self.ajuster_direction(gestionnaire_evenements, delta);
+        }
     }
 
-    pub fn ajuster_direction(&mut self, gestionnaire_evenements: &evenements::GestionnaireEvenements, taux_rafraichissement: u64) {
+    // Un pas de la simulation, toujours de la même durée pour un comportement identique peu importe l'affichage
+    fn deplacer(&mut self,
+                    gestionnaire_evenements: &evenements::GestionnaireEvenements,
+                    labyrinthe: &labyrinthe::Labyrinthe,
+                    duree: f32)
+    {
+        self.position_precedente = self.position;
+
+        self.deplacement.ajuster_vitesse(&self.clone(), gestionnaire_evenements, duree);
+
+        if self.deplacement.mode_aerien {
+            self.deplacement.voler(&mut self.position, labyrinthe, duree);
+        }
+        else {
+            self.deplacement.marcher(&mut self.position, labyrinthe, duree);
+        }
+    }
+
+    // Déplace sans transition, par exemple au début d'un labyrinthe
+    pub fn placer(&mut self, position: glm::Vec3) {
+
+        self.position = position;
+        self.position_precedente = position;
+    }
+
+    // Position de la caméra entre les deux derniers pas, avec le balancement de la tête
+    // proportion: part du prochain pas déjà écoulée, entre 0 et 1
+    pub fn position_camera(&self, proportion: f32) -> glm::Vec3 {
+
+        let position = glm::lerp(&self.position_precedente, &self.position, proportion);
+
+        if !self.balancement {
+            return position;
+        }
+
+        let (vertical, lateral) = self.deplacement.balancement();
+        position + glm::Vec3::new(0.0, vertical, 0.0) + self.droite * lateral
+    }
+
+    // En mode aérien, l'observateur peut monter et descendre; retourne le nouveau mode
+    pub fn basculer_mode_aerien(&mut self) -> bool {
+
+        let deplacement = &mut self.deplacement;
+
+        deplacement.mode_aerien = !deplacement.mode_aerien;
+        deplacement.vitesse_verticale = 0.0;
+        deplacement.saut_demande = false;
+        deplacement.au_sol = false; // En reprenant la marche, l'observateur tombe jusqu'au sol
+
+        deplacement.mode_aerien
+    }
+
+    // duree: depuis la dernière image
+    pub fn ajuster_direction(&mut self, gestionnaire_evenements: &evenements::GestionnaireEvenements, duree: f32) {
         
-        self.regard.ajuster_direction(gestionnaire_evenements, taux_rafraichissement);
+        let parametres = ParametresRegard {
+            sensibilite: self.sensibilite,
+            inverser_y: self.inverser_y,
+            lissage: self.lissage,
+        };
+
+        self.regard.ajuster_direction(gestionnaire_evenements, &parametres, duree);
+
+        self.nouvelle_direction(self.regard.angles.obtenir_direction());
+    }
+
+    // Les angles sont en radians, comme pour le regard contrôlé par la souris
+    pub fn orienter(&mut self, angle_xz: f32, angle_yz: f32) {
+
+        self.regard.angles.modifier(angle_xz, angle_yz);
+        self.regard.angles.maintenir_angles();
 
         self.nouvelle_direction(self.regard.angles.obtenir_direction());
     }
@@ -70,6 +192,11 @@ impl Observateur {
         &self.droite
     }
 
+    // Des yeux au-dessus des pieds, plus basse accroupi
+    pub fn hauteur_yeux(&self) -> f32 {
+        self.deplacement.hauteur_yeux
+    }
+
     fn nouvelle_direction(&mut self, direction: glm::Vec3) {
 
         self.direction = direction;
@@ -98,8 +225,18 @@ impl Observateur {
 #[derive(Clone)]
 struct Deplacement {
 
-    pub vitesse: glm::Vec3,
+    pub vitesse: glm::Vec3, // Horizontale en marchant
     mode_aerien: bool,
+
+    // Marche
+    vitesse_verticale: f32,
+    au_sol: bool,
+    saut_demande: bool, // Jusqu'au prochain pas
+    accroupi: bool,
+    hauteur_yeux: f32, // Au-dessus des pieds
+
+    phase_balancement: f32, // En radians, un pas par demi-tour
+    amplitude_balancement: f32, // Entre 0 et 1, selon la vitesse
 }
 
 impl Deplacement {
@@ -109,6 +246,15 @@ impl Deplacement {
         Deplacement {
             vitesse: glm::Vec3::new(0.0, 0.0, 0.0),
             mode_aerien: false,
+
+            vitesse_verticale: 0.0,
+            au_sol: false,
+            saut_demande: false,
+            accroupi: false,
+            hauteur_yeux: HAUTEUR_DEBOUT,
+
+            phase_balancement: 0.0,
+            amplitude_balancement: 0.0,
         }
     }
 
@@ -116,66 +262,183 @@ impl Deplacement {
         &mut self,
         observateur: &Observateur,
         gestionnaire_evenements: &evenements::GestionnaireEvenements,
-        taux_rafraichissement: u64) {
+        duree: f32) {
+
+        // Accroupi, on avance moins vite
+        let vitesse = if self.accroupi && !self.mode_aerien { VITESSE_ACCROUPI } else { VITESSE_MARCHE };
 
-        let vitesse = 1.25;
-        let mut cible = glm::Vec3::new(0.0, 0.0, 0.0);
+        // Les touches donnent 0 ou 1, la manette permet d'avancer plus lentement
+        let valeur = |commande| gestionnaire_evenements.valeur_commande(commande);
+        let axe = |positive, negative| valeur(positive) - valeur(negative);
 
-        if gestionnaire_evenements.clavier.est_appuyee(&glutin::event::VirtualKeyCode::A) {
-            cible -= observateur.droite();
+        let mut cible = observateur.droite() * axe(commandes::Commande::Droite, commandes::Commande::Gauche) +
+            observateur.direction() * axe(commandes::Commande::Avancer, commandes::Commande::Reculer);
+
+        if self.mode_aerien {
+            cible += observateur.haut() * axe(commandes::Commande::Monter, commandes::Commande::Descendre);
         }
-        if gestionnaire_evenements.clavier.est_appuyee(&glutin::event::VirtualKeyCode::D) {
-            cible += observateur.droite();
+        else {
+            cible.y = 0.0;
         }
-        if gestionnaire_evenements.clavier.est_appuyee(&glutin::event::VirtualKeyCode::S) {
-            cible -= observateur.direction();
+
+        if cible == glm::Vec3::new(0.0, 0.0, 0.0) {
+            self.ralentir(duree);
         }
-        if gestionnaire_evenements.clavier.est_appuyee(&glutin::event::VirtualKeyCode::W) {
-            cible += observateur.direction();
+        else {
+
+            // En diagonale, on ne va pas plus vite
+            if cible.norm() > 1.0 {
+                cible = cible.normalize();
+            }
+
+            cible *= vitesse;
+            self.accelerer_vers(&cible, duree);
         }
+    }
 
-        if self.mode_aerien {
+    fn voler(&mut self, position: &mut glm::Vec3, labyrinthe: &labyrinthe::Labyrinthe, duree: f32) {
 
-            if gestionnaire_evenements.clavier.est_appuyee(&glutin::event::VirtualKeyCode::LShift) {
-                cible -= observateur.haut();
-            }
-            if gestionnaire_evenements.clavier.est_appuyee(&glutin::event::VirtualKeyCode::Space) {
-                cible += observateur.haut();
-            }
+        let depart = *position;
+        *position = labyrinthe.deplacer(position, &(self.vitesse * duree), &self.capsule());
+
+        // Contre un mur, seule la vitesse le long du mur est gardée
+        self.vitesse = (*position - depart) / duree;
+    }
+
+    // Gravité, saut, accroupissement et marches; position est celle des yeux
+    fn marcher(&mut self, position: &mut glm::Vec3, labyrinthe: &labyrinthe::Labyrinthe, duree: f32) {
+
+        const GRAVITE: f32 = 9.8;
+        const VITESSE_SAUT: f32 = 2.5; // Environ 30 cm de haut
+        const HAUTEUR_ACCROUPI: f32 = 0.9;
+        const VITESSE_ACCROUPISSEMENT: f32 = 10.0; // Proportion de l'écart comblée par seconde
+        const HAUTEUR_MARCHE: f32 = 0.3; // Plus haut, le sol devant bloque comme un mur
+        const MARGE_PLAFOND: f32 = 0.1; // Entre les yeux et le plafond
+
+        let mut pieds = position.y - self.hauteur_yeux;
+
+        let hauteur_cible = if self.accroupi { HAUTEUR_ACCROUPI } else { HAUTEUR_DEBOUT };
+        self.hauteur_yeux += (hauteur_cible - self.hauteur_yeux) * (VITESSE_ACCROUPISSEMENT * duree).min(1.0);
+
+        // On monte sur le sol devant s'il n'est pas trop haut
+        let deplacement = glm::Vec3::new(self.vitesse.x, 0.0, self.vitesse.z) * duree;
+        let destination = labyrinthe.deplacer(position, &deplacement, &self.capsule());
+
+        if labyrinthe.hauteur_sol(&destination) <= pieds + HAUTEUR_MARCHE {
+
+            // Contre un mur, seule la vitesse le long du mur est gardée, et le balancement s'arrête
+            self.vitesse.x = (destination.x - position.x) / duree;
+            self.vitesse.z = (destination.z - position.z) / duree;
+
+            position.x = destination.x;
+            position.z = destination.z;
+        }
+        else {
+            self.vitesse.x = 0.0;
+            self.vitesse.z = 0.0;
         }
 
-        if cible == glm::Vec3::new(0.0, 0.0, 0.0) {
-            self.ralentir(taux_rafraichissement);
+        let sol = labyrinthe.hauteur_sol(position);
+
+        if self.saut_demande && self.au_sol {
+            self.vitesse_verticale = VITESSE_SAUT;
+            self.au_sol = false;
+        }
+        self.saut_demande = false;
+
+        if !self.au_sol {
+            self.vitesse_verticale -= GRAVITE * duree;
+            pieds += self.vitesse_verticale * duree;
+        }
+
+        // Atterrissage, ou marche à monter ou à descendre sans décoller du sol
+        if pieds <= sol || (self.au_sol && pieds - sol <= HAUTEUR_MARCHE) {
+            pieds = sol;
+            self.vitesse_verticale = 0.0;
+            self.au_sol = true;
         }
         else {
+            self.au_sol = false;
+        }
+
+        // La tête ne traverse pas le plafond: au sol, on reste penché, en sautant, on retombe
+        let plafond = labyrinthe.hauteur_plafond(position) - MARGE_PLAFOND;
 
-            cible = cible.normalize() * vitesse;
-            self.accelerer_vers(&cible, taux_rafraichissement);
+        if pieds + self.hauteur_yeux > plafond {
+
+            if self.au_sol {
+                self.hauteur_yeux = plafond - pieds;
+            }
+            else {
+                pieds = plafond - self.hauteur_yeux;
+                self.vitesse_verticale = self.vitesse_verticale.min(0.0);
+            }
         }
+
+        position.y = pieds + self.hauteur_yeux;
+
+        self.avancer_balancement(duree);
     }
 
-    pub fn delta(&self, taux_rafraichissement: u64) -> glm::Vec3 {
-        
-        if self.mode_aerien {
-            return self.vitesse / taux_rafraichissement as f32;
+    // Volume du corps autour des yeux
+    fn capsule(&self) -> collision::Capsule {
+
+        const RAYON: f32 = 0.2;
+        const AU_DESSUS_YEUX: f32 = 0.1;
+
+        // En volant, il n'y a pas de pieds: seule la tête est considérée
+        let bas = if self.mode_aerien { -AU_DESSUS_YEUX } else { -self.hauteur_yeux };
+
+        collision::Capsule {
+            rayon: RAYON,
+            bas,
+            haut: AU_DESSUS_YEUX,
         }
+    }
+
+    // Décalages vertical et latéral de la tête
+    fn balancement(&self) -> (f32, f32) {
+
+        const AMPLITUDE: f32 = 0.035;
 
-        glm::Vec3::new(self.vitesse.x, 0.0, self.vitesse.z) / taux_rafraichissement as f32
+        let amplitude = AMPLITUDE * self.amplitude_balancement;
+        let phase = self.phase_balancement;
+
+        // La tête descend à chaque pas et se balance d'un côté à l'autre à chaque paire de pas
+        (-amplitude * (1.0 - (2.0 * phase).cos()) / 2.0, amplitude * 0.5 * phase.sin())
     }
 
-    fn ralentir(&mut self, taux_rafraichissement: u64) {
+    fn avancer_balancement(&mut self, duree: f32) {
+
+        const PAS_PAR_UNITE: f32 = 1.6;
+
+        let vitesse = (self.vitesse.x * self.vitesse.x + self.vitesse.z * self.vitesse.z).sqrt();
+        let cible = if self.au_sol { (vitesse / VITESSE_MARCHE).min(1.0) } else { 0.0 };
+
+        self.amplitude_balancement += (cible - self.amplitude_balancement) * (8.0 * duree).min(1.0);
 
-        let ralentissement = 1.0 - (10.0 / taux_rafraichissement as f32);
+        // Chaque pas est un demi-tour de la phase
+        self.phase_balancement += vitesse * PAS_PAR_UNITE * std::f32::consts::PI * duree;
+        self.phase_balancement %= 2.0 * std::f32::consts::PI;
+    }
+
+    fn ralentir(&mut self, duree: f32) {
+
+        let ralentissement = (1.0 - 10.0 * duree).max(0.0);
         self.vitesse = self.vitesse * ralentissement;
     }
 
-    fn accelerer_vers(&mut self, cible: &glm::Vec3, taux_rafraichissement: u64) {
+    fn accelerer_vers(&mut self, cible: &glm::Vec3, duree: f32) {
 
-        let proportion_cible = 20.0 / taux_rafraichissement as f32;
+        let proportion_cible = (20.0 * duree).min(1.0);
         self.vitesse = cible * proportion_cible + self.vitesse * (1.0 - proportion_cible);
     }
 }
 
+const HAUTEUR_DEBOUT: f32 = 1.5; // Des yeux au-dessus des pieds
+const VITESSE_MARCHE: f32 = 1.25; // Aussi en volant
+const VITESSE_ACCROUPI: f32 = 0.6;
+
 // Représente les angles d'une direction
 #[derive(Clone)]
 struct Angles {
@@ -236,12 +499,20 @@ impl Angles {
     }
 }
 
+struct ParametresRegard {
+
+    sensibilite: f32,
+    inverser_y: bool,
+    lissage: f32,
+}
+
 // Permet l'ajustement des angles selon les mouvements de la souris
 #[derive(Clone)]
 struct Regard {
 
     pub angles: Angles,
     vitesse_angles: Angles,
+    mouvement_lisse: (f32, f32), // Mouvement de la souris, lissé sur les dernières images
 }
 
 impl Regard {
@@ -252,21 +523,50 @@ impl Regard {
 
             angles: Angles::new(),
             vitesse_angles: Angles::new(),
+            mouvement_lisse: (0.0, 0.0),
         }
     }
 
-    pub fn ajuster_direction(&mut self, gestionnaire_evenements: &evenements::GestionnaireEvenements, taux_rafraichissement: u64) {
+    pub fn ajuster_direction(&mut self,
+                    gestionnaire_evenements: &evenements::GestionnaireEvenements,
+                    parametres: &ParametresRegard,
+                    duree: f32)
+    {
+        if duree <= 0.0 {
+            return;
+        }
 
-        let taux_rafraichissement = taux_rafraichissement as f32;
-        const SENSABILITE: f32 = 0.04;
+        const RADIANS_PAR_UNITE: f32 = 0.04 / 60.0; // Rotation pour chaque unité de mouvement de la souris
         const VITESSE_STABILISATION: f32 = 20.0;
 
-        let mut vitesse_angle_xz = SENSABILITE * gestionnaire_evenements.souris.delta_x();
-        let mut vitesse_angle_yz = SENSABILITE * gestionnaire_evenements.souris.delta_y();
+        // Le mouvement de la souris depuis la dernière image devient une vitesse, en unités par seconde
+        // Le lissage est donné pour des images de 1/60 s
+        let souris = &gestionnaire_evenements.souris;
+        let lissage = parametres.lissage.powf(duree * 60.0);
+        self.mouvement_lisse = (
+            self.mouvement_lisse.0 * lissage + souris.delta_x() / duree * (1.0 - lissage),
+            self.mouvement_lisse.1 * lissage + souris.delta_y() / duree * (1.0 - lissage),
+        );
+
+        let sensibilite = parametres.sensibilite;
+        let mut vitesse_angle_xz = RADIANS_PAR_UNITE * sensibilite * self.mouvement_lisse.0;
+        let mut vitesse_angle_yz = RADIANS_PAR_UNITE * sensibilite * self.mouvement_lisse.1;
+
+        // Le stick droit donne directement une vitesse de rotation
+        let manette = &gestionnaire_evenements.manette;
+        let (regard_x, regard_y) = manette.regard();
+        vitesse_angle_xz += manette.parametres.vitesse_regard * sensibilite * regard_x;
+        vitesse_angle_yz += manette.parametres.vitesse_regard * sensibilite * regard_y;
+
+        if parametres.inverser_y {
+            vitesse_angle_yz = -vitesse_angle_yz;
+        }
 
-        self.vitesse_angles.ajouter(
-            -VITESSE_STABILISATION * self.vitesse_angles.angle_xz / taux_rafraichissement,
-            -VITESSE_STABILISATION * self.vitesse_angles.angle_yz / taux_rafraichissement);        
+        // La rotation précédente s'estompe, peu importe la durée des images
+        let stabilisation = (-VITESSE_STABILISATION * duree).exp();
+        self.vitesse_angles.modifier(
+            self.vitesse_angles.angle_xz * stabilisation,
+            self.vitesse_angles.angle_yz * stabilisation);
 
         if vitesse_angle_xz.abs() < self.vitesse_angles.angle_xz.abs() {
             vitesse_angle_xz = self.vitesse_angles.angle_xz;
@@ -278,8 +578,63 @@ impl Regard {
         self.vitesse_angles.modifier(vitesse_angle_xz, vitesse_angle_yz);
 
         self.angles.ajouter(
-            self.vitesse_angles.angle_xz / taux_rafraichissement,
-            self.vitesse_angles.angle_yz / taux_rafraichissement);
+            self.vitesse_angles.angle_xz * duree,
+            self.vitesse_angles.angle_yz * duree);
         self.angles.maintenir_angles();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use aleatoire;
+
+    // Marche pendant la durée donnée, de la première cellule du chemin vers la deuxième, surélevée de marche
+    // Retourne l'index de la cellule atteinte dans le chemin, et la hauteur des pieds
+    fn marcher_vers_marche(marche: f32, duree: f32) -> (Option<usize>, f32) {
+
+        aleatoire::initialiser(7);
+        let mut labyrinthe = labyrinthe::Labyrinthe::new(9, 9, labyrinthe::Algorithme::Profondeur);
+
+        let chemin = labyrinthe.chemin_sortie().to_vec();
+        assert!(chemin.len() >= 2);
+
+        labyrinthe.surelever_sol(chemin[0].0, chemin[0].1, 0.0);
+        labyrinthe.surelever_sol(chemin[1].0, chemin[1].1, marche);
+
+        let centre = |(x, z): (i32, i32)| labyrinthe.position_monde(x as f32 + 0.5, z as f32 + 0.5);
+        let mut position = centre(chemin[0]) + glm::Vec3::new(0.0, HAUTEUR_DEBOUT, 0.0);
+
+        let mut deplacement = Deplacement::new();
+        deplacement.vitesse = glm::normalize(&(centre(chemin[1]) - centre(chemin[0]))) * VITESSE_MARCHE;
+
+        for _ in 0..(duree / PAS_SIMULATION) as usize {
+            deplacement.marcher(&mut position, &labyrinthe, PAS_SIMULATION);
+        }
+
+        let (x, z) = labyrinthe.coordonnees_grille(&position);
+        let cellule = (x.floor() as i32, z.floor() as i32);
+
+        (chemin.iter().position(|autre| *autre == cellule), position.y - deplacement.hauteur_yeux)
+    }
+
+    #[test]
+    fn marche_basse_montee() {
+
+        let (cellule, pieds) = marcher_vers_marche(0.2, 0.8);
+
+        assert_eq!(cellule, Some(1));
+        assert!((pieds - 0.2).abs() < 1e-4, "pieds à {}", pieds);
+    }
+
+    #[test]
+    fn marche_haute_bloque_comme_un_mur() {
+
+        let (cellule, pieds) = marcher_vers_marche(0.6, 0.8);
+
+        assert_eq!(cellule, Some(0));
+        assert!(pieds.abs() < 1e-4, "pieds à {}", pieds);
+    }
+}
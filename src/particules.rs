@@ -0,0 +1,222 @@
+extern crate nalgebra_glm as glm;
+
+use aleatoire;
+
+/*
+    Interface publique du module particules
+
+    Sert à animer les flammes et les braises au-dessus des torches
+*/
+
+pub struct SystemeParticules {
+
+    emetteurs: std::vec::Vec<Emetteur>,
+    particules: std::vec::Vec<Particule>,
+}
+
+impl SystemeParticules {
+
+    // Chaque position représente la flamme d'une torche
+    pub fn new(positions_flammes: &[[f32; 3]]) -> SystemeParticules {
+
+        let emetteurs: std::vec::Vec<Emetteur> = positions_flammes.iter().map(|position| {
+            Emetteur {
+                position: *position,
+                accumulation_flamme: aleatoire::nombre(1.0),
+                accumulation_braise: aleatoire::nombre(1.0),
+            }
+        }).collect();
+
+        let capacite = emetteurs.len() * (FLAMMES_PAR_SECONDE * DUREE_VIE_FLAMME) as usize * 2;
+
+        SystemeParticules {
+
+            emetteurs,
+            particules: std::vec::Vec::with_capacity(capacite),
+        }
+    }
+
+    // delta: secondes écoulées depuis la dernière mise à jour
+    pub fn mettre_a_jour(&mut self, delta: f32) {
+
+        // Évite une avalanche de particules après une longue pause
+        let delta = delta.min(0.1);
+
+        for particule in self.particules.iter_mut() {
+
+            particule.age += delta;
+            particule.position += particule.vitesse * delta;
+
+            // Les flammes ralentissent horizontalement et sont tirées vers le haut
+            particule.vitesse.x *= 1.0 - 2.0 * delta;
+            particule.vitesse.z *= 1.0 - 2.0 * delta;
+            particule.vitesse.y += particule.portance * delta;
+        }
+
+        self.particules.retain(|particule| particule.age < particule.duree_vie);
+
+        for emetteur in self.emetteurs.iter_mut() {
+
+            emetteur.accumulation_flamme += delta * FLAMMES_PAR_SECONDE;
+            while emetteur.accumulation_flamme >= 1.0 {
+
+                emetteur.accumulation_flamme -= 1.0;
+                self.particules.push(Particule::flamme(&emetteur.position));
+            }
+
+            emetteur.accumulation_braise += delta * BRAISES_PAR_SECONDE;
+            while emetteur.accumulation_braise >= 1.0 {
+
+                emetteur.accumulation_braise -= 1.0;
+                self.particules.push(Particule::braise(&emetteur.position));
+            }
+        }
+    }
+
+    // Crée les sommets de toutes les particules; chaque particule est un carré orienté vers la caméra par le vertex shader
+    pub fn generer_sommets(&self) -> (std::vec::Vec<SommetParticule>, std::vec::Vec<u32>) {
+
+        let mut sommets = std::vec::Vec::with_capacity(self.particules.len() * 4);
+        let mut indices = std::vec::Vec::with_capacity(self.particules.len() * 6);
+
+        const COINS: [[f32; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+        for particule in self.particules.iter() {
+
+            let premier_sommet = sommets.len() as u32;
+            let progression = particule.age / particule.duree_vie;
+
+            let couleur = particule.couleur(progression);
+            let taille = particule.taille * (1.0 - 0.6 * progression);
+
+            for coin in COINS.iter() {
+
+                sommets.push(SommetParticule {
+                    centre: [particule.position.x, particule.position.y, particule.position.z],
+                    coin: *coin,
+                    taille,
+                    couleur,
+                });
+            }
+
+            // Deux triangles par carré
+            indices.extend_from_slice(&[
+                premier_sommet, premier_sommet + 1, premier_sommet + 2,
+                premier_sommet, premier_sommet + 2, premier_sommet + 3,
+            ]);
+        }
+
+        (sommets, indices)
+    }
+}
+
+// Primitive pour OpenGL
+#[derive(Copy, Clone)]
+pub struct SommetParticule {
+    centre: [f32; 3],
+    coin: [f32; 2], // Entre -1 et 1, permet de déplacer le sommet par rapport au centre
+    taille: f32,
+    couleur: [f32; 4],
+}
+// Permet à Glium de l'utiliser avec OpenGL
+implement_vertex!(SommetParticule, centre, coin, taille, couleur);
+
+
+
+
+
+/*
+    Partie privée du module particules
+*/
+
+const FLAMMES_PAR_SECONDE: f32 = 40.0;
+const BRAISES_PAR_SECONDE: f32 = 1.5;
+const DUREE_VIE_FLAMME: f32 = 0.45;
+const DUREE_VIE_BRAISE: f32 = 1.6;
+
+// Nombre aléatoire entre -limite et limite
+fn ecart_aleatoire(limite: f32) -> f32 {
+
+    aleatoire::nombre(2.0 * limite) - limite
+}
+
+struct Emetteur {
+
+    position: [f32; 3],
+
+    // Fractions de particules pas encore émises, pour un débit indépendant du taux de rafraîchissement
+    accumulation_flamme: f32,
+    accumulation_braise: f32,
+}
+
+enum SorteParticule {
+
+    Flamme,
+    Braise,
+}
+
+struct Particule {
+
+    sorte: SorteParticule,
+
+    position: glm::Vec3,
+    vitesse: glm::Vec3,
+    portance: f32, // Accélération vers le haut
+
+    age: f32,
+    duree_vie: f32,
+    taille: f32,
+}
+
+impl Particule {
+
+    fn flamme(position: &[f32; 3]) -> Particule {
+
+        const RAYON: f32 = 0.015;
+
+        Particule {
+
+            sorte: SorteParticule::Flamme,
+
+            position: glm::Vec3::new(
+                position[0] + ecart_aleatoire(RAYON),
+                position[1] - 0.02,
+                position[2] + ecart_aleatoire(RAYON)),
+            vitesse: glm::Vec3::new(ecart_aleatoire(0.05), 0.15 + aleatoire::nombre(0.1), ecart_aleatoire(0.05)),
+            portance: 0.6,
+
+            age: 0.0,
+            duree_vie: DUREE_VIE_FLAMME * (0.6 + aleatoire::nombre(0.4)),
+            taille: 0.035 + aleatoire::nombre(0.015),
+        }
+    }
+
+    fn braise(position: &[f32; 3]) -> Particule {
+
+        Particule {
+
+            sorte: SorteParticule::Braise,
+
+            position: glm::Vec3::new(position[0], position[1], position[2]),
+            vitesse: glm::Vec3::new(ecart_aleatoire(0.12), 0.25 + aleatoire::nombre(0.2), ecart_aleatoire(0.12)),
+            portance: 0.1,
+
+            age: 0.0,
+            duree_vie: DUREE_VIE_BRAISE * (0.5 + aleatoire::nombre(0.5)),
+            taille: 0.006,
+        }
+    }
+
+    // progression: 0 à la naissance, 1 à la mort
+    fn couleur(&self, progression: f32) -> [f32; 4] {
+
+        let disparition = 1.0 - progression;
+
+        match self.sorte {
+
+            // Du jaune vers le rouge
+            SorteParticule::Flamme => [1.0, 0.85 - 0.6 * progression, 0.3 - 0.3 * progression, 0.6 * disparition],
+            SorteParticule::Braise => [1.0, 0.5, 0.15, disparition],
+        }
+    }
+}
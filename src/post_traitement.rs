@@ -0,0 +1,345 @@
+use glium::{Surface, CapabilitiesSource}; // Surface et CapabilitiesSource sont des traits et doivent être importés
+
+use configuration;
+use shaders;
+
+/*
+    Interface publique du module post_traitement
+
+    Sert à dessiner la scène dans une image HDR, puis à appliquer des effets sur l'image complète
+*/
+
+pub struct PostTraitement {
+
+    dimensions: (u32, u32),
+    echantillons: u32, // Demandés pour le MSAA, 0 le désactive
+
+    couleur_hdr: glium::texture::Texture2d,
+    profondeur: glium::texture::DepthTexture2d,
+    multiechantillonnage: Option<Multiechantillonnage>, // Avec le MSAA, la scène y est dessinée puis résolue
+    bloom: [glium::texture::Texture2d; 2], // Demi-résolution, utilisées à tour de rôle par le flou
+    image_composee: glium::texture::Texture2d, // Résultat de la composition, avant le FXAA
+}
+
+impl PostTraitement {
+
+    // Les dimensions doivent être non nulles
+    // Si les textures multiéchantillonnées ne peuvent pas être créées, le MSAA est désactivé
+    pub fn new(affichage: &dyn glium::backend::Facade, dimensions: (u32, u32), echantillons: u32) -> PostTraitement {
+
+        let (largeur, hauteur) = dimensions;
+        let dimensions_bloom = ((largeur / 2).max(1), (hauteur / 2).max(1));
+
+        let texture_hdr = |largeur: u32, hauteur: u32| -> glium::texture::Texture2d {
+            glium::texture::Texture2d::empty_with_format(
+                affichage,
+                glium::texture::UncompressedFloatFormat::F16F16F16F16,
+                glium::texture::MipmapsOption::NoMipmap,
+                largeur,
+                hauteur).unwrap()
+        };
+
+        let multiechantillonnage = if echantillons > 1 {
+
+            // Le pilote peut limiter le nombre d'échantillons selon le format
+            let echantillons = echantillons_supportes(affichage, echantillons);
+
+            if echantillons <= 1 {
+                println!("Le MSAA n'est pas supporté par les textures de la scène, il est désactivé");
+                None
+            }
+            else {
+                match Multiechantillonnage::new(affichage, dimensions, echantillons) {
+                    Ok(multiechantillonnage) => Some(multiechantillonnage),
+                    Err(erreur) => {
+                        println!("Le MSAA à {} échantillons n'est pas supporté, il est désactivé: {:?}", echantillons, erreur);
+                        None
+                    }
+                }
+            }
+        }
+        else {
+            None
+        };
+
+        PostTraitement {
+
+            dimensions,
+            echantillons,
+
+            couleur_hdr: texture_hdr(largeur, hauteur),
+            profondeur: glium::texture::DepthTexture2d::empty_with_format(
+                affichage,
+                glium::texture::DepthFormat::I24,
+                glium::texture::MipmapsOption::NoMipmap,
+                largeur,
+                hauteur).unwrap(),
+            multiechantillonnage,
+            bloom: [
+                texture_hdr(dimensions_bloom.0, dimensions_bloom.1),
+                texture_hdr(dimensions_bloom.0, dimensions_bloom.1),
+            ],
+            image_composee: glium::texture::Texture2d::empty_with_format(
+                affichage,
+                glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                glium::texture::MipmapsOption::NoMipmap,
+                largeur,
+                hauteur).unwrap(),
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    pub fn echantillons(&self) -> u32 {
+        self.echantillons
+    }
+
+    // Surface dans laquelle la scène doit être dessinée avant d'appeler appliquer
+    pub fn cible_scene(&self, affichage: &dyn glium::backend::Facade) -> glium::framebuffer::SimpleFrameBuffer<'_> {
+
+        if let Some(ref multiechantillonnage) = self.multiechantillonnage {
+
+            return glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
+                affichage,
+                &multiechantillonnage.couleur,
+                &multiechantillonnage.profondeur).unwrap();
+        }
+
+        glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
+            affichage,
+            &self.couleur_hdr,
+            &self.profondeur).unwrap()
+    }
+
+    // Applique toutes les passes actives et dessine le résultat sur la surface
+    pub fn appliquer<S: Surface>(
+        &self,
+        surface: &mut S,
+        parametres: &configuration::ParametresPostTraitement,
+        camera: &configuration::ParametresCamera,
+        programme_opengl: &shaders::ProgrammeOpenGL,
+        affichage: &dyn glium::backend::Facade)
+    {
+        if let Some(ref multiechantillonnage) = self.multiechantillonnage {
+            self.resoudre(multiechantillonnage, programme_opengl, affichage);
+        }
+
+        let echantillonner = |texture| {
+            glium::uniforms::Sampler::new(texture)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
+                .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp)
+        };
+
+        if parametres.bloom {
+
+            // On ne garde que les zones plus lumineuses que le seuil
+            let donnees_extraction = uniform! {
+                scene: echantillonner(&self.couleur_hdr),
+                seuil: parametres.seuil_bloom,
+            };
+
+            dessiner_ecran(
+                &mut self.bloom[0].as_surface(),
+                &programme_opengl.programme_extraction,
+                &donnees_extraction);
+
+            // Flou gaussien séparé en une passe horizontale et une passe verticale
+            const ITERATIONS_FLOU: u32 = 2;
+            let (largeur, hauteur) = self.bloom[0].dimensions();
+
+            for _ in 0..ITERATIONS_FLOU {
+
+                let donnees_horizontal = uniform! {
+                    image: echantillonner(&self.bloom[0]),
+                    pas: [1.0 / largeur as f32, 0.0f32],
+                };
+                dessiner_ecran(
+                    &mut self.bloom[1].as_surface(),
+                    &programme_opengl.programme_flou,
+                    &donnees_horizontal);
+
+                let donnees_vertical = uniform! {
+                    image: echantillonner(&self.bloom[1]),
+                    pas: [0.0f32, 1.0 / hauteur as f32],
+                };
+                dessiner_ecran(
+                    &mut self.bloom[0].as_surface(),
+                    &programme_opengl.programme_flou,
+                    &donnees_vertical);
+            }
+        }
+
+        let donnees_composition = uniform! {
+            scene: echantillonner(&self.couleur_hdr),
+            profondeur: glium::uniforms::Sampler::new(&self.profondeur)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            bloom: echantillonner(&self.bloom[0]),
+
+            plan_proche: camera.plan_proche,
+            plan_loin: camera.plan_loin,
+
+            tonalite_active: parametres.tonalite,
+            exposition: parametres.exposition,
+            gamma_actif: parametres.gamma,
+            bloom_actif: parametres.bloom,
+            intensite_bloom: parametres.intensite_bloom,
+            brouillard_actif: parametres.brouillard,
+            densite_brouillard: parametres.densite_brouillard,
+            couleur_brouillard: parametres.couleur_brouillard,
+            vignette_active: parametres.vignette,
+            intensite_vignette: parametres.intensite_vignette,
+        };
+
+        if !parametres.fxaa {
+
+            dessiner_ecran(surface, &programme_opengl.programme_composition, &donnees_composition);
+            return;
+        }
+
+        // Le FXAA s'applique sur l'image composée, en couleurs finales
+        dessiner_ecran(
+            &mut self.image_composee.as_surface(),
+            &programme_opengl.programme_composition,
+            &donnees_composition);
+
+        let (largeur, hauteur) = self.dimensions;
+        let donnees_fxaa = uniform! {
+            image: echantillonner(&self.image_composee),
+            taille_texel: [1.0 / largeur as f32, 1.0 / hauteur as f32],
+        };
+
+        dessiner_ecran(surface, &programme_opengl.programme_fxaa, &donnees_fxaa);
+    }
+
+    // Ramène les images multiéchantillonnées à un échantillon par pixel pour les autres passes
+    fn resoudre(&self,
+                multiechantillonnage: &Multiechantillonnage,
+                programme_opengl: &shaders::ProgrammeOpenGL,
+                affichage: &dyn glium::backend::Facade)
+    {
+        // La couleur est la moyenne des échantillons
+        let source = glium::framebuffer::SimpleFrameBuffer::new(affichage, &multiechantillonnage.couleur).unwrap();
+        source.fill(&self.couleur_hdr.as_surface(), glium::uniforms::MagnifySamplerFilter::Nearest);
+
+        // glium ne copie que la couleur; la profondeur est écrite par un shader
+        let mut cible = glium::framebuffer::SimpleFrameBuffer::depth_only(affichage, &self.profondeur).unwrap();
+
+        let donnees = uniform! {
+            profondeur: &multiechantillonnage.profondeur,
+        };
+
+        let parametres = glium::DrawParameters {
+            depth: glium::Depth {
+                test: glium::DepthTest::Overwrite,
+                write: true,
+                .. Default::default()
+            },
+            .. Default::default()
+        };
+
+        cible.draw(
+            glium::vertex::EmptyVertexAttributes { len: 3 },
+            glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+            &programme_opengl.programme_resolution_profondeur,
+            &donnees,
+            &parametres,
+        ).unwrap();
+    }
+}
+
+// Avec OpenGL ES, glium ne sait créer ni les textures de profondeur ni les textures HDR
+pub fn est_supporte(affichage: &dyn glium::backend::Facade) -> bool {
+
+    affichage.get_context().get_opengl_version().0 == glium::Api::Gl
+}
+
+// Dessine un triangle couvrant tout l'écran; les coordonnées sont calculées par le vertex shader
+pub fn dessiner_ecran<S: Surface, U: glium::uniforms::Uniforms>(surface: &mut S, programme: &glium::Program, uniforms: &U) {
+
+    surface.draw(
+        glium::vertex::EmptyVertexAttributes { len: 3 },
+        glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+        programme,
+        uniforms,
+        &Default::default(),
+    ).unwrap();
+}
+
+
+
+
+
+/*
+    Partie privée du module post_traitement
+*/
+
+// Scène multiéchantillonnée, à la même résolution que les autres images
+// Plus grand nombre d'échantillons, au plus celui demandé, supporté par les formats de la scène
+// Si le pilote ne donne pas l'information, le nombre demandé est conservé
+fn echantillons_supportes(affichage: &dyn glium::backend::Facade, echantillons: u32) -> u32 {
+
+    let formats = [
+        glium::texture::TextureFormat::UncompressedFloat(glium::texture::UncompressedFloatFormat::F16F16F16F16),
+        glium::texture::TextureFormat::DepthFormat(glium::texture::DepthFormat::I24),
+    ];
+
+    let capacites = affichage.get_context().get_capabilities();
+    let mut supportes = echantillons;
+
+    for format in formats.iter() {
+
+        let possibles = match capacites.internal_formats_textures.get(format) {
+            Some(infos) => match infos.multisamples {
+                Some(ref possibles) => possibles,
+                None => continue,
+            },
+            None => continue,
+        };
+
+        supportes = possibles.iter()
+            .map(|&nombre| nombre as u32)
+            .filter(|&nombre| nombre <= supportes)
+            .max()
+            .unwrap_or(0);
+    }
+
+    supportes
+}
+
+struct Multiechantillonnage {
+
+    couleur: glium::texture::Texture2dMultisample,
+    profondeur: glium::texture::DepthTexture2dMultisample,
+}
+
+impl Multiechantillonnage {
+
+    fn new(affichage: &dyn glium::backend::Facade,
+           dimensions: (u32, u32),
+           echantillons: u32) -> Result<Multiechantillonnage, glium::texture::TextureCreationError>
+    {
+        let (largeur, hauteur) = dimensions;
+
+        Ok(Multiechantillonnage {
+
+            couleur: glium::texture::Texture2dMultisample::empty_with_format(
+                affichage,
+                glium::texture::UncompressedFloatFormat::F16F16F16F16,
+                glium::texture::MipmapsOption::NoMipmap,
+                largeur,
+                hauteur,
+                echantillons)?,
+            profondeur: glium::texture::DepthTexture2dMultisample::empty_with_format(
+                affichage,
+                glium::texture::DepthFormat::I24,
+                glium::texture::MipmapsOption::NoMipmap,
+                largeur,
+                hauteur,
+                echantillons)?,
+        })
+    }
+}
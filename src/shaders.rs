@@ -2,6 +2,12 @@
     Interface publique du module shaders
 
     Sert à compiler les shaders.
+
+    Le code GLSL est lu dans le dossier shaders/, ce qui permet de le modifier pendant que le programme tourne
+    Si un fichier est absent, on utilise la copie intégrée à l'exécutable
+
+    Les fichiers sont écrits pour OpenGL 4.3; la ligne #version est remplacée selon le contexte obtenu,
+    ce qui permet aussi d'utiliser OpenGL 3.3 (blocs uniformes) et OpenGL ES 3.0 (tableaux uniformes)
 */
 
 // Représente un programme de shaders OpenGL
@@ -9,71 +15,161 @@ pub struct ProgrammeOpenGL {
 
     pub programme_prepasse: glium::Program, // Permet de calculer la profondeur
     pub programme: glium::Program, // Permet de calculer la couleur
+    pub programme_particules: glium::Program, // Permet de dessiner les flammes des torches
+
+    // Post-traitement de l'image complète
+    pub programme_extraction: glium::Program, // Garde les zones lumineuses pour le bloom
+    pub programme_flou: glium::Program,
+    pub programme_composition: glium::Program, // Combine toutes les passes et dessine sur l'écran
+    pub programme_copie: glium::Program, // Étire une image sur l'écran, sans effets
+    pub programme_fxaa: glium::Program, // Anticrénelage de l'image finale
+    pub programme_resolution_profondeur: glium::Program, // Profondeur multiéchantillonnée vers une texture ordinaire
+
+    pub programme_carte: glium::Program, // Dessine la carte par-dessus l'image finale
+    pub programme_texte: glium::Program,
+    pub programme_lignes: glium::Program, // Dessine des lignes dans la scène, pour le débogage
+
+    // Avec OpenGL ES, glium ne sait pas lire les blocs uniformes; les lumières et les matériaux
+    // sont alors envoyés élément par élément, voir ecran::Vue::dessiner_scene
+    pub tableaux_uniformes: bool,
+
+    surveillance: Surveillance,
 }
 
 impl ProgrammeOpenGL {
 
-    pub fn new(affichage: &glium::Display) -> ProgrammeOpenGL {
+    pub fn new(affichage: &dyn glium::backend::Facade) -> ProgrammeOpenGL {
 
-        let vertex_shader_prepasse = code_source::vertex_shader_prepasse();
-        let fragment_shader_prepasse = code_source::fragment_shader_prepasse();
-        let programme_prepasse = glium::Program::from_source(
-            affichage,
-            &vertex_shader_prepasse,
-            &fragment_shader_prepasse,
-            None);
+        let version = affichage.get_context().get_opengl_version_string().to_string();
+        let tableaux_uniformes = en_tete(affichage) == EN_TETE_300_ES;
+
+        if tableaux_uniformes {
+            println!("OpenGL {}: les shaders utilisent des tableaux uniformes au lieu des shader storage buffers", version);
+        }
+        else if en_tete(affichage) != EN_TETE_430 {
+            println!("OpenGL {}: les shaders utilisent des blocs uniformes au lieu des shader storage buffers", version);
+        }
 
-        // On vérifie si le programme est correct, sinon on arrête le programme avec l'erreur
-        let programme_prepasse = match programme_prepasse {
+        let programme_prepasse = ProgrammeOpenGL::compiler(affichage, PREPASSE);
+        let programme = ProgrammeOpenGL::compiler(affichage, SCENE);
+        let programme_particules = ProgrammeOpenGL::compiler(affichage, PARTICULES);
 
-            Ok(o) => o,
-            Err(e) => {
-                
-                ProgrammeOpenGL::erreur_creation(&e);
-                panic!("La compilation des shaders a échouée");
+        let programme_extraction = ProgrammeOpenGL::compiler(affichage, EXTRACTION);
+        let programme_flou = ProgrammeOpenGL::compiler(affichage, FLOU);
+        let programme_composition = ProgrammeOpenGL::compiler(affichage, COMPOSITION);
+        let programme_copie = ProgrammeOpenGL::compiler(affichage, COPIE);
+        let programme_fxaa = ProgrammeOpenGL::compiler(affichage, FXAA);
+        let programme_resolution_profondeur = ProgrammeOpenGL::compiler(affichage, RESOLUTION_PROFONDEUR);
+
+        let programme_carte = ProgrammeOpenGL::compiler(affichage, CARTE);
+        let programme_texte = ProgrammeOpenGL::compiler(affichage, TEXTE);
+        let programme_lignes = ProgrammeOpenGL::compiler(affichage, LIGNES);
+
+        ProgrammeOpenGL {
+
+            programme_prepasse,
+            programme,
+            programme_particules,
+
+            programme_extraction,
+            programme_flou,
+            programme_composition,
+            programme_copie,
+            programme_fxaa,
+            programme_resolution_profondeur,
+
+            programme_carte,
+            programme_texte,
+            programme_lignes,
+
+            tableaux_uniformes,
+
+            surveillance: Surveillance::new(&[PREPASSE, SCENE]),
+        }
+    }
+
+    // Recompile programme_prepasse et programme si leurs fichiers ont été modifiés
+    // En cas d'erreur, l'ancien programme est conservé
+    pub fn recharger_modifies(&mut self, affichage: &dyn glium::backend::Facade) {
+
+        for sources in self.surveillance.sources_modifiees() {
+
+            let programme = match ProgrammeOpenGL::compiler_fichiers(affichage, sources) {
+
+                Ok(programme) => programme,
+                Err(()) => {
+                    println!("L'ancien programme est conservé jusqu'à la prochaine modification.\n");
+                    continue;
+                }
+            };
+
+            println!("Shaders rechargés: {}, {}", sources.0, sources.1);
+
+            if sources == PREPASSE {
+                self.programme_prepasse = programme;
             }
-        };
+            else if sources == SCENE {
+                self.programme = programme;
+            }
+        }
+    }
 
-        let vertex_shader = code_source::vertex_shader();
-        let fragment_shader = code_source::fragment_shader();
-        let programme = glium::Program::from_source(
-            affichage,
-            &vertex_shader,
-            &fragment_shader,
-            None);
+    // Les fichiers ont priorité; s'ils ne compilent pas, on se rabat sur le code intégré
+    fn compiler(affichage: &dyn glium::backend::Facade, sources: Sources) -> glium::Program {
+
+        if let Ok(programme) = ProgrammeOpenGL::compiler_fichiers(affichage, sources) {
+            return programme;
+        }
 
-        // On vérifie si le programme est correct, sinon on arrête le programme avec l'erreur
-        let programme = match programme {
+        println!("Le code intégré à l'exécutable est utilisé pour {} et {}.\n", sources.0, sources.1);
 
-            Ok(o) => o,
-            Err(e) => {
-                
-                ProgrammeOpenGL::erreur_creation(&e);
+        let vertex_shader = code_source::integre(sources.0);
+        let fragment_shader = code_source::integre(sources.1);
+
+        match glium::Program::from_source(
+            affichage,
+            &adapter(vertex_shader, affichage),
+            &adapter(fragment_shader, affichage),
+            None)
+        {
+            Ok(programme) => programme,
+            Err(erreur) => {
+
+                ProgrammeOpenGL::erreur_creation(&erreur, sources, (vertex_shader, fragment_shader));
                 panic!("La compilation des shaders a échouée");
             }
-        };
-
-        ProgrammeOpenGL {
-            
-            programme_prepasse: programme_prepasse,
-            programme: programme 
         }
     }
 
-    fn erreur_creation(erreur: &glium::program::ProgramCreationError) {
+    // Affiche l'erreur s'il y a lieu
+    fn compiler_fichiers(affichage: &dyn glium::backend::Facade, sources: Sources) -> Result<glium::Program, ()> {
+
+        let vertex_shader = code_source::lire(sources.0);
+        let fragment_shader = code_source::lire(sources.1);
+
+        glium::Program::from_source(
+            affichage,
+            &adapter(&vertex_shader, affichage),
+            &adapter(&fragment_shader, affichage),
+            None)
+            .map_err(|erreur| ProgrammeOpenGL::erreur_creation(&erreur, sources, (&vertex_shader, &fragment_shader)))
+    }
+
+    fn erreur_creation(erreur: &glium::program::ProgramCreationError, sources: Sources, codes: (&str, &str)) {
 
         match erreur {
-                    
+
             glium::program::ProgramCreationError::CompilationError(e) => {
-                println!("\n\nIl y a au moins une erreur de compilation des shaders:\n{}", e);
+                println!("\n\nIl y a au moins une erreur de compilation des shaders {} et {}:\n{}", sources.0, sources.1, e);
+                afficher_lignes_erreur(e, sources, codes);
             },
 
             glium::program::ProgramCreationError::LinkingError(e) => {
-                println!("\n\nIl y a au moins une erreur de linking des shaders:\n{}", e);
+                println!("\n\nIl y a au moins une erreur de linking des shaders {} et {}:\n{}", sources.0, sources.1, e);
             },
 
-            _ => { 
-                println!("\n\nUne erreur inconnue est survenue à la compilation des shaders.");
+            _ => {
+                println!("\n\nUne erreur inconnue est survenue à la compilation des shaders {} et {}.", sources.0, sources.1);
                 println!("Voir glium::program::ProgramCreationError\n");
             },
         }
@@ -88,140 +184,181 @@ impl ProgrammeOpenGL {
     Partie privée du module shaders
 */
 
-mod code_source
-{
-    // Déclaration de tous les shaders utilisés
-    // La notation r#""# permet de préserver la chaîne brute
+const CHEMIN: &str = "shaders/";
 
-    pub fn vertex_shader_prepasse() -> std::string::String {
+// Fichiers du vertex shader et du fragment shader d'un programme
+type Sources = (&'static str, &'static str);
 
-        std::string::String::from(r#"
-            #version 430
-            uniform layout(std140);
+const PREPASSE: Sources = ("prepasse.vert", "prepasse.frag");
+const SCENE: Sources = ("scene.vert", "scene.frag");
+const PARTICULES: Sources = ("particules.vert", "particules.frag");
+const EXTRACTION: Sources = ("ecran.vert", "extraction.frag");
+const FLOU: Sources = ("ecran.vert", "flou.frag");
+const COMPOSITION: Sources = ("ecran.vert", "composition.frag");
+const COPIE: Sources = ("ecran.vert", "copie.frag");
+const FXAA: Sources = ("ecran.vert", "fxaa.frag");
+const RESOLUTION_PROFONDEUR: Sources = ("ecran.vert", "resolution_profondeur.frag");
+const CARTE: Sources = ("carte.vert", "carte.frag");
+const TEXTE: Sources = ("texte.vert", "texte.frag");
+const LIGNES: Sources = ("lignes.vert", "carte.frag");
 
-            uniform mat4 camera_perspective;
-            
-            in vec3 position;
-            in vec3 normale;
-            in vec3 coordonnees_texture;
+const EN_TETE_430: &str = "#version 430\n";
+const EN_TETE_330: &str = "#version 330 core\n#define BLOCS_UNIFORMES\n";
+const EN_TETE_300_ES: &str = "#version 300 es\n#define TABLEAUX_UNIFORMES\n\
+    precision highp float;\nprecision highp int;\nprecision highp sampler2D;\nprecision highp sampler2DArray;\n";
 
-            void main() {
-                gl_Position = camera_perspective * vec4(position, 1.0);
-            }
-        "#)
+fn en_tete(affichage: &dyn glium::backend::Facade) -> &'static str {
+
+    match *affichage.get_context().get_opengl_version() {
+
+        glium::Version(glium::Api::Gl, majeure, mineure) if (majeure, mineure) >= (4, 3) => EN_TETE_430,
+        glium::Version(glium::Api::Gl, ..) => EN_TETE_330,
+        glium::Version(glium::Api::GlEs, ..) => EN_TETE_300_ES,
     }
+}
 
-    pub fn fragment_shader_prepasse() -> std::string::String {
+// Remplace la ligne #version; #line conserve les numéros de ligne du fichier dans les erreurs
+fn adapter(code: &str, affichage: &dyn glium::backend::Facade) -> std::string::String {
 
-        std::string::String::from(r#"
-            #version 430
-            uniform layout(std140);
-            
-            void main() {
+    let corps = match code.find('\n') {
+        Some(fin) if code.starts_with("#version") => &code[fin + 1..],
+        _ => code,
+    };
 
-            }
-        "#)
-    }
-    
-    pub fn vertex_shader() -> std::string::String {
+    format!("{}#line 2\n{}", en_tete(affichage), corps)
+}
 
-        std::string::String::from(r#"
-            #version 430
-            uniform layout(std140);
+// Le journal ne précise pas quel shader est fautif; on montre donc la ligne dans les deux fichiers
+fn afficher_lignes_erreur(journal: &str, sources: Sources, codes: (&str, &str)) {
 
-            uniform mat4 camera_perspective;
-            uniform vec3 direction_regard;
+    let mut numeros: std::vec::Vec<usize> = journal.lines().filter_map(numero_ligne).collect();
+    numeros.sort();
+    numeros.dedup();
 
-            const uint NBR_LUMIERES = 8;
+    for numero in numeros {
 
-            layout(std140) buffer lumieres {
-                vec4 positions[NBR_LUMIERES];
-                vec4 couleurs[NBR_LUMIERES];
-            };
-            
-            in vec3 position;
-            in vec3 normale;
-            in vec3 coordonnees_texture;
-
-            out vec3 normal;
-            out vec3 coord_tex;
-            
-            out vec3 directionRegard;
-            
-            out Lumieres {
-                vec4 couleurs[NBR_LUMIERES];
-                vec3 directions[NBR_LUMIERES];
-                float distances[NBR_LUMIERES];
-            } outLumieres;
-
-            void main() {
-                gl_Position = camera_perspective * vec4(position, 1.0);
-                
-                normal = normale;
-
-                coord_tex = coordonnees_texture;
-
-                directionRegard = direction_regard;
-
-                for(int i=0; i<NBR_LUMIERES; ++i) {
-                    outLumieres.distances[i] = distance(vec3(positions[i]), position);
-                    outLumieres.directions[i] = normalize(position - vec3(positions[i]));
-                    outLumieres.couleurs[i] = couleurs[i];
-                }
+        for (fichier, code) in [(sources.0, codes.0), (sources.1, codes.1)].iter() {
+
+            if let Some(ligne) = code.lines().nth(numero - 1) {
+                println!("  {}:{:<4}| {}", fichier, numero, ligne);
             }
-        "#)
+        }
     }
-    
-    pub fn fragment_shader() -> std::string::String {
 
-        std::string::String::from(r#"
-            #version 430
-            uniform layout(std140);
+    println!();
+}
 
-            uniform sampler2DArray textures;
+// Les pilotes écrivent «0:12(5): error» (Mesa), «0(12) : error» (NVIDIA) ou «ERROR: 0:12:» (AMD, Intel)
+fn numero_ligne(ligne: &str) -> Option<usize> {
 
-            in vec3 normal;
-            in vec3 coord_tex;
+    let debut = ligne.trim_start_matches("ERROR: ").trim_start();
 
-            in vec3 directionRegard;
+    let mut chiffres = debut.splitn(2, [':', '(']);
+    chiffres.next()?.parse::<usize>().ok()?; // Numéro de la chaîne source, toujours 0 ici
 
-            const uint NBR_LUMIERES = 8;
+    let reste = chiffres.next()?;
+    let numero: std::string::String = reste.chars().take_while(|c| c.is_ascii_digit()).collect();
 
-            in Lumieres {
-                vec4 couleurs[NBR_LUMIERES];
-                vec3 directions[NBR_LUMIERES];
-                float distances[NBR_LUMIERES];
-            } lumieres;
+    numero.parse::<usize>().ok().filter(|numero| *numero > 0)
+}
 
-            out vec4 couleur;
-            
-            void main() {
+// Vérifie périodiquement la date de modification des fichiers
+struct Surveillance {
 
-                vec4 luminosite = vec4(0.0, 0.0, 0.0, 1.0);
+    programmes: std::vec::Vec<(Sources, [Option<std::time::SystemTime>; 2])>,
+    derniere_verification: std::time::Instant,
+}
 
-                const float INTENSITE_SPECULAIRE = 0.45;
-                const float INTENSITE_DIFFUSE = 0.45;
-                const float INTENSITE_AMBIANTE = 1.0 - (INTENSITE_DIFFUSE + INTENSITE_SPECULAIRE);
+impl Surveillance {
 
-                for(int i=0; i<NBR_LUMIERES; ++i) {
+    fn new(programmes: &[Sources]) -> Surveillance {
 
-                    vec3 direction_reflexion = reflect(-lumieres.directions[i], normal);
+        Surveillance {
 
-                    float lumiere_speculaire = INTENSITE_SPECULAIRE * pow(max(dot(directionRegard, direction_reflexion), 0.0), 12);
+            programmes: programmes.iter()
+                .map(|sources| (*sources, Surveillance::dates(*sources)))
+                .collect(),
+            derniere_verification: std::time::Instant::now(),
+        }
+    }
 
-                    float lumiere_diffuse = INTENSITE_DIFFUSE * max(dot(normal, lumieres.directions[i]), 0.0);
-                    
-                    const float FACTEUR_DIMINUTION = 10.0;
-                    float diminution = FACTEUR_DIMINUTION * (lumieres.distances[i] + lumieres.distances[i] * lumieres.distances[i]) + 1.0;
-                    vec4 luminosite2 = (lumiere_speculaire + lumiere_diffuse + INTENSITE_AMBIANTE) * lumieres.couleurs[i] / diminution;
-                    
-                    luminosite.x = max(luminosite.x, luminosite2.x);
-                    luminosite.y = max(luminosite.y, luminosite2.y);
-                    luminosite.z = max(luminosite.z, luminosite2.z);
-                }
+    fn sources_modifiees(&mut self) -> std::vec::Vec<Sources> {
+
+        // Inutile d'interroger le disque à chaque image
+        const INTERVALLE: std::time::Duration = std::time::Duration::from_millis(500);
+
+        if self.derniere_verification.elapsed() < INTERVALLE {
+            return std::vec::Vec::new();
+        }
+
+        self.derniere_verification = std::time::Instant::now();
 
-                couleur = texture(textures, coord_tex) * luminosite;
+        let mut modifiees = std::vec::Vec::new();
+
+        for (sources, dates) in self.programmes.iter_mut() {
+
+            let nouvelles_dates = Surveillance::dates(*sources);
+
+            if nouvelles_dates != *dates {
+
+                *dates = nouvelles_dates;
+                modifiees.push(*sources);
             }
-        "#)
+        }
+
+        modifiees
     }
-}
\ No newline at end of file
+
+    fn dates(sources: Sources) -> [Option<std::time::SystemTime>; 2] {
+
+        let date = |fichier: &str| {
+            std::fs::metadata(format!("{}{}", CHEMIN, fichier))
+                .and_then(|metadonnees| metadonnees.modified())
+                .ok()
+        };
+
+        [date(sources.0), date(sources.1)]
+    }
+}
+
+mod code_source
+{
+    // Copie de tous les shaders, intégrée à l'exécutable à la compilation
+
+    pub fn integre(fichier: &str) -> &'static str {
+
+        match fichier {
+
+            "prepasse.vert" => include_str!("../shaders/prepasse.vert"),
+            "prepasse.frag" => include_str!("../shaders/prepasse.frag"),
+            "scene.vert" => include_str!("../shaders/scene.vert"),
+            "scene.frag" => include_str!("../shaders/scene.frag"),
+            "particules.vert" => include_str!("../shaders/particules.vert"),
+            "particules.frag" => include_str!("../shaders/particules.frag"),
+            "ecran.vert" => include_str!("../shaders/ecran.vert"),
+            "extraction.frag" => include_str!("../shaders/extraction.frag"),
+            "flou.frag" => include_str!("../shaders/flou.frag"),
+            "composition.frag" => include_str!("../shaders/composition.frag"),
+            "copie.frag" => include_str!("../shaders/copie.frag"),
+            "fxaa.frag" => include_str!("../shaders/fxaa.frag"),
+            "resolution_profondeur.frag" => include_str!("../shaders/resolution_profondeur.frag"),
+            "carte.vert" => include_str!("../shaders/carte.vert"),
+            "carte.frag" => include_str!("../shaders/carte.frag"),
+            "texte.vert" => include_str!("../shaders/texte.vert"),
+            "texte.frag" => include_str!("../shaders/texte.frag"),
+            "lignes.vert" => include_str!("../shaders/lignes.vert"),
+
+            _ => panic!("Le shader {} n'existe pas", fichier),
+        }
+    }
+
+    // Lit le fichier dans le dossier des shaders, ou la copie intégrée s'il est absent
+    pub fn lire(fichier: &str) -> std::string::String {
+
+        match std::fs::read_to_string(format!("{}{}", super::CHEMIN, fichier)) {
+
+            Ok(code) => code,
+            Err(_) => integre(fichier).to_string(),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+extern crate nalgebra_glm as glm;
+
+use labyrinthe;
+use observateur;
+
+/*
+    Interface publique du module survol
+
+    Sert à parcourir automatiquement le chemin du départ jusqu'à la sortie, par exemple pour une démonstration
+    La caméra passe par le centre des cellules du chemin (voir labyrinthe::Labyrinthe::chemin_sortie),
+    le long d'une spline de Catmull-Rom, et regarde un peu plus loin sur le chemin pour anticiper les virages
+
+    Le survol peut être suivi dans la fenêtre, ou enregistré image par image hors de l'écran (--flythrough)
+*/
+
+pub struct Survol {
+
+    points: std::vec::Vec<glm::Vec3>, // Centres des cellules du chemin, à la hauteur de vol
+    longueur_segment: f32, // Distance entre deux centres voisins
+    parcouru: f32, // En segments: la partie entière donne le segment, le reste la progression dans celui-ci
+
+    position: glm::Vec3,
+    direction: glm::Vec3,
+
+    pub vitesse: f32, // En unités par seconde
+}
+
+impl Survol {
+
+    // Le chemin est toujours le même pour un labyrinthe, peu importe où se trouve l'observateur
+    pub fn new(labyrinthe: &labyrinthe::Labyrinthe, vitesse: f32) -> Survol {
+
+        const HAUTEUR_VOL: f32 = 1.2; // Au-dessus du sol, un peu sous les yeux de l'observateur debout
+
+        let points: std::vec::Vec<glm::Vec3> = labyrinthe.chemin_sortie().iter()
+            .map(|(x, z)| {
+                let centre = labyrinthe.position_monde(*x as f32 + 0.5, *z as f32 + 0.5);
+                centre + glm::Vec3::new(0.0, HAUTEUR_VOL, 0.0)
+            })
+            .collect();
+
+        let longueur_segment = if points.len() >= 2 { glm::distance(&points[0], &points[1]) } else { 1.0 };
+
+        let mut survol = Survol {
+            points,
+            longueur_segment,
+            parcouru: 0.0,
+
+            position: glm::Vec3::new(observateur::POSITION_DEPART[0], observateur::POSITION_DEPART[1], observateur::POSITION_DEPART[2]),
+            direction: glm::Vec3::new(0.0, 0.0, 1.0),
+
+            vitesse,
+        };
+
+        // Dès la première image, la caméra est sur le chemin et regarde devant
+        if !survol.points.is_empty() {
+            survol.position = survol.point(0.0);
+            survol.direction = survol.direction_voulue().unwrap_or(survol.direction);
+        }
+
+        survol
+    }
+
+    // duree: en secondes
+    pub fn avancer(&mut self, duree: f32) {
+
+        const VITESSE_ROTATION: f32 = 6.0; // Proportion de l'écart avec la direction voulue comblée par seconde, environ
+
+        if self.points.is_empty() {
+            return;
+        }
+
+        // Les centres des cellules voisines sont à égale distance, la vitesse est donc à peu près constante
+        self.parcouru = (self.parcouru + self.vitesse * duree / self.longueur_segment).min(self.fin());
+        self.position = self.point(self.parcouru);
+
+        if let Some(direction) = self.direction_voulue() {
+
+            let proportion = 1.0 - (-VITESSE_ROTATION * duree).exp();
+            let melange = glm::lerp(&self.direction, &direction, proportion);
+
+            if melange.norm() > 1e-3 {
+                self.direction = melange.normalize();
+            }
+        }
+    }
+
+    // La sortie est atteinte
+    pub fn termine(&self) -> bool {
+
+        self.parcouru >= self.fin()
+    }
+
+    pub fn position(&self) -> &glm::Vec3 {
+        &self.position
+    }
+
+    pub fn direction(&self) -> &glm::Vec3 {
+        &self.direction
+    }
+
+    // Nombre d'images nécessaires pour tout le survol, au rythme donné
+    pub fn nombre_images(&self, images_par_seconde: f32) -> u32 {
+
+        let duree = self.fin() * self.longueur_segment / self.vitesse;
+        (duree * images_par_seconde).ceil() as u32 + 1
+    }
+
+    fn fin(&self) -> f32 {
+
+        self.points.len().saturating_sub(1) as f32
+    }
+
+    // Spline de Catmull-Rom: passe par chaque point, avec une tangente donnée par ses voisins
+    fn point(&self, parcouru: f32) -> glm::Vec3 {
+
+        let dernier = self.points.len() as i32 - 1;
+        let segment = (parcouru.floor() as i32).clamp(0, (dernier - 1).max(0));
+        let t = (parcouru - segment as f32).clamp(0.0, 1.0);
+
+        // Aux extrémités, le point manquant est le point lui-même
+        let obtenir = |indice: i32| self.points[indice.clamp(0, dernier) as usize];
+
+        let (p0, p1, p2, p3) = (obtenir(segment - 1), obtenir(segment), obtenir(segment + 1), obtenir(segment + 2));
+
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        (p1 * 2.0 +
+            (p2 - p0) * t +
+            (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 +
+            (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+    }
+
+    // Vers un point un peu plus loin sur le chemin; None à la sortie, où la direction ne change plus
+    fn direction_voulue(&self) -> Option<glm::Vec3> {
+
+        const ANTICIPATION: f32 = 1.2; // En segments
+
+        let cible = self.point((self.parcouru + ANTICIPATION).min(self.fin()));
+        let ecart = cible - self.position;
+
+        if ecart.norm() > 1e-3 { Some(ecart.normalize()) } else { None }
+    }
+}
@@ -0,0 +1,186 @@
+extern crate image;
+use glium::{Surface}; // Surface est un trait et doit être importé
+
+/*
+    Interface publique du module texte
+
+    Sert à dessiner du texte à l'écran avec une police bitmap
+
+    L'image de la police contient les 256 caractères Latin-1 dans une grille de 16 × 16 cellules carrées
+    La police est à chasse fixe: chaque caractère avance de CHASSE fois la taille d'une cellule
+    Les autres caractères sont remplacés par «?»
+*/
+
+pub struct Police {
+
+    image: Option<image::RgbaImage>, // Envoyée à OpenGL au premier affichage
+    texture: Option<glium::texture::Texture2d>,
+}
+
+impl Police {
+
+    // Sans image, le texte n'est simplement pas affiché
+    pub fn charger() -> Police {
+
+        let image = match image::open(CHEMIN) {
+
+            Ok(image) => Some(image.to_rgba()),
+            Err(erreur) => {
+                println!("La police {} n'a pas pu être lue, le texte ne sera pas affiché: {}", CHEMIN, erreur);
+                None
+            }
+        };
+
+        Police {
+
+            image,
+            texture: None,
+        }
+    }
+
+    // Largeur en pixels d'une ligne de texte
+    pub fn largeur(texte: &str, taille: f32) -> f32 {
+
+        texte.chars().count() as f32 * CHASSE * taille
+    }
+
+    pub fn dessiner<S: Surface>(&mut self,
+                    surface: &mut S,
+                    texte: &Texte,
+                    programme: &glium::Program,
+                    affichage: &dyn glium::backend::Facade)
+    {
+        if texte.sommets.is_empty() {
+            return;
+        }
+
+        if let Some(image) = self.image.take() {
+
+            let dimensions = image.dimensions();
+            let image = glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions);
+
+            self.texture = Some(glium::texture::Texture2d::with_mipmaps(
+                affichage,
+                image,
+                glium::texture::MipmapsOption::NoMipmap).unwrap());
+        }
+
+        let texture = match self.texture {
+            Some(ref texture) => texture,
+            None => return,
+        };
+
+        let (largeur_ecran, hauteur_ecran) = surface.get_dimensions();
+
+        let donnees_globales = uniform! {
+            dimensions_ecran: [largeur_ecran as f32, hauteur_ecran as f32],
+            police: glium::uniforms::Sampler::new(texture)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear),
+        };
+
+        let parametres = glium::DrawParameters {
+            blend: glium::Blend::alpha_blending(),
+            .. Default::default()
+        };
+
+        surface.draw(
+            &glium::VertexBuffer::new(affichage, &texte.sommets).unwrap(),
+            glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+            programme,
+            &donnees_globales,
+            &parametres,
+        ).unwrap();
+    }
+}
+
+// Lignes de texte à dessiner en une seule fois
+pub struct Texte {
+
+    sommets: std::vec::Vec<SommetTexte>,
+}
+
+impl Texte {
+
+    pub fn new() -> Texte {
+
+        Texte {
+            sommets: std::vec::Vec::new(),
+        }
+    }
+
+    // position: coin inférieur gauche de la ligne, en pixels à partir du coin inférieur gauche de l'écran
+    // taille: hauteur d'une ligne, en pixels
+    // Une ombre est ajoutée sous le texte pour qu'il reste lisible sur les zones claires
+    pub fn ajouter(&mut self, texte: &str, position: (f32, f32), taille: f32, couleur: [f32; 4]) {
+
+        let decalage_ombre = (taille * 0.06).max(1.0);
+        let ombre = [0.0, 0.0, 0.0, couleur[3] * 0.8];
+
+        self.ajouter_ligne(texte, (position.0 + decalage_ombre, position.1 - decalage_ombre), taille, ombre);
+        self.ajouter_ligne(texte, position, taille, couleur);
+    }
+
+    fn ajouter_ligne(&mut self, texte: &str, position: (f32, f32), taille: f32, couleur: [f32; 4]) {
+
+        let avance = CHASSE * taille;
+
+        for (i, caractere) in texte.chars().enumerate() {
+
+            if caractere == ' ' {
+                continue;
+            }
+
+            let code = match caractere as u32 {
+                code @ 0x20..=0x7E | code @ 0xA0..=0xFF => code,
+                _ => '?' as u32,
+            };
+
+            // La cellule est centrée sur l'espace occupé par le caractère
+            let x = position.0 + i as f32 * avance - (taille - avance) / 2.0;
+            let y = position.1;
+
+            // L'image est envoyée à l'envers à OpenGL, la première rangée est donc en haut de la texture
+            let u = (code % 16) as f32 / 16.0;
+            let v = 1.0 - (code / 16 + 1) as f32 / 16.0;
+            let cote = 1.0 / 16.0;
+
+            let coins = [
+                ([x, y], [u, v]),
+                ([x + taille, y], [u + cote, v]),
+                ([x + taille, y + taille], [u + cote, v + cote]),
+                ([x, y + taille], [u, v + cote]),
+            ];
+
+            // Deux triangles par caractère
+            for index in [0, 1, 2, 0, 2, 3].iter() {
+
+                let (position, coordonnees) = coins[*index];
+                self.sommets.push(SommetTexte { position, coordonnees, couleur });
+            }
+        }
+    }
+}
+
+
+
+
+
+/*
+    Partie privée du module texte
+*/
+
+const CHEMIN: &str = "assets/police.png";
+
+// Avance d'un caractère par rapport à la taille d'une cellule, mesurée lors de la création de l'image
+const CHASSE: f32 = 0.45;
+
+// Primitive pour OpenGL
+#[derive(Copy, Clone)]
+struct SommetTexte {
+    position: [f32; 2], // En pixels
+    coordonnees: [f32; 2], // Dans l'image de la police
+    couleur: [f32; 4],
+}
+// Permet à Glium de l'utiliser avec OpenGL
+implement_vertex!(SommetTexte, position, coordonnees, couleur);
@@ -1,19 +1,21 @@
+extern crate nalgebra_glm as glm;
+
 /*
     Interface publique du module textures
 
     Sert à charger et à utiliser les textures
 */
 
-pub struct Textures<'a> {
+pub struct Textures {
 
-    images: std::vec::Vec<glium::texture::RawImage2d<'a, u8>>,
-    identifiants: std::collections::HashMap<String, f32>,
+    images: std::vec::Vec<image::RgbaImage>, // Conservées jusqu'à generer_textures
+    identifiants: std::collections::HashMap<String, u32>,
     textures: Option<glium::texture::texture2d_array::Texture2dArray>,
 }
 
-impl<'a> Textures<'a> {
+impl Textures {
 
-    pub fn new() -> Textures<'a> {
+    pub fn new() -> Textures {
 
         Textures {
 
@@ -23,51 +25,191 @@ impl<'a> Textures<'a> {
         }
     }
 
-    // Donne le numéro associé à l'identifiant
-    pub fn obtenir_id(&self, identifiant: &str) -> f32 {
+    // Donne la couche du tableau de textures associée à l'identifiant
+    pub fn obtenir_id(&self, identifiant: &str) -> u32 {
 
-        match self.identifiants.get(identifiant) {
+        match self.essayer_id(identifiant) {
 
-            Some(id) => return *id,
+            Some(id) => id,
 
-            None => panic!("L'identifiant pour la texture "),
+            None => panic!("L'identifiant pour la texture {} n'existe pas", identifiant),
         }
     }
 
-    // identifiant servira à obtenir l'index de la texture
-    pub fn charger_images(&mut self, noms_images: &[&str], extension: &str) {
+    // Comme obtenir_id, mais pour les images optionnelles
+    pub fn essayer_id(&self, identifiant: &str) -> Option<u32> {
+
+        self.identifiants.get(identifiant).cloned()
+    }
+
+    // Donne la couche de la carte de normales associée à l'identifiant d'une image
+    // Si aucune carte n'a été chargée, elle est générée à partir de l'image; relief contrôle sa profondeur
+    // Doit être appelée avant generer_textures
+    pub fn obtenir_normales(&mut self, identifiant: &str, relief: f32) -> u32 {
 
-        for i in 0..noms_images.len() {
+        let identifiant_normales = identifiant.to_owned() + SUFFIXE_NORMALES;
 
-            self.charger_image(&(noms_images[i].to_owned() + extension), noms_images[i]);
+        if let Some(id) = self.essayer_id(&identifiant_normales) {
+            return id;
         }
+
+        let couleur = &self.images[self.obtenir_id(identifiant) as usize];
+        let normales = generer_carte_normales(couleur, relief);
+
+        self.ajouter_image(normales, &identifiant_normales)
+    }
+
+    // Donne la couche de la carte de spécularité associée à l'identifiant d'une image, si elle a été chargée
+    pub fn obtenir_speculaire(&self, identifiant: &str) -> Option<u32> {
+
+        self.essayer_id(&(identifiant.to_owned() + SUFFIXE_SPECULAIRE))
+    }
+
+    // Donne le tableau de textures, s'il a été généré avec generer_textures
+    pub fn obtenir_textures(&self) -> Option<&glium::texture::texture2d_array::Texture2dArray> {
+
+        self.textures.as_ref()
     }
 
     // identifiant servira à obtenir l'index de la texture
-    fn charger_image(&mut self, nom_image_et_extension: &str, identifiant: &str) {
+    // Les cartes optionnelles «nom_normales» et «nom_speculaire» sont chargées si elles existent
+    pub fn charger_images(&mut self, noms_images: &[&str], extension: &str) {
 
-        const CHEMIN: &str = "images/";
+        for nom_image in noms_images.iter() {
 
-        let image = image::io::Reader::open(CHEMIN.to_owned() + nom_image_et_extension).unwrap().decode().unwrap().to_rgba();
-        let dimensions = image.dimensions();
-        let image = glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions);
+            let image = lire_image(&(nom_image.to_string() + extension))
+                .unwrap_or_else(|| panic!("L'image {}{} est introuvable", nom_image, extension));
+            self.ajouter_image(image, nom_image);
 
-        self.identifiants.insert(identifiant.to_string(), self.images.len() as f32);
-        self.images.push(image);
+            for suffixe in [SUFFIXE_NORMALES, SUFFIXE_SPECULAIRE].iter() {
+
+                let identifiant = nom_image.to_string() + suffixe;
+
+                if let Some(image) = lire_image(&(identifiant.clone() + extension)) {
+                    self.ajouter_image(image, &identifiant);
+                }
+            }
+        }
     }
 
     // Cette fonction devrait être appelée une fois après avoir chargé toutes les images
-    pub fn generer_textures(&mut self, affichage: &glium::Display) {
+    // Les mipmaps sont générés par OpenGL lors de l'envoi des images
+    pub fn generer_textures(&mut self, affichage: &dyn glium::backend::Facade) {
 
-        let mut vecteur: std::vec::Vec<glium::texture::RawImage2d<u8>> = std::vec::Vec::new();
+        let mut vecteur: std::vec::Vec<image::RgbaImage> = std::vec::Vec::new();
         std::mem::swap(&mut vecteur, &mut self.images);
 
-        let textures = glium::texture::texture2d_array::Texture2dArray::new(affichage, vecteur);
+        let vecteur: std::vec::Vec<glium::texture::RawImage2d<u8>> = vecteur.into_iter().map(|image| {
+            let dimensions = image.dimensions();
+            glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions)
+        }).collect();
+
+        let textures = glium::texture::texture2d_array::Texture2dArray::with_mipmaps(
+            affichage,
+            vecteur,
+            glium::texture::MipmapsOption::AutoGeneratedMipmaps);
         self.textures = Some(textures.unwrap());
     }
+
+    // identifiant servira à obtenir l'index de la texture
+    fn ajouter_image(&mut self, image: image::RgbaImage, identifiant: &str) -> u32 {
+
+        // Toutes les couches du tableau de textures doivent avoir les mêmes dimensions
+        let image = match self.images.first() {
+
+            Some(premiere_image) => ajuster_dimensions(image, premiere_image.dimensions()),
+            None => image,
+        };
+
+        let id = self.images.len() as u32;
+
+        self.identifiants.insert(identifiant.to_string(), id);
+        self.images.push(image);
+
+        id
+    }
+}
+
+// Remplace les textures manquantes par un damier magenta, facile à remarquer à l'écran
+pub fn generer_texture_manquante(affichage: &dyn glium::backend::Facade) -> glium::texture::texture2d_array::Texture2dArray {
+
+    const MAGENTA: (u8, u8, u8, u8) = (255, 0, 255, 255);
+    const NOIR: (u8, u8, u8, u8) = (0, 0, 0, 255);
+
+    let damier = vec![
+        vec![MAGENTA, NOIR],
+        vec![NOIR, MAGENTA],
+    ];
+
+    glium::texture::texture2d_array::Texture2dArray::with_mipmaps(
+        affichage,
+        vec![damier],
+        glium::texture::MipmapsOption::NoMipmap).unwrap()
 }
 
 /*
     Partie privée du module textures
 */
 
+const CHEMIN: &str = "images/";
+const SUFFIXE_NORMALES: &str = "_normales";
+const SUFFIXE_SPECULAIRE: &str = "_speculaire";
+
+// Donne None si l'image n'existe pas
+fn lire_image(nom_image_et_extension: &str) -> Option<image::RgbaImage> {
+
+    let lecteur = match image::io::Reader::open(CHEMIN.to_owned() + nom_image_et_extension) {
+
+        Ok(lecteur) => lecteur,
+        Err(_) => return None,
+    };
+
+    match lecteur.decode() {
+
+        Ok(image) => Some(image.to_rgba()),
+        Err(e) => panic!("L'image {} n'a pas pu être décodée: {}", nom_image_et_extension, e),
+    }
+}
+
+fn ajuster_dimensions(image: image::RgbaImage, dimensions: (u32, u32)) -> image::RgbaImage {
+
+    if image.dimensions() == dimensions {
+        return image;
+    }
+
+    image::imageops::resize(&image, dimensions.0, dimensions.1, image::FilterType::Triangle)
+}
+
+// Utilise la luminosité de l'image comme hauteur pour en déduire les normales
+fn generer_carte_normales(couleur: &image::RgbaImage, relief: f32) -> image::RgbaImage {
+
+    let (largeur, hauteur) = couleur.dimensions();
+
+    let hauteur_pixel = |x: i64, y: i64| -> f32 {
+
+        // La texture se répète, donc les voisins d'un bord sont de l'autre côté
+        let x = x.rem_euclid(largeur as i64) as u32;
+        let y = y.rem_euclid(hauteur as i64) as u32;
+        let pixel = couleur.get_pixel(x, y);
+
+        (0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32) / 255.0
+    };
+
+    image::RgbaImage::from_fn(largeur, hauteur, |x, y| {
+
+        let x = x as i64;
+        let y = y as i64;
+
+        // L'axe y de l'image pointe vers le bas, alors que celui de la texture pointe vers le haut
+        let pente_u = (hauteur_pixel(x + 1, y) - hauteur_pixel(x - 1, y)) * relief;
+        let pente_v = (hauteur_pixel(x, y - 1) - hauteur_pixel(x, y + 1)) * relief;
+
+        let normale = glm::normalize(&glm::Vec3::new(-pente_u, -pente_v, 1.0));
+
+        image::Rgba([
+            ((normale.x * 0.5 + 0.5) * 255.0) as u8,
+            ((normale.y * 0.5 + 0.5) * 255.0) as u8,
+            ((normale.z * 0.5 + 0.5) * 255.0) as u8,
+            255])
+    })
+}
@@ -0,0 +1,27 @@
+/*
+    Test du rejeu des événements
+
+    tests/references/parcours.txt a été enregistré avec --record: l'observateur marche, tourne, saute,
+    s'accroupit, vole et utilise la manette, avec des images de durées variées et une pause
+    Le rejeu (--replay --verify) doit redonner exactement la même trajectoire et la même direction du regard
+
+    Après un changement voulu du déplacement, l'enregistrement peut être mis à jour avec:
+    cargo run -- --replay tests/references/parcours.txt --record tests/references/parcours.txt.nouveau
+*/
+
+#[test]
+fn rejeu_reproduit_la_trajectoire() {
+
+    let chemin = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("references").join("parcours.txt");
+
+    let sortie = std::process::Command::new(env!("CARGO_BIN_EXE_labyrinthe"))
+        .arg("--replay").arg(&chemin)
+        .arg("--verify")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+
+    assert!(sortie.status.success(), "Le rejeu diffère de l'enregistrement:\n{}{}",
+        String::from_utf8_lossy(&sortie.stdout),
+        String::from_utf8_lossy(&sortie.stderr));
+}
@@ -0,0 +1,209 @@
+extern crate image;
+
+/*
+    Tests de régression du rendu
+
+    Chaque point de vue est dessiné hors de l'écran par le programme (--render-to), dans un labyrinthe
+    généré avec une graine fixe, puis comparé à une image de référence de tests/references/
+
+    Les images sont produites avec les vrais shaders; Mesa llvmpipe suffit, sans serveur graphique
+    Le test échoue si aucun contexte OpenGL n'est disponible
+    En cas d'échec, l'image obtenue et une image des différences sont écrites dans le dossier temporaire de cargo
+
+    Pour régénérer les références après un changement voulu du rendu:
+    LABYRINTHE_MAJ_REFERENCES=1 cargo test --test rendu_reference
+*/
+
+const GRAINE: &str = "7";
+const DIMENSIONS: &str = "320x180";
+
+// Nom de l'image de référence, puis caméra: x,y,z,lacet,tangage
+const POINTS_DE_VUE: [(&str, &str); 4] = [
+    ("couloir_torches", "0.5,1.2,0.5,0,0"),
+    ("plafond", "0.5,1.2,0.5,0,50"),
+    ("sol", "0.5,1.2,1.5,90,-45"),
+    ("mur_briques", "1.5,1.1,-1.5,-30,10"),
+];
+
+// Différence de couleur (ΔE dans l'espace CIELAB) à partir de laquelle un pixel est considéré différent
+// Une valeur d'environ 2.3 correspond à la plus petite différence perceptible
+const SEUIL_PIXEL: f32 = 4.0;
+
+// Proportion maximale de pixels différents pour qu'une image soit acceptée
+const PROPORTION_MAXIMALE: f32 = 0.005;
+
+#[test]
+fn rendu_correspond_aux_references() {
+
+    let dossier_references = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("references");
+    let dossier_sortie = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("rendu_reference");
+    std::fs::create_dir_all(&dossier_sortie).unwrap();
+
+    let mise_a_jour = std::env::var_os("LABYRINTHE_MAJ_REFERENCES").is_some();
+    let mut echecs = std::vec::Vec::new();
+
+    for (nom, camera) in POINTS_DE_VUE.iter() {
+
+        let chemin_obtenu = dossier_sortie.join(format!("{}.png", nom));
+        let chemin_reference = dossier_references.join(format!("{}.png", nom));
+
+        // Sans contexte OpenGL, par exemple sur une machine sans Mesa, rien n'est vérifié: c'est un échec
+        if let Err(erreur) = dessiner(&chemin_obtenu, camera, &dossier_references.join("configuration.txt")) {
+            panic!("Le point de vue {} n'a pas pu être dessiné:\n{}", nom, erreur);
+        }
+
+        let obtenue = image::open(&chemin_obtenu).unwrap().to_rgba();
+
+        if mise_a_jour {
+
+            obtenue.save(&chemin_reference).unwrap();
+            println!("Référence mise à jour: {}", chemin_reference.display());
+            continue;
+        }
+
+        let reference = match image::open(&chemin_reference) {
+
+            Ok(reference) => reference.to_rgba(),
+            Err(_) => {
+                echecs.push(format!(
+                    "{}: la référence {} est introuvable (LABYRINTHE_MAJ_REFERENCES=1 pour la créer)",
+                    nom, chemin_reference.display()));
+                continue;
+            }
+        };
+
+        if let Err(erreur) = comparer(&obtenue, &reference, &dossier_sortie.join(format!("{}_differences.png", nom))) {
+            echecs.push(format!("{}: {} (image obtenue: {})", nom, erreur, chemin_obtenu.display()));
+        }
+    }
+
+    if !echecs.is_empty() {
+        panic!("Le rendu ne correspond pas aux références:\n{}", echecs.join("\n"));
+    }
+}
+
+// Lance le programme pour dessiner une seule image
+fn dessiner(chemin: &std::path::Path, camera: &str, configuration: &std::path::Path) -> Result<(), String> {
+
+    let sortie = std::process::Command::new(env!("CARGO_BIN_EXE_labyrinthe"))
+        .arg("--render-to").arg(chemin)
+        .arg("--camera").arg(camera)
+        .arg("--size").arg(DIMENSIONS)
+        .arg("--seed").arg(GRAINE)
+        .arg("--config").arg(configuration)
+        .current_dir(env!("CARGO_MANIFEST_DIR")) // Les textures sont cherchées dans images/
+        .output()
+        .map_err(|erreur| erreur.to_string())?;
+
+    if !sortie.status.success() {
+        return Err(format!(
+            "{}{}",
+            String::from_utf8_lossy(&sortie.stdout),
+            String::from_utf8_lossy(&sortie.stderr)));
+    }
+
+    Ok(())
+}
+
+// Écrit une image des différences si la tolérance est dépassée
+fn comparer(obtenue: &image::RgbaImage, reference: &image::RgbaImage, chemin_differences: &std::path::Path) -> Result<(), String> {
+
+    if obtenue.dimensions() != reference.dimensions() {
+        return Err(format!("dimensions {:?} au lieu de {:?}", obtenue.dimensions(), reference.dimensions()));
+    }
+
+    let mut differences = image::RgbaImage::new(reference.width(), reference.height());
+    let mut pixels_differents = 0;
+    let mut ecart_maximal: f32 = 0.0;
+
+    for (x, y, pixel_reference) in reference.enumerate_pixels() {
+
+        let ecart = ecart_perceptuel(obtenue.get_pixel(x, y), pixel_reference);
+        ecart_maximal = ecart_maximal.max(ecart);
+
+        // La référence est affichée en gris pâle, et les pixels différents en rouge
+        let gris = (luminance(pixel_reference) * 0.3 * 255.0) as u8;
+
+        let couleur = if ecart > SEUIL_PIXEL {
+            pixels_differents += 1;
+            [255, gris, gris, 255]
+        }
+        else {
+            [gris, gris, gris, 255]
+        };
+
+        differences.put_pixel(x, y, image::Rgba(couleur));
+    }
+
+    let proportion = pixels_differents as f32 / (reference.width() * reference.height()) as f32;
+
+    if proportion > PROPORTION_MAXIMALE {
+
+        differences.save(chemin_differences).unwrap();
+
+        return Err(format!(
+            "{:.2}% des pixels diffèrent (maximum {:.2}%), écart maximal ΔE {:.1}, voir {}",
+            proportion * 100.0,
+            PROPORTION_MAXIMALE * 100.0,
+            ecart_maximal,
+            chemin_differences.display()));
+    }
+
+    Ok(())
+}
+
+// Distance euclidienne dans l'espace CIELAB (ΔE 1976)
+fn ecart_perceptuel(a: &image::Rgba<u8>, b: &image::Rgba<u8>) -> f32 {
+
+    let (l1, a1, b1) = vers_lab(a);
+    let (l2, a2, b2) = vers_lab(b);
+
+    ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+}
+
+fn luminance(pixel: &image::Rgba<u8>) -> f32 {
+
+    let [r, v, b] = vers_lineaire(pixel);
+    0.2126 * r + 0.7152 * v + 0.0722 * b
+}
+
+fn vers_lineaire(pixel: &image::Rgba<u8>) -> [f32; 3] {
+
+    let composante = |valeur: u8| -> f32 {
+
+        let valeur = valeur as f32 / 255.0;
+
+        if valeur <= 0.04045 {
+            valeur / 12.92
+        }
+        else {
+            ((valeur + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    [composante(pixel[0]), composante(pixel[1]), composante(pixel[2])]
+}
+
+// sRGB vers CIELAB, avec le point blanc D65
+fn vers_lab(pixel: &image::Rgba<u8>) -> (f32, f32, f32) {
+
+    let [r, v, b] = vers_lineaire(pixel);
+
+    let x = (0.4124 * r + 0.3576 * v + 0.1805 * b) / 0.9505;
+    let y = 0.2126 * r + 0.7152 * v + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * v + 0.9505 * b) / 1.089;
+
+    let f = |t: f32| -> f32 {
+
+        if t > 0.008856 {
+            t.cbrt()
+        }
+        else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}